@@ -0,0 +1,16 @@
+use std::process::Command;
+
+fn main() {
+    // the commit the binary was built from, shown in the about page for bug reports. source
+    // tarballs have no git history, so it's left unknown there
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map_or_else(|| "unknown".into(), |hash| hash.trim().to_string());
+    println!("cargo:rustc-env=GIT_HASH={hash}");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+}
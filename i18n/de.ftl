@@ -0,0 +1,300 @@
+# pages
+page-main = Push-to-Talk
+page-settings = Einstellungen
+page-diagnostics = Diagnose
+page-about = Über
+about-audio-server = Soundserver
+about-repository = Quellcode
+about-report-issue = Problem melden
+about-copy = Alles kopieren
+update-available = Version { $version } ist verfügbar
+
+# main page
+compact-mode = Kompaktmodus
+enable = Aktivieren
+exit = Beenden
+select-mic-hint = Wähle ein Mikrofon, um Push-to-Talk zu aktivieren
+virtual-mic-hint = Wähle „{ $device }“ in einer beliebigen Anwendung, um Push-to-Talk zu nutzen
+copy-virtual-mic-name = Quellnamen kopieren ({ $name })
+copied = „{ $text }“ kopiert
+microphone = Mikrofon
+choose-microphone = Mikrofon wählen...
+search-microphones = Mikrofone durchsuchen...
+talk-button = Zum Sprechen gedrückt halten
+talk-button-live = Sendet
+inert-no-microphone = Wähle zuerst ein Mikrofon
+inert-ptt-disabled = Aktiviere zuerst Push-to-Talk
+inert-already-default = Bereits auf dem Standardwert
+mic-muted = Stumm
+mic-transmitting = Sendet
+mic-typing = Stumm beim Tippen
+window-title = { $state ->
+    [live] Global Push-to-Talk — Live
+    [muted] Global Push-to-Talk — Stumm
+   *[disabled] Global Push-to-Talk — Deaktiviert
+}
+enable-ptt = Push-to-Talk aktivieren
+refresh-mics = Geräteliste aktualisieren
+device-source = Quelle: { $name }
+device-format = Format: { $spec }
+device-port = Anschluss: { $port }
+device-default = Systemstandard
+show-full-window = Ganzes Fenster anzeigen
+
+# settings page
+profile = Profil
+profile-name = Profilname
+save = Speichern
+cancel = Abbrechen
+new = Neu
+rename = Umbenennen
+delete = Löschen
+add = Hinzufügen
+show-hidden-devices = Ausgeblendete Geräte anzeigen
+hide = Ausblenden
+unhide = Einblenden
+timing = Zeitsteuerung
+auto-mute-after = Automatisch stummschalten nach
+max-hold-off = Aus
+max-hold-minutes = { $minutes } min
+hot-mic-warning-after = Erinnern, wenn offen seit
+reset = Zurücksetzen
+whisper-volume = Flüsterlautstärke
+ui-scale = Größe der Oberfläche
+colors = Farben
+color-transmitting = Sendet
+color-muted = Stumm
+color-disabled = Deaktiviert
+color-invalid = Keine Farbe, bitte #rrggbb verwenden
+when-disabled = Wenn deaktiviert
+theme = Design
+theme-system = Systemeinstellung folgen
+theme-light = Hell
+theme-dark = Dunkel
+theme-default = das Standarddesign
+theme-current = { $source ->
+    [system] Folgt dem System: { $theme }
+   *[setting] Verwendet { $theme }
+}
+resting-open = Mikrofon offen lassen
+resting-muted = Mikrofon stumm lassen
+on-close = Beim Schließen des Fensters
+close-background = Im Hintergrund weiterlaufen
+close-exit = Beenden
+on-lock = Beim Sperren des Bildschirms
+lock-restore = Stummschalten, beim Entsperren wiederherstellen
+lock-stay-muted = Stummschalten und stumm lassen
+lock-ignore = Nichts tun
+on-crash = Falls die App abstürzt
+fail-closed = Mikrofon stumm lassen
+fail-open = Mikrofon offen lassen
+background-reminder = Daran erinnern, dass es weiterläuft
+start-at-login = Bei der Anmeldung starten
+attention-on-change = Fenster hervorheben, wenn anderswo umgeschaltet
+notify-on-toggle = Benachrichtigen, wenn per Tastenkürzel umgeschaltet
+sound-on-unmute = Ton beim Öffnen des Mikrofons
+sound-on-mute = beim Schließen
+announce = Für Screenreader ansagen
+announce-live = Mikrofon aktiv
+announce-muted = Mikrofon stumm
+announce-disabled = Push-to-Talk deaktiviert
+osd-on-toggle = Bildschirmanzeige beim Umschalten
+osd-on-press = und bei jedem Drücken
+osd-live = Live
+osd-muted = Stumm
+osd-disabled = Push-to-Talk deaktiviert
+headset-button = Stummschalttaste des Headsets schaltet das Mikrofon um
+headset-any = Jedes Headset
+headset-none = Kein Headset mit Stummschalttaste gefunden
+headset-found = { $count ->
+    [one] 1 Headset gefunden, die Stummschalttaste hält das Mikrofon offen oder geschlossen
+   *[other] { $count } Headsets gefunden, ihre Stummschalttasten halten das Mikrofon offen oder geschlossen
+}
+headset-denied = { $count } Eingabegeräte können nicht gelesen werden. Tritt der Gruppe input bei oder gib deinem Benutzer per udev-Regel Zugriff auf das Headset
+activity-history = Verlauf führen, wann das Mikrofon offen war
+activity-log = Auch in activity.log speichern
+remote-control = Fernsteuerung über das Netzwerk erlauben
+remote-control-address = Lauscht auf { $address }, siehe remote_address und remote_port in der Konfigurationsdatei
+remote-control-no-token = Lege zuerst remote_token in der Konfigurationsdatei fest, Anfragen ohne werden abgelehnt
+obs-integration = In OBS zusammen mit dem Mikrofon stummschalten
+obs-connecting = Verbinde mit OBS…
+obs-connected = Mit OBS verbunden
+obs-disconnected = Nicht mit OBS verbunden: { $error }
+obs-no-input = Lege zuerst obs_input in der Konfigurationsdatei auf die Audioquelle in OBS fest
+mqtt = Zustand über MQTT veröffentlichen
+pause-media = Mediaplayer während des Sendens pausieren
+suppress-typing = Offenes Mikrofon beim Tippen stummschalten
+watched-apps = Push-to-Talk aktivieren, wenn diese Anwendungen starten
+watched-app-placeholder = Programm- oder Anwendungsname, z. B. Discord
+watched-app-running = { $app } (läuft)
+disable-with-watched-apps = Wieder deaktivieren, wenn die letzte beendet wird
+mqtt-connecting = Verbinde mit dem MQTT-Broker…
+mqtt-connected = Mit dem MQTT-Broker verbunden
+mqtt-disconnected = Nicht mit dem MQTT-Broker verbunden: { $error }
+check-for-updates = Einmal täglich auf GitHub nach neuen Versionen suchen
+save-stats = Tägliche Sprechzeit-Statistik speichern
+builtin-tray-icons = Eingebaute Symbole im Infobereich verwenden
+tray-label-setting = Zustand als Text neben dem Symbol im Infobereich zeigen
+auto-enable = Push-to-Talk nach der Wahl eines Mikrofons automatisch aktivieren
+overlay = Beim Senden eine Einblendung anzeigen
+overlay-corner = Position der Einblendung
+overlay-show = Einblendung anzeigen
+overlay-show-transmitting = Nur beim Senden
+overlay-show-active = Immer, wenn Push-to-Talk aktiviert ist, stumm abgedunkelt
+overlay-output = Bildschirm
+overlay-output-any = Beliebig
+overlay-margin = Abstand zum Rand
+overlay-scale = Größe der Einblendung
+overlay-opacity = Deckkraft der Einblendung
+corner-top-left = Oben links
+corner-top-right = Oben rechts
+corner-bottom-left = Unten links
+corner-bottom-right = Unten rechts
+
+# hotkeys
+hotkey-trigger = Auslöser
+hotkey-toggle-active = Aktivieren/Deaktivieren
+hotkey-whisper = Flüstern
+hotkey-next-profile = Nächstes Profil
+hotkey-unbound = Nicht belegt
+hotkey-test = Testen
+hotkey-test-progress = { $events ->
+    [one] 1 Ereignis
+   *[other] { $events } Ereignisse
+}, { $seconds } s
+hotkeys-configured-by-system = Unter Wayland verwaltet der Compositor die Tastenkürzel, ändere sie in den Systemeinstellungen
+hotkeys = Tastenkürzel
+trigger-keyboard = Auslöser nur von
+keyboard-any = Jeder Tastatur
+keyboard-denied = { $count } Eingabegeräte können nicht gelesen werden, daher lässt sich der Auslöser auf keines davon beschränken. Tritt der Gruppe input bei
+edit = Bearbeiten
+hotkeys-registered = Registriert
+hotkeys-starting = Wird registriert…
+hotkeys-failed = Nicht registriert: { $error }
+hotkey-recording = Tastenkombination drücken, Escape zum Abbrechen
+hotkey-captured = Neue Kombination für { $action }:
+hotkey-use = Übernehmen
+hotkey-retry = Erneut versuchen
+hotkey-invalid = Diese Kombination kann nicht verwendet werden: { $error }
+hotkey-duplicate = Bereits für { $action } verwendet
+portal-trigger = Push-to-Talk-Auslöser/Mikrofon einschalten
+portal-toggle-active = Push-to-Talk aktivieren/deaktivieren
+portal-whisper = Flüstern/Mikrofon mit geringerer Lautstärke einschalten
+portal-next-profile = Zum nächsten Profil wechseln
+
+# diagnostics page
+diagnostics-hotkeys = Tastenkürzel
+diagnostics-hotkeys-portal = GlobalShortcuts-Portal (Wayland)
+diagnostics-hotkeys-x11 = X11-Tastaturgriffe
+diagnostics-hotkeys-portal-impl = GlobalShortcuts-Portal ({ $implementation })
+diagnostics-hotkeys-evdev = { $backend }, dazu aus /dev/input gelesene Tastaturen: { $keyboards }
+diagnostics-hotkeys-evdev-none = { $backend }, dazu /dev/input, wo keine Tastatur lesbar ist
+diagnostics-hotkeys-evdev-unscanned = { $backend }, dazu aus /dev/input gelesene Tastaturen
+diagnostics-hotkeys-failed = Start fehlgeschlagen
+diagnostics-hotkeys-starting = Wird gestartet...
+diagnostics-session = Sitzung
+diagnostics-talk-session = Sprechzeit in dieser Sitzung
+diagnostics-talk-today = Sprechzeit heute
+diagnostics-not-saved = Nicht gespeichert
+talk-time = { $time } ({ $activations ->
+    [one] 1 Mal
+   *[other] { $activations } Mal
+})
+reset-stats = Statistik zurücksetzen
+activity = Mikrofonaktivität
+activity-opened = Geöffnet
+activity-closed = Geschlossen
+activity-duration = Dauer
+activity-cause = Geöffnet / geschlossen durch
+activity-still-open = Noch offen
+activity-clear = Verlauf löschen
+activity-export = Exportieren…
+activity-export-title = Mikrofonaktivität exportieren
+activity-exported = Aktivität nach { $path } exportiert
+activity-export-failed = Aktivität konnte nicht exportiert werden: { $error }
+cause-hotkey = Tastenkürzel
+cause-tray = Tray
+cause-window = Fenster
+cause-headset = Headset
+cause-command = Befehl
+cause-lock = Bildschirm gesperrt
+cause-auto = Automatisch
+events = Letzte Ereignisse
+events-filter = Ereignisse filtern...
+session-x11 = X11
+session-wayland = Wayland
+session-wayland-xwayland = Wayland mit XWayland
+diagnostics-virtual-mic = Virtuelles Mikrofon
+diagnostics-source-mic = Quellmikrofon
+diagnostics-config-file = Konfigurationsdatei
+diagnostics-sandbox = Sandbox
+diagnostics-flatpak = Flatpak ({ $id })
+diagnostics-none = Keines
+diagnostics-unknown = Unbekannt
+
+# errors
+error = Fehler
+retry = Erneut versuchen
+overwrite = Überschreiben
+virtual-mic-missing = Das virtuelle Mikrofon ist verschwunden, Anwendungen hören darüber nur Stille.
+virtual-mic-recreated = Das virtuelle Mikrofon war verschwunden und wurde neu erstellt.
+recreate = Neu erstellen
+recreate-virtual-mic = Das virtuelle Mikrofon automatisch neu erstellen, wenn es verschwindet
+config-unreadable = Die Konfigurationsdatei konnte nicht gelesen werden. Es werden Standardwerte verwendet und Änderungen nicht gespeichert. { $error }
+shortcuts-failed = Globale Tastenkürzel konnten nicht geladen werden. Tastenkürzel funktionieren nicht. Stelle sicher, dass du einen Wayland-Compositor mit einer Portal-Implementierung verwendest, die globale Tastenkürzel unterstützt.
+audio-unavailable = Keine Verbindung zum Soundserver möglich: { $error }
+hotkeys-unavailable = { $error } Das Mikrofon kann weiterhin über dieses Fenster oder das Tray-Symbol eingeschaltet werden.
+tray-missing = Kein Systemtray gefunden. Push-to-Talk läuft nach dem Schließen dieses Fensters weiter, starte es erneut, um das Fenster zurückzuholen.
+whisper-volume-failed = Flüsterlautstärke konnte nicht gesetzt werden: { $error }
+mute-failed = Stummschaltung fehlgeschlagen: { $error }
+save-failed = Einstellungen konnten nicht gespeichert werden: { $error }
+hotkey-register-failed = { $hotkey } konnte nicht registriert werden: { $error }
+
+# notifications
+config-load-failed = Konfiguration konnte nicht geladen werden, Standardwerte werden verwendet
+config-reload-failed = Konfiguration konnte nicht neu geladen werden
+autostart-failed = Autostart konnte nicht geändert werden
+profile-change-failed = Profil konnte nicht geändert werden
+profile-switched = Zum Profil „{ $name }“ gewechselt
+auto-closed = Mikrofon nach { $minutes ->
+    [one] 1 Minute
+   *[other] { $minutes } Minuten
+} automatisch geschlossen — das Loslassen der Taste ging verloren
+auto-enabled = Push-to-Talk aktiviert
+auto-enabled-body = Dein Mikrofon ist jetzt stumm, bis du das Auslöser-Tastenkürzel gedrückt hältst. Das lässt sich in den Einstellungen abschalten.
+mic-not-found = Mikrofon nicht gefunden
+mic-not-found-body = Kein Mikrofon passt zu „{ $query }“. Wähle stattdessen eines im Fenster aus.
+mic-ambiguous-body = Mehrere Mikrofone passen zu „{ $query }“: { $matches }. Gib mehr an oder wähle eines im Fenster aus.
+running-in-background = Global Push-to-Talk läuft im Hintergrund weiter
+dont-show-again = Nicht mehr anzeigen
+ptt-enabled = Push-to-Talk aktiviert
+ptt-disabled = Push-to-Talk deaktiviert
+disable = Deaktivieren
+open-window = Fenster öffnen
+hot-mic = Dein Mikrofon ist seit { $minutes ->
+    [one] 1 Minute
+   *[other] { $minutes } Minuten
+} offen
+
+# tray
+tray-enable = Push-to-Talk aktivieren
+tray-mic-open = Mikrofon offen
+tray-unavailable = Push-to-Talk ist nicht verfügbar
+tray-hold-to-talk = { $hotkey } gedrückt halten, um zu sprechen
+tray-no-trigger = Kein Auslöser-Tastenkürzel belegt
+tray-no-mic = Kein Mikrofon gewählt
+tray-mic = Mikrofon: { $mic }
+tray-muted-while-disabled = Mikrofon ist stumm, solange deaktiviert
+tray-open-while-disabled = Mikrofon ist offen, solange deaktiviert
+tray-open-for = Offen seit { $time }
+tray-state = { $state ->
+    [transmitting] Global Push-to-Talk — sendet
+    [muted] Global Push-to-Talk — stumm
+    [error] Global Push-to-Talk — Fehler
+   *[disabled] Global Push-to-Talk — deaktiviert
+}
+tray-label = { $state ->
+    [transmitting] LIVE
+    [muted] AN
+   *[disabled] AUS
+}
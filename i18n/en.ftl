@@ -0,0 +1,300 @@
+# pages
+page-main = Push-to-Talk
+page-settings = Settings
+page-diagnostics = Diagnostics
+page-about = About
+about-audio-server = Audio server
+about-repository = Source code
+about-report-issue = Report an issue
+about-copy = Copy all
+update-available = Version { $version } is available
+
+# main page
+compact-mode = Compact mode
+enable = Enable
+exit = Exit
+select-mic-hint = Select a microphone to enable push-to-talk
+virtual-mic-hint = Select "{ $device }" in any application to use push-to-talk
+copy-virtual-mic-name = Copy the source name ({ $name })
+copied = Copied "{ $text }"
+microphone = Microphone
+choose-microphone = Choose Microphone...
+search-microphones = Search microphones...
+talk-button = Hold to talk
+talk-button-live = Transmitting
+inert-no-microphone = Select a microphone first
+inert-ptt-disabled = Enable push-to-talk first
+inert-already-default = Already at the default
+mic-muted = Muted
+mic-transmitting = Transmitting
+mic-typing = Muted while typing
+window-title = { $state ->
+    [live] Global Push-to-Talk — Live
+    [muted] Global Push-to-Talk — Muted
+   *[disabled] Global Push-to-Talk — Disabled
+}
+enable-ptt = Enable push-to-talk
+refresh-mics = Refresh device list
+device-source = Source: { $name }
+device-format = Format: { $spec }
+device-port = Port: { $port }
+device-default = System default
+show-full-window = Show the full window
+
+# settings page
+profile = Profile
+profile-name = Profile name
+save = Save
+cancel = Cancel
+new = New
+rename = Rename
+delete = Delete
+add = Add
+show-hidden-devices = Show hidden devices
+hide = Hide
+unhide = Unhide
+timing = Timing
+auto-mute-after = Auto-mute when held for
+max-hold-off = Off
+max-hold-minutes = { $minutes } min
+hot-mic-warning-after = Remind me when open for
+reset = Reset
+whisper-volume = Whisper volume
+ui-scale = Interface size
+colors = Colors
+color-transmitting = Transmitting
+color-muted = Muted
+color-disabled = Disabled
+color-invalid = Not a color, use #rrggbb
+when-disabled = When disabled
+theme = Theme
+theme-system = Follow system
+theme-light = Light
+theme-dark = Dark
+theme-default = the default theme
+theme-current = { $source ->
+    [system] Following the system: { $theme }
+   *[setting] Using { $theme }
+}
+resting-open = Keep microphone open
+resting-muted = Keep microphone muted
+on-close = When closing the window
+close-background = Keep running in the background
+close-exit = Exit
+on-lock = When the screen locks
+lock-restore = Mute, then restore on unlock
+lock-stay-muted = Mute and stay muted
+lock-ignore = Do nothing
+on-crash = If the app crashes
+fail-closed = Leave the microphone muted
+fail-open = Leave the microphone open
+background-reminder = Remind me that it's still running
+start-at-login = Start at login
+attention-on-change = Flag the window when toggled from elsewhere
+notify-on-toggle = Notify when toggled with a hotkey
+sound-on-unmute = Sound when the microphone opens
+sound-on-mute = when it closes
+announce = Announce for screen readers
+announce-live = Microphone live
+announce-muted = Microphone muted
+announce-disabled = Push-to-talk disabled
+osd-on-toggle = On-screen display when toggled
+osd-on-press = and on every press
+osd-live = Live
+osd-muted = Muted
+osd-disabled = Push-to-talk disabled
+headset-button = Headset mute button toggles the microphone
+headset-any = Any headset
+headset-none = No headset with a mute button found
+headset-found = { $count ->
+    [one] Found 1 headset, the mute button latches the microphone open or closed
+   *[other] Found { $count } headsets, their mute buttons latch the microphone open or closed
+}
+headset-denied = { $count } input devices can't be read. Add yourself to the input group, or give your user access to the headset with a udev rule
+activity-history = Keep a history of when the microphone was open
+activity-log = Also save it to activity.log
+remote-control = Allow remote control over the network
+remote-control-address = Listening on { $address }, see remote_address and remote_port in the config file
+remote-control-no-token = Set remote_token in the config file first, requests without it are refused
+obs-integration = Mute in OBS along with the microphone
+obs-connecting = Connecting to OBS…
+obs-connected = Connected to OBS
+obs-disconnected = Not connected to OBS: { $error }
+obs-no-input = Set obs_input in the config file to the OBS audio source to mute first
+mqtt = Publish the state over MQTT
+pause-media = Pause media players while transmitting
+suppress-typing = Mute the open microphone while typing
+watched-apps = Enable push-to-talk when these applications start
+watched-app-placeholder = Binary or application name, e.g. Discord
+watched-app-running = { $app } (running)
+disable-with-watched-apps = Disable again when the last one quits
+mqtt-connecting = Connecting to the MQTT broker…
+mqtt-connected = Connected to the MQTT broker
+mqtt-disconnected = Not connected to the MQTT broker: { $error }
+check-for-updates = Check GitHub for new versions once a day
+save-stats = Keep daily talk time statistics
+builtin-tray-icons = Use built-in tray icons
+tray-label-setting = Show the state as text next to the tray icon
+auto-enable = Enable push-to-talk automatically after selecting a microphone
+overlay = Show an overlay while transmitting
+overlay-corner = Overlay position
+overlay-show = Show the overlay
+overlay-show-transmitting = Only while transmitting
+overlay-show-active = Always while push-to-talk is enabled, dimmed while muted
+overlay-output = Monitor
+overlay-output-any = Any
+overlay-margin = Distance from the edges
+overlay-scale = Overlay size
+overlay-opacity = Overlay opacity
+corner-top-left = Top left
+corner-top-right = Top right
+corner-bottom-left = Bottom left
+corner-bottom-right = Bottom right
+
+# hotkeys
+hotkey-trigger = Trigger
+hotkey-toggle-active = Enable/Disable
+hotkey-whisper = Whisper
+hotkey-next-profile = Next Profile
+hotkey-unbound = Unbound
+hotkey-test = Test
+hotkey-test-progress = { $events ->
+    [one] 1 event
+   *[other] { $events } events
+}, { $seconds }s
+hotkeys-configured-by-system = Hotkeys are managed by your compositor on Wayland, change them in your system settings
+hotkeys = Hotkeys
+trigger-keyboard = Trigger only from
+keyboard-any = Any keyboard
+keyboard-denied = { $count } input devices can't be read, so the trigger can't be kept to one of them. Add yourself to the input group
+edit = Edit
+hotkeys-registered = Registered
+hotkeys-starting = Registering…
+hotkeys-failed = Not registered: { $error }
+hotkey-recording = Press a key combination, Escape to cancel
+hotkey-captured = New combination for { $action }:
+hotkey-use = Use this
+hotkey-retry = Try again
+hotkey-invalid = This combination can't be used: { $error }
+hotkey-duplicate = Already used for { $action }
+portal-trigger = Push-to-talk trigger/unmute microphone
+portal-toggle-active = Enable/disable push-to-talk
+portal-whisper = Whisper/unmute microphone at a lower volume
+portal-next-profile = Switch to the next profile
+
+# diagnostics page
+diagnostics-hotkeys = Hotkeys
+diagnostics-hotkeys-portal = GlobalShortcuts portal (Wayland)
+diagnostics-hotkeys-x11 = X11 key grabs
+diagnostics-hotkeys-portal-impl = GlobalShortcuts portal ({ $implementation })
+diagnostics-hotkeys-evdev = { $backend }, plus keyboards read from /dev/input: { $keyboards }
+diagnostics-hotkeys-evdev-none = { $backend }, plus reading /dev/input, where no keyboard can be read
+diagnostics-hotkeys-evdev-unscanned = { $backend }, plus keyboards read from /dev/input
+diagnostics-hotkeys-failed = Failed to start
+diagnostics-hotkeys-starting = Starting...
+diagnostics-session = Session
+diagnostics-talk-session = Talk time this session
+diagnostics-talk-today = Talk time today
+diagnostics-not-saved = Not saved
+talk-time = { $time } ({ $activations ->
+    [one] 1 time
+   *[other] { $activations } times
+})
+reset-stats = Reset statistics
+activity = Microphone activity
+activity-opened = Opened
+activity-closed = Closed
+activity-duration = Duration
+activity-cause = Opened / closed by
+activity-still-open = Still open
+activity-clear = Clear history
+activity-export = Export…
+activity-export-title = Export microphone activity
+activity-exported = Exported the activity to { $path }
+activity-export-failed = Couldn't export the activity: { $error }
+cause-hotkey = Hotkey
+cause-tray = Tray
+cause-window = Window
+cause-headset = Headset
+cause-command = Command
+cause-lock = Screen locked
+cause-auto = Automatic
+events = Recent events
+events-filter = Filter events...
+session-x11 = X11
+session-wayland = Wayland
+session-wayland-xwayland = Wayland with XWayland
+diagnostics-virtual-mic = Virtual microphone
+diagnostics-source-mic = Source microphone
+diagnostics-config-file = Config file
+diagnostics-sandbox = Sandbox
+diagnostics-flatpak = Flatpak ({ $id })
+diagnostics-none = None
+diagnostics-unknown = Unknown
+
+# errors
+error = Error
+retry = Retry
+overwrite = Overwrite
+virtual-mic-missing = The virtual microphone disappeared, so applications using it only hear silence.
+virtual-mic-recreated = The virtual microphone disappeared and was created again.
+recreate = Recreate
+recreate-virtual-mic = Recreate the virtual microphone automatically when it disappears
+config-unreadable = The config file could not be read, so defaults are used and changes won't be saved. { $error }
+shortcuts-failed = Failed to load global shortcuts. Hotkeys will not work. Make sure you are using a Wayland compositor with a portal implementation that supports global shortcuts.
+audio-unavailable = Could not connect to the audio server: { $error }
+hotkeys-unavailable = { $error } You can still open the microphone from this window or the tray.
+tray-missing = No system tray was found. Push-to-talk keeps running when this window is closed, start it again to bring the window back.
+whisper-volume-failed = Failed to set whisper volume: { $error }
+mute-failed = Failed to set mute: { $error }
+save-failed = Failed to save settings: { $error }
+hotkey-register-failed = Failed to register { $hotkey }: { $error }
+
+# notifications
+config-load-failed = Failed to load config, using defaults
+config-reload-failed = Failed to reload config
+autostart-failed = Failed to change autostart
+profile-change-failed = Failed to change profile
+profile-switched = Switched to profile "{ $name }"
+auto-closed = Microphone auto-closed after { $minutes ->
+    [one] 1 minute
+   *[other] { $minutes } minutes
+} — release event was lost
+auto-enabled = Push-to-talk enabled
+auto-enabled-body = Your microphone is now muted until you hold the trigger hotkey. This can be turned off in the settings.
+mic-not-found = Microphone not found
+mic-not-found-body = No microphone matches “{ $query }”. Pick one in the window instead.
+mic-ambiguous-body = Several microphones match “{ $query }”: { $matches }. Be more specific, or pick one in the window.
+running-in-background = Global Push-to-Talk is running in the background
+dont-show-again = Don't show again
+ptt-enabled = Push-to-talk enabled
+ptt-disabled = Push-to-talk disabled
+disable = Disable
+open-window = Open window
+hot-mic = Your microphone has been live for { $minutes ->
+    [one] 1 minute
+   *[other] { $minutes } minutes
+}
+
+# tray
+tray-enable = Enable Push-to-Talk
+tray-mic-open = Microphone open
+tray-unavailable = Push-to-talk is unavailable
+tray-hold-to-talk = Hold { $hotkey } to talk
+tray-no-trigger = No trigger hotkey bound
+tray-no-mic = No microphone selected
+tray-mic = Mic: { $mic }
+tray-muted-while-disabled = Microphone muted while disabled
+tray-open-while-disabled = Microphone open while disabled
+tray-open-for = Open for { $time }
+tray-state = { $state ->
+    [transmitting] Global Push-to-Talk — transmitting
+    [muted] Global Push-to-Talk — muted
+    [error] Global Push-to-Talk — error
+   *[disabled] Global Push-to-Talk — disabled
+}
+tray-label = { $state ->
+    [transmitting] LIVE
+    [muted] ON
+   *[disabled] OFF
+}
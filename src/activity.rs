@@ -0,0 +1,292 @@
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    fmt::Display,
+    fs::OpenOptions,
+    io::{self, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use ashpd::desktop::{ResponseError, file_chooser::SelectedFiles};
+use chrono::{DateTime, Local};
+
+use crate::{
+    config::Config,
+    events::{self, Level},
+    i18n::tr,
+    instance,
+};
+
+// older sessions are dropped once there are this many
+const CAPACITY: usize = 500;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("file chooser portal request failed: {0}")]
+    Portal(#[from] ashpd::Error),
+    #[error("the chosen location is not a local file")]
+    NotAFile,
+    #[error("failed to write the file: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// What opened or closed the microphone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cause {
+    HotKey,
+    Tray,
+    Window,
+    // the mute button of a headset
+    Headset,
+    // from another program, through `global-ptt <command>` or D-Bus
+    Command,
+    // the screen was locked or unlocked
+    Lock,
+    // timeouts, switching push-to-talk on or off and the like
+    Auto,
+}
+
+impl Cause {
+    /// An untranslated name, for files.
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::HotKey => "hotkey",
+            Self::Tray => "tray",
+            Self::Window => "window",
+            Self::Headset => "headset",
+            Self::Command => "command",
+            Self::Lock => "locked",
+            Self::Auto => "auto",
+        }
+    }
+}
+
+impl Display for Cause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&match self {
+            Self::HotKey => tr!("cause-hotkey"),
+            Self::Tray => tr!("cause-tray"),
+            Self::Window => tr!("cause-window"),
+            Self::Headset => tr!("cause-headset"),
+            Self::Command => tr!("cause-command"),
+            Self::Lock => tr!("cause-lock"),
+            Self::Auto => tr!("cause-auto"),
+        })
+    }
+}
+
+/// One stretch of time with the microphone open.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub opened: DateTime<Local>,
+    pub opened_by: Cause,
+    // unset while the mic is still open
+    pub closed: Option<(DateTime<Local>, Cause)>,
+}
+
+impl Session {
+    pub fn duration(&self) -> Option<Duration> {
+        self.closed
+            .and_then(|(closed, _)| (closed - self.opened).to_std().ok())
+    }
+}
+
+/// The most recent sessions of this run, oldest first.
+#[derive(Debug, Default, Clone)]
+pub struct History {
+    sessions: VecDeque<Session>,
+}
+
+impl History {
+    pub fn opened(&mut self, cause: Cause) {
+        if self.sessions.len() == CAPACITY {
+            self.sessions.pop_front();
+        }
+        self.sessions.push_back(Session {
+            opened: Local::now(),
+            opened_by: cause,
+            closed: None,
+        });
+    }
+
+    /// Closes the last session, returning it unless there was none open.
+    pub fn closed(&mut self, cause: Cause) -> Option<&Session> {
+        let session = self.sessions.back_mut().filter(|s| s.closed.is_none())?;
+        session.closed = Some((Local::now(), cause));
+        Some(session)
+    }
+
+    pub fn sessions(&self) -> impl DoubleEndedIterator<Item = &Session> {
+        self.sessions.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.sessions.clear();
+    }
+}
+
+// activity.log (activity-<id>.log for other instances), next to the config file
+fn path() -> Option<PathBuf> {
+    Config::path().map(|path| path.with_file_name(instance::file_name("activity.log")))
+}
+
+/// Appends a closed session to the log on disk, one tab separated line each.
+pub fn log(session: &Session) {
+    let (Some((closed, closed_by)), Some(duration)) = (session.closed, session.duration()) else {
+        return;
+    };
+    let line = format!(
+        "{}\t{}\t{}\t{}\t{}\n",
+        session.opened.to_rfc3339(),
+        closed.to_rfc3339(),
+        duration.as_secs(),
+        session.opened_by.id(),
+        closed_by.id()
+    );
+    if let Err(e) = append(&line) {
+        events::record(
+            Level::Error,
+            format!("Failed to write the activity log: {e}"),
+        );
+    }
+}
+
+fn append(line: &str) -> io::Result<()> {
+    let path = path().ok_or_else(|| io::Error::other("could not find the config directory"))?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+/// Removes the log on disk.
+pub fn clear_log() {
+    let Some(path) = path() else {
+        return;
+    };
+    if let Err(e) = std::fs::remove_file(&path)
+        && e.kind() != io::ErrorKind::NotFound
+    {
+        events::record(
+            Level::Error,
+            format!("Failed to remove {}: {e}", path.display()),
+        );
+    }
+}
+
+/// The sessions as CSV, with a header. Times are ISO 8601, sessions that are still open have no
+/// closing time or duration.
+pub fn to_csv<'a>(sessions: impl Iterator<Item = &'a Session>) -> String {
+    let mut csv = String::from("opened,closed,duration_seconds,opened_by,closed_by\n");
+    for session in sessions {
+        let (closed, closed_by) = session
+            .closed
+            .map(|(closed, closed_by)| (closed.to_rfc3339(), closed_by.id()))
+            .unwrap_or_default();
+        let duration = session
+            .duration()
+            .map(|d| d.as_secs().to_string())
+            .unwrap_or_default();
+        let fields = [
+            session.opened.to_rfc3339(),
+            closed,
+            duration,
+            session.opened_by.id().to_string(),
+            closed_by.to_string(),
+        ];
+        let fields: Vec<_> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+// quotes fields that would otherwise break the row apart, doubling any quotes inside
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Asks where to save `csv` and writes it there. Returns the path, or nothing when the dialog was
+/// cancelled.
+pub async fn export(csv: String) -> Result<Option<PathBuf>, ExportError> {
+    let response = SelectedFiles::save_file()
+        .title(tr!("activity-export-title").as_str())
+        .current_name("activity.csv")
+        .modal(true)
+        .send()
+        .await?
+        .response();
+    let files = match response {
+        Ok(files) => files,
+        Err(ashpd::Error::Response(ResponseError::Cancelled)) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let path = files
+        .uris()
+        .first()
+        .and_then(|uri| uri.to_file_path().ok())
+        .ok_or(ExportError::NotAFile)?;
+    std::fs::write(&path, csv)?;
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        for (field, quoted) in [
+            ("hotkey", "hotkey"),
+            ("", ""),
+            ("a,b", "\"a,b\""),
+            ("say \"hi\"", "\"say \"\"hi\"\"\""),
+            ("two\nlines", "\"two\nlines\""),
+            ("carriage\rreturn", "\"carriage\rreturn\""),
+        ] {
+            assert_eq!(csv_field(field), quoted);
+        }
+    }
+
+    #[test]
+    fn open_sessions_have_empty_fields() {
+        let opened = DateTime::parse_from_rfc3339("2024-05-01T10:00:00+02:00")
+            .expect("valid time")
+            .with_timezone(&Local);
+        let sessions = [
+            Session {
+                opened,
+                opened_by: Cause::HotKey,
+                closed: Some((opened + chrono::Duration::seconds(90), Cause::Lock)),
+            },
+            Session {
+                opened,
+                opened_by: Cause::Tray,
+                closed: None,
+            },
+        ];
+        let csv = to_csv(sessions.iter());
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "opened,closed,duration_seconds,opened_by,closed_by"
+        );
+        assert_eq!(
+            lines[1],
+            format!(
+                "{},{},90,hotkey,locked",
+                opened.to_rfc3339(),
+                (opened + chrono::Duration::seconds(90)).to_rfc3339()
+            )
+        );
+        assert_eq!(lines[2], format!("{},,,tray,", opened.to_rfc3339()));
+        assert_eq!(lines.len(), 3);
+    }
+}
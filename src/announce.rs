@@ -0,0 +1,96 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use notify_rust::{Hint, Notification, Urgency};
+
+use crate::{
+    events::{self, Level},
+    i18n::tr,
+    ipc::Snapshot,
+};
+
+// the notification replaces the one before instead of queueing up, where supported
+const SYNCHRONOUS_HINT: &str = "x-canonical-private-synchronous";
+
+/// A change of state worth telling screen reader users about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Announcement {
+    Live,
+    Muted,
+    Disabled,
+}
+
+impl Announcement {
+    pub const ALL: [Self; 3] = [Self::Live, Self::Muted, Self::Disabled];
+
+    pub fn of(state: &Snapshot) -> Self {
+        match (state.active, state.muted) {
+            (true, false) => Self::Live,
+            (true, true) => Self::Muted,
+            (false, _) => Self::Disabled,
+        }
+    }
+
+    /// What's said, also the label of its setting.
+    pub fn text(self) -> String {
+        match self {
+            Self::Live => tr!("announce-live"),
+            Self::Muted => tr!("announce-muted"),
+            Self::Disabled => tr!("announce-disabled"),
+        }
+    }
+}
+
+/// Announces changes through notifications, which screen readers such as Orca read out. They're
+/// sent from a thread of their own, and when several are waiting only the latest is sent.
+#[derive(Debug, Clone)]
+pub struct Announcer {
+    tx: Sender<Announcement>,
+}
+
+impl Announcer {
+    pub fn start() -> Self {
+        let (tx, rx) = mpsc::channel();
+        let spawned = std::thread::Builder::new()
+            .name("announcements".into())
+            .spawn(move || run(&rx));
+        if let Err(e) = spawned {
+            events::record(
+                Level::Warning,
+                format!("Failed to start the announcement thread: {e}"),
+            );
+        }
+        Self { tx }
+    }
+
+    pub fn announce(&self, announcement: Announcement) {
+        let _ = self.tx.send(announcement);
+    }
+}
+
+fn run(rx: &Receiver<Announcement>) {
+    // the last notification, replaced by the next one
+    let mut shown = None;
+    while let Ok(mut announcement) = rx.recv() {
+        // already out of date
+        while let Ok(newer) = rx.try_recv() {
+            announcement = newer;
+        }
+
+        let mut notification = Notification::new();
+        notification
+            .appname("Global Push-to-Talk")
+            .summary(&announcement.text())
+            .icon("audio-input-microphone")
+            // low urgency may be shown silently, which screen readers skip
+            .urgency(Urgency::Normal)
+            .hint(Hint::Transient(true))
+            .hint(Hint::Custom(SYNCHRONOUS_HINT.into(), "global-ptt".into()));
+        if let Some(id) = shown {
+            notification.id(id);
+        }
+        match notification.show() {
+            Ok(handle) => shown = Some(handle.id()),
+            Err(e) => events::record(Level::Warning, format!("Failed to announce a change: {e}")),
+        }
+    }
+}
@@ -1,61 +1,446 @@
-use std::{io::Write, os::unix::net::UnixStream, process::exit, str::FromStr};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Display,
+    path::PathBuf,
+    process::exit,
+    str::FromStr,
+    time::{Duration, Instant, SystemTime},
+};
 
 use ashpd::zbus::block_on;
-use global_hotkey::{hotkey::HotKey, wayland::using_wayland};
+use confy::ConfyError;
+use global_hotkey::wayland::using_wayland;
 use iced::{
-    Element, Font, Length, Subscription, Task, Theme,
+    Border, Color, Element, Font, Length, Point, Subscription, Task, Theme,
     alignment::{Horizontal, Vertical},
     font::{Style, Weight},
     futures::StreamExt,
-    keyboard::{self, Key, Modifiers},
+    keyboard::{self, Key, Modifiers, key::Physical},
+    task,
     widget::{
-        button, checkbox, column, container, pick_list, rich_text, row, rule, space, span, text,
-        tooltip,
+        button, checkbox, column, container, mouse_area, pick_list, rich_text, row, rule,
+        scrollable, slider, space, span, stack, text, text_input, tooltip,
     },
-    window::{Id, Settings, UserAttention, close_requests, settings::PlatformSpecific},
+    window::{Id, Position, Settings, UserAttention, close_requests, settings::PlatformSpecific},
 };
 use iced_fonts::lucide;
 use ksni::{Handle, TrayMethods};
 use notify_rust::Notification;
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM, SIGUSR1, SIGUSR2};
 use signal_hook_tokio::Signals;
-use tokio::{io::AsyncReadExt, net::UnixListener, sync::mpsc::Sender};
-use tokio_stream::wrappers::UnixListenerStream;
+use tokio::sync::{mpsc::Sender, watch};
 
 use crate::{
     APP_ID, PADDING, SPACING,
-    hotkey::{HotKeyConfig, hotkeys},
-    pulse::{InputDevice, PulseAudioState, VIRTUALMIC_DESCRIPTION},
-    tray::Tray,
+    activity::{self, Cause, History},
+    announce::{Announcement, Announcer},
+    apps, autostart,
+    config::{
+        self, CloseBehavior, Config, Corner, FailPolicy, Hook, IndicatorColor, LockBehavior,
+        MAX_OVERLAY_MARGIN, MAX_OVERLAY_SCALE, MAX_UI_SCALE, MIN_OVERLAY_OPACITY,
+        MIN_OVERLAY_SCALE, MIN_UI_SCALE, OverlayShow, ProfileError, RestingState, ThemePreference,
+        format_color, parse_color,
+    },
+    crash,
+    dbus::{self, Claim, Service},
+    events::{self, Level},
+    flatpak, headset,
+    hooks::Hooks,
+    hotkey::{Binding, HotKeyBackend, HotKeyConfig, hotkeys},
+    i18n::tr,
+    input,
+    ipc::{
+        self, BoundHotKey, Device, HotKeyTarget, MatchError, Reply, Request, Snapshot, Subscriber,
+    },
+    lock, mpris, mqtt, obs,
+    osd::{Indication, Osd},
+    overlay::{self, Overlay, OverlayState},
+    pulse::{self, InputDevice, PulseAudioState},
+    remote,
+    sounds::{Sound, Sounds},
+    stats::{self, TalkTime},
+    suspend, systemd,
+    tray::{IconColors, Tray, TrayHandle},
+    typing,
+    updates::{self, Release},
 };
 
 #[derive(Debug, Clone)]
 pub enum Msg {
     GlobalShortcutsFail,
     ChooseMicrophone(String),
+    TalkButton(bool),
+    MicSearchInput(String),
+    MicSearchMove(isize),
+    MicSearchSubmit,
     SetActive(bool),
-    ToggleActive,
+    ToggleActive(Cause),
     SetMuted(bool),
+    ToggleMute(Cause),
+    UpdateTrayMuted,
+    HoldTimeout,
+    HotKeyEvent(HotKeyAction, bool),
     UpdateHotKeyDescriptions(HotKeyConfig<String>),
+    UpdateHotKeys(HotKeyConfig<Binding>),
     ShowWindow,
-    Close,
+    PushToast(Severity, String),
+    DismissToast(usize),
+    ExpireToasts,
+    SwitchPage(Page),
+    ShowAbout,
+    CopyAbout,
+    OpenUrl(String),
+    Command(Request, Reply),
+    // a request without anyone waiting for an answer, e.g. over D-Bus
+    Request(Request),
+    Reply(Reply),
+    RetryBackend,
+    RetryHotKeys,
+    TraySpawned(TrayHandle),
+    DBusPublished(Result<(), String>),
+    ObsStatus(obs::Status),
+    MqttStatus(mqtt::Status),
+    HeadsetScan(headset::Scan),
+    ScanKeyboards,
+    KeyboardScan(input::Scan),
+    HookFinished(Hook, Result<(), String>),
+    Close(Id),
+    SetCompact(bool),
+    DragCompact,
     Exit,
     SetTheme(Option<Theme>),
-    InitChangeHotKeyTX(Sender<HotKeyConfig<HotKey>>),
+    ChooseTheme(ThemePreference),
+    CheckConfig,
+    SwitchProfile(String),
+    NextProfile,
+    StartProfileEdit(ProfileEdit),
+    ProfileNameInput(String),
+    SubmitProfileEdit,
+    CancelProfileEdit,
+    DeleteProfile,
+    OverwriteConfig,
+    SetDeviceHidden(String, bool),
+    ShowHiddenDevices(bool),
+    SetAutoEnable(bool),
+    SetBuiltinTrayIcons(bool),
+    SetTrayLabel(bool),
+    SetOverlay(bool),
+    ChooseOverlayCorner(Corner),
+    ChooseOverlayShow(OverlayShow),
+    ChooseOverlayOutput(OverlayOutput),
+    OverlayMarginChanged(u16),
+    OverlayScaleChanged(u16),
+    OverlayOpacityChanged(u8),
+    SaveOverlayLook,
+    MaxHoldChanged(u32),
+    WhisperVolumeChanged(u8),
+    SaveWhisperVolume,
+    UiScaleChanged(u16),
+    SaveUiScale,
+    CheckVirtualMic,
+    RecreateVirtualMic,
+    SetRecreateVirtualMic(bool),
+    SaveTiming,
+    HotMicWarningChanged(u32),
+    SaveHotMicWarning,
+    ResetTiming,
+    ChooseRestingState(RestingState),
+    ChooseCloseBehavior(CloseBehavior),
+    ChooseLockBehavior(LockBehavior),
+    ChooseFailPolicy(FailPolicy),
+    SessionLocked(bool),
+    Suspending,
+    Resumed,
+    Typing,
+    TypingHoldOffPassed,
+    SetSuppressTyping(bool),
+    WatchedApps(Vec<String>),
+    WatchedAppInput(String),
+    AddWatchedApp,
+    RemoveWatchedApp(String),
+    SetDisableWithWatchedApps(bool),
+    SetBackgroundReminder(bool),
+    SetAutostart(bool),
+    AutostartChanged(Result<bool, String>),
+    InitChangeHotKeyTX(Sender<HotKeyConfig<Binding>>),
+    HotKeyBackendReady(HotKeyBackend),
+    ControlSocketReady,
     StartHotKeyRecording(HotKeyAction),
+    RecordHotKeyPress(String, String),
+    RecordHotKeyRelease,
     FinishHotKeyRecording(String),
+    ConfirmHotKey,
+    RetryHotKeyRecording,
+    CancelHotKeyRecording,
+    StartHotKeyTest(HotKeyAction),
+    HotKeyTestEvent(bool),
+    HotKeyTestTick,
+    OpenTimerTick,
+    SetSaveStats(bool),
+    SetAttentionOnChange(bool),
+    SetNotifyOnToggle(bool),
+    SetSound(Sound, bool),
+    SetAnnounce(Announcement, bool),
+    SetOsdOnToggle(bool),
+    SetOsdOnPress(bool),
+    SetActivityHistory(bool),
+    SetActivityLog(bool),
+    ClearActivity,
+    SetCheckForUpdates(bool),
+    SetRemoteControl(bool),
+    SetObsIntegration(bool),
+    SetMqtt(bool),
+    SetHeadsetButton(bool),
+    ChooseHeadset(headset::Choice),
+    ChooseTriggerKeyboard(input::Choice),
+    SetPauseMedia(bool),
+    CheckForUpdates,
+    UpdateChecked(Option<Release>),
+    ExportActivity,
+    ActivityExported(Result<Option<PathBuf>, String>),
+    ColorInput(IndicatorColor, String),
+    SetColor(IndicatorColor, Option<[u8; 3]>),
+    WindowFocused(Id, bool),
+    EndFlash,
+    EventFilterInput(String),
+    CopyEvents,
+    CopyVirtualMicName,
+    ResetStats,
     None,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HotKeyAction {
     Trigger,
     ToggleActive,
+    // like the trigger, but at a reduced volume
+    WhisperTrigger,
+    NextProfile,
+}
+
+impl HotKeyAction {
+    const ALL: [Self; 4] = [
+        Self::Trigger,
+        Self::ToggleActive,
+        Self::WhisperTrigger,
+        Self::NextProfile,
+    ];
+
+    fn name(&self) -> String {
+        match self {
+            Self::Trigger => tr!("hotkey-trigger"),
+            Self::ToggleActive => tr!("hotkey-toggle-active"),
+            Self::WhisperTrigger => tr!("hotkey-whisper"),
+            Self::NextProfile => tr!("hotkey-next-profile"),
+        }
+    }
+
+    fn binding<'a, T>(&self, hotkeys: &'a HotKeyConfig<T>) -> &'a T {
+        match self {
+            Self::Trigger => &hotkeys.trigger,
+            Self::ToggleActive => &hotkeys.toggle_active,
+            Self::WhisperTrigger => &hotkeys.whisper,
+            Self::NextProfile => &hotkeys.next_profile,
+        }
+    }
+
+    fn binding_mut<'a, T>(&self, hotkeys: &'a mut HotKeyConfig<T>) -> &'a mut T {
+        match self {
+            Self::Trigger => &mut hotkeys.trigger,
+            Self::ToggleActive => &mut hotkeys.toggle_active,
+            Self::WhisperTrigger => &mut hotkeys.whisper,
+            Self::NextProfile => &mut hotkeys.next_profile,
+        }
+    }
+}
+
+impl From<HotKeyTarget> for HotKeyAction {
+    fn from(target: HotKeyTarget) -> Self {
+        match target {
+            HotKeyTarget::Trigger => Self::Trigger,
+            HotKeyTarget::ToggleActive => Self::ToggleActive,
+            HotKeyTarget::Whisper => Self::WhisperTrigger,
+            HotKeyTarget::NextProfile => Self::NextProfile,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+// a message shown in the corner of the window for a while
+#[derive(Debug, Clone)]
+struct Toast {
+    text: String,
+    severity: Severity,
+    // only counts down once the toast has been seen
+    deadline: Option<Instant>,
+}
+
+const TOAST_DURATION: Duration = Duration::from_secs(6);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Page {
+    Main,
+    Settings,
+    Diagnostics,
+    // opened from its own button rather than the page switcher
+    About,
+}
+
+impl Page {
+    const ALL: [Self; 3] = [Self::Main, Self::Settings, Self::Diagnostics];
+
+    fn window_size(self) -> (f32, f32) {
+        match self {
+            Self::Main => (600.0, 360.0),
+            Self::Settings => (600.0, 600.0),
+            Self::Diagnostics => (600.0, 480.0),
+            Self::About => (600.0, 280.0),
+        }
+    }
+}
+
+impl Display for Page {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&match self {
+            Self::Main => tr!("page-main"),
+            Self::Settings => tr!("page-settings"),
+            Self::Diagnostics => tr!("page-diagnostics"),
+            Self::About => tr!("page-about"),
+        })
+    }
+}
+
+// a monitor to put the overlay on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OverlayOutput {
+    // wherever the compositor puts it
+    Any,
+    Named(String),
+}
+
+impl Display for OverlayOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&match self {
+            Self::Any => tr!("overlay-output-any"),
+            Self::Named(name) => name.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ProfileEdit {
+    Create,
+    Rename,
+}
+
+// keys held down so far while recording a new hotkey
+#[derive(Debug, Clone)]
+struct HotKeyRecording {
+    action: HotKeyAction,
+    keys: Vec<String>,
+    modifiers: String,
+    // the finished combination, waiting to be confirmed
+    captured: Option<String>,
+}
+
+// mute changes within this time are shown in the tray at once
+const TRAY_UPDATE_DELAY: Duration = Duration::from_millis(100);
+
+const COMPACT_SIZE: (f32, f32) = (160.0, 48.0);
+// small enough for a corner of the screen, the footer wraps and the page scrolls below this
+const MIN_WINDOW_SIZE: (f32, f32) = (400.0, 250.0);
+const COMPACT_MARGIN: f32 = 24.0;
+
+// longest max hold time that can be set in the UI, in minutes
+const MAX_HOLD_LIMIT: u32 = 120;
+
+// how often to make sure the virtual mic hasn't been removed
+const VIRTUAL_MIC_CHECK_INTERVAL: Duration = Duration::from_secs(3);
+
+// clicks shorter than this latch the talk button instead of acting as a hold
+const LATCH_CLICK: Duration = Duration::from_millis(250);
+
+// how often a long-running instance looks whether a day has passed since the last update check
+const UPDATE_CHECK_INTERVAL: Duration = Duration::from_hours(1);
+
+// attention is asked for at most this often, so that quick toggling doesn't strobe the taskbar
+const ATTENTION_INTERVAL: Duration = Duration::from_secs(10);
+// how long the indicator stands out afterwards
+const FLASH_DURATION: Duration = Duration::from_secs(1);
+
+// notification action that turns off the background reminder
+const DONT_SHOW_AGAIN: &str = "dont-show-again";
+// actions of the notification about toggling push-to-talk with a hotkey
+const DISABLE: &str = "disable";
+const OPEN_WINDOW: &str = "open-window";
+
+// quick picks next to each color field: red, orange, yellow, green, blue and purple
+const SWATCHES: [[u8; 3]; 6] = [
+    [0xe5, 0x39, 0x35],
+    [0xfb, 0x8c, 0x00],
+    [0xfd, 0xd8, 0x35],
+    [0x43, 0xa0, 0x47],
+    [0x1e, 0x88, 0xe5],
+    [0x8e, 0x24, 0xaa],
+];
+
+// more devices than this get a search field instead of a plain list
+const MIC_SEARCH_THRESHOLD: usize = 6;
+
+const HOTKEY_TEST_SECONDS: u32 = 10;
+
+// listens for a hotkey's events without acting on them
+#[derive(Debug, Clone)]
+struct HotKeyTest {
+    action: HotKeyAction,
+    seconds_left: u32,
+    pressed: bool,
+    events: u32,
+}
+
+// mutes the open mic while typing, until the keyboard was quiet for the hold-off
+#[derive(Debug, Clone, Copy, Default)]
+struct TypingMute {
+    until: Option<Instant>,
+}
+
+impl TypingMute {
+    fn is_muting(self) -> bool {
+        self.until.is_some()
+    }
+
+    // a key was pressed, which mutes the mic if it's `open` with nothing holding it open.
+    // returns whether that starts muting it, rather than keeping it muted for longer
+    fn key(&mut self, now: Instant, hold_off: Duration, open: bool) -> bool {
+        open && self.until.replace(now + hold_off).is_none()
+    }
+
+    // how much longer the mic stays muted, ending it once the time is up
+    fn remaining(&mut self, now: Instant) -> Option<Duration> {
+        let left = self
+            .until?
+            .checked_duration_since(now)
+            .filter(|left| !left.is_zero());
+        if left.is_none() {
+            self.until = None;
+        }
+        left
+    }
+
+    fn stop(&mut self) {
+        self.until = None;
+    }
 }
 
 #[derive(Clone)]
 struct Backend {
     pa_state: PulseAudioState,
-    tray: Option<Handle<Tray>>,
 }
 
 #[derive(Clone)]
@@ -64,86 +449,377 @@ enum BackendState {
     Error(String),
 }
 
+// the states the app is in are mostly flags of their own
 #[derive(Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct App {
     active: bool,
     muted: bool,
+    // which of the triggers are currently held
+    trigger_held: bool,
+    whisper_held: bool,
+    // the talk button in the window, held or latched open by a click
+    button_held: bool,
+    button_pressed_at: Option<Instant>,
+    button_latched: bool,
+    // keeps the mic muted while the screen is locked
+    locked: bool,
+    // set when the screen locked with the mic open, to whether the talk button was latched
+    open_before_lock: Option<bool>,
+    // between the system getting ready to suspend and resuming, also keeps the mic muted
+    suspended: bool,
+    // when the mic was last opened, while it is still open
+    open_since: Option<Instant>,
+    // hot mic reminders sent since the mic was opened
+    hot_mic_warnings: u64,
+    focused_window: Option<Id>,
+    // the indicator stands out until then after push-to-talk was toggled from elsewhere
+    flash_until: Option<Instant>,
+    last_attention: Option<Instant>,
+    // a newer release, once found
+    update: Option<Release>,
+    // what the next change of the mic is caused by
+    cause: Option<Cause>,
+    history: History,
+    // hex colors being typed, which may not be valid yet
+    color_drafts: HashMap<IndicatorColor, String>,
+    session_talk: TalkTime,
+    // only events containing this are listed on the diagnostics page
+    event_filter: String,
+    // saved totals for today, only kept up to date while saving is enabled
+    today_talk: TalkTime,
     hk_descriptions: HotKeyConfig<String>,
+    hotkeys: HotKeyConfig<Binding>,
     backend: BackendState,
-    theme: Option<Theme>,
-    change_hotkey_tx: Option<Sender<HotKeyConfig<HotKey>>>,
-    recording_hotkey: Option<HotKeyAction>,
+    page: Page,
+    window_open: bool,
+    // the small always-on-top window shown instead of the main one in compact mode
+    compact_window: Option<Id>,
+    toasts: Vec<Toast>,
+    tray: Option<Handle<Tray>>,
+    overlay: Option<Overlay>,
+    // whether a mute change still has to be shown in the tray
+    tray_update_pending: bool,
+    mute_failed: bool,
+    // bumped to restart the hotkey listener
+    hotkey_attempt: u32,
+    // set when hotkeys can't be used, while audio may still work
+    hotkey_error: Option<String>,
+    // known once the hotkey listener is up
+    hotkey_backend: Option<HotKeyBackend>,
+    // startup steps systemd waits for, see notify_ready
+    control_socket_ready: bool,
+    systemd_ready: bool,
+    // theme requested by the desktop, unless overridden in the config
+    system_theme: Option<Theme>,
+    change_hotkey_tx: Option<Sender<HotKeyConfig<Binding>>>,
+    recording_hotkey: Option<HotKeyRecording>,
+    config: Config,
+    hold_watchdog: Option<task::Handle>,
+    hotkey_test: Option<HotKeyTest>,
+    config_modified: Option<SystemTime>,
+    autostart: bool,
+    profile_edit: Option<(ProfileEdit, String)>,
+    show_hidden_devices: bool,
+    // filters the microphone picker when there are many devices
+    mic_search: String,
+    mic_highlight: usize,
+    // follows the slider while it's dragged, saved on release
+    whisper_volume: u8,
+    // follows the slider while it's dragged, applied on release
+    ui_scale: u16,
+    // set while the config file can't be parsed
+    config_error: Option<String>,
+    // the virtual mic was removed by something else
+    virtual_mic_missing: bool,
+    // unset when the session bus can't be reached or the name is taken
+    dbus: Option<zbus::Connection>,
+    // what subscribers and the bus were last told
+    published_state: Option<Snapshot>,
+    // connections that follow every change
+    subscribers: Vec<Subscriber>,
+    hooks: Hooks,
+    // dropping it stops remote control
+    remote_control: Option<iced::task::Handle>,
+    dry_run: bool,
+    // the notification about the last toggle, replaced by the next one
+    toggle_notification: Option<u32>,
+    sounds: Sounds,
+    osd: Osd,
+    announcer: Announcer,
+    // dropping it disconnects from OBS
+    obs: Option<iced::task::Handle>,
+    obs_status: Option<obs::Status>,
+    // dropping it disconnects from the MQTT broker
+    mqtt: Option<iced::task::Handle>,
+    mqtt_status: Option<mqtt::Status>,
+    // dropping it stops listening to headsets
+    headset: Option<iced::task::Handle>,
+    headset_scan: Option<headset::Scan>,
+    keyboard_scan: Option<input::Scan>,
+    // dropping it stops pausing media players, without resuming the ones it paused
+    pause_media: Option<iced::task::Handle>,
+    typing: Option<iced::task::Handle>,
+    // keeps the mic muted for typing, while the app still counts it as open
+    typing_mute: TypingMute,
+    watched_apps: Option<iced::task::Handle>,
+    // the watched applications that are running, as named in the config
+    watched_running: Vec<String>,
+    watched_app_input: String,
+    // push-to-talk was toggled by hand since a watched application last started
+    watched_overridden: bool,
+    // the state for OBS, MQTT, headset LEDs and media players, which follow it in tasks of
+    // their own
+    published: watch::Sender<Snapshot>,
 }
 
-impl App {
-    pub fn new() -> (Self, Task<Msg>) {
-        // there must only be one running instance of this application
+/// What to set up right after starting, from the command line.
+#[derive(Debug, Clone, Default)]
+pub struct Startup {
+    pub hidden: bool,
+    pub active: bool,
+    // a source name, or part of its name or description
+    pub mic: Option<String>,
+    // audio changes are printed instead of made
+    pub dry_run: bool,
+}
 
-        // try to open existing instance
-        let socket_path = format!("/tmp/{APP_ID}.{}", nix::unistd::Uid::current());
-        if let Ok(mut stream) = UnixStream::connect(socket_path.clone())
-            && stream.write_all(b"open").is_ok()
-        {
-            // existing instance successfully opened
-            exit(0);
+impl Startup {
+    // what the flags ask of an instance that's already running
+    fn requests(&self) -> Vec<Request> {
+        let mut requests = Vec::new();
+        if let Some(mic) = &self.mic {
+            requests.push(Request::SelectMicrophone(mic.clone()));
         }
+        if self.active {
+            requests.push(Request::SetActive(true));
+        }
+        requests
+    }
+}
 
-        // create new unix listener
-        let _ = std::fs::remove_file(&socket_path);
-        let ipc_stream: Task<Msg> = Task::future(async move { UnixListener::bind(socket_path) })
-            .then(|res| {
-                let Ok(listener) = res else {
-                    return Task::none();
-                };
+impl App {
+    // every field is set up here, most of the length is the struct itself
+    #[allow(clippy::too_many_lines)]
+    pub fn new(startup: &Startup) -> (Self, Task<Msg>) {
+        // there must only be one running instance of this application, the one owning the bus name.
+        // any other one passes the flags on to it
+        let (dbus_service, dbus_stream) = Service::new();
+        let dbus = match block_on(dbus_service.claim()) {
+            Ok(Claim::Owner(connection)) => Some(connection),
+            Ok(Claim::Taken(connection)) => {
+                let requests = startup.requests();
+                if let Err(e) = block_on(dbus::forward(&connection, startup.hidden, &requests)) {
+                    eprintln!("Another instance is running, but failed to reach it: {e}");
+                    exit(1);
+                }
+                exit(0);
+            }
+            Err(e) => {
+                events::record(Level::Warning, format!("D-Bus service unavailable: {e}"));
+                None
+            }
+        };
 
-                let stream = UnixListenerStream::new(listener);
-                Task::stream(stream).then(|incoming| {
-                    Task::future(async {
-                        let Ok(mut incoming) = incoming else {
-                            return Msg::None;
-                        };
+        // without a session bus the lock file decides instead, it also guards the socket
+        if !ipc::claim_instance() {
+            let probe = if startup.hidden {
+                Request::Status
+            } else {
+                Request::Show
+            };
+            if let Err(e) = ipc::request_waiting(&probe) {
+                eprintln!("Another instance is running, but failed to reach it: {e}");
+                exit(1);
+            }
+            for request in startup.requests() {
+                let _ = ipc::request(request);
+            }
+            exit(0);
+        }
 
-                        let mut buffer = String::new();
-                        let _ = incoming.read_to_string(&mut buffer).await;
+        let backend = load_backend(startup.dry_run);
+        let (tray_builder, tray_stream) = Tray::new();
+        let tray = block_on(tray_builder.clone().spawn());
 
-                        if buffer == "open" {
-                            Msg::ShowWindow
-                        } else {
-                            Msg::None
-                        }
-                    })
-                })
-            });
+        // there may not be a tray host yet, e.g. when started before the panel
+        let retry_tray = if tray.is_err() {
+            Task::perform(tray_builder.spawn_retrying(), Msg::TraySpawned)
+        } else {
+            Task::none()
+        };
 
-        let pa_state = PulseAudioState::init();
-        let (tray_builder, tray_stream) = Tray::new();
-        let tray = block_on(tray_builder.spawn());
+        let (config, config_error) = match Config::load() {
+            Ok(config) => (config, None),
+            Err(e) => {
+                let e = config_error_message(&e);
+                let _ = Notification::new()
+                    .appname("Global Push-to-Talk")
+                    .summary(&tr!("config-load-failed"))
+                    .body(&e)
+                    .show();
+                (Config::default(), Some(e))
+            }
+        };
+        let ipc_stream = ipc::listen(config.allowed_clients());
 
-        let backend = match (pa_state, tray.ok()) {
-            (Ok(pa_state), tray) => BackendState::Loaded(Backend { pa_state, tray }),
-            (Err(e), _) => BackendState::Error(e.to_string()),
+        let autostart = if flatpak::is_sandboxed() {
+            config.autostart()
+        } else {
+            autostart::is_enabled()
         };
 
-        let this = Self {
+        let mut this = Self {
             muted: false,
             active: false,
+            trigger_held: false,
+            whisper_held: false,
+            button_held: false,
+            button_pressed_at: None,
+            button_latched: false,
+            locked: false,
+            open_before_lock: None,
+            suspended: false,
+            open_since: None,
+            hot_mic_warnings: 0,
+            focused_window: None,
+            flash_until: None,
+            last_attention: None,
+            color_drafts: HashMap::new(),
+            cause: None,
+            update: None,
+            history: History::default(),
+            session_talk: TalkTime::default(),
+            event_filter: String::new(),
+            today_talk: if config.save_stats() {
+                stats::load_today()
+            } else {
+                TalkTime::default()
+            },
             hk_descriptions: HotKeyConfig::default(),
-            theme: None,
+            hotkeys: HotKeyConfig::default(),
+            system_theme: None,
             backend,
+            page: Page::Main,
+            window_open: false,
+            compact_window: None,
+            toasts: Vec::new(),
+            tray: tray.ok(),
+            overlay: None,
+            tray_update_pending: false,
+            mute_failed: false,
+            hotkey_attempt: 0,
+            hotkey_error: None,
+            hotkey_backend: None,
+            control_socket_ready: false,
+            systemd_ready: false,
             change_hotkey_tx: None,
             recording_hotkey: None,
+            whisper_volume: config.whisper_volume(),
+            ui_scale: config.ui_scale(),
+            config,
+            hold_watchdog: None,
+            hotkey_test: None,
+            config_modified: config_modified(),
+            autostart,
+            profile_edit: None,
+            show_hidden_devices: false,
+            mic_search: String::new(),
+            mic_highlight: 0,
+            config_error,
+            virtual_mic_missing: false,
+            dbus,
+            published_state: None,
+            subscribers: Vec::new(),
+            hooks: Hooks::default(),
+            remote_control: None,
+            dry_run: startup.dry_run,
+            toggle_notification: None,
+            sounds: Sounds::start(),
+            osd: Osd::start(),
+            announcer: Announcer::start(),
+            obs: None,
+            obs_status: None,
+            mqtt: None,
+            mqtt_status: None,
+            headset: None,
+            headset_scan: None,
+            keyboard_scan: None,
+            pause_media: None,
+            typing: None,
+            typing_mute: TypingMute::default(),
+            watched_apps: None,
+            watched_running: Vec::new(),
+            watched_app_input: String::new(),
+            watched_overridden: false,
+            published: watch::channel(Snapshot {
+                active: false,
+                muted: true,
+                microphone: None,
+            })
+            .0,
         };
 
         // handling signals
-        let signal_handler = match Signals::new([signal_hook::consts::SIGUSR1]) {
-            Ok(signals) => Task::stream(signals).map(|_| Msg::ShowWindow),
+        // logging out sends SIGTERM or SIGHUP, which must not leave the virtual mic behind
+        let signal_handler = match Signals::new([SIGUSR1, SIGUSR2, SIGTERM, SIGINT, SIGHUP]) {
+            Ok(signals) => Task::stream(signals).map(|signal| match signal {
+                SIGUSR1 => Msg::ShowWindow,
+                SIGUSR2 => Msg::ToggleActive(Cause::Command),
+                _ => Msg::Exit,
+            }),
             Err(_) => Task::none(),
         };
 
+        this.sync_fail_policy();
+        let restore = this.restore_session();
+        let remote_control = this.sync_remote_control();
+        this.published.send_replace(this.snapshot());
+        let obs = this.sync_obs();
+        let mqtt = this.sync_mqtt();
+        let headset = this.sync_headset();
+        let pause_media = this.sync_pause_media();
+        let watched_apps = this.sync_watched_apps();
+        let typing = this.sync_typing();
+        let lock = Task::stream(lock::watch());
+        let suspend = Task::stream(suspend::watch(this.published.subscribe()));
+        this.sync_tray();
+        this.sync_overlay();
+
+        // new users still need to see the window to set up a microphone
+        let has_mic = match &this.backend {
+            BackendState::Loaded(b) => b.pa_state.get_active_source_name().is_some(),
+            BackendState::Error(_) => false,
+        };
+        let has_mic = has_mic || startup.mic.is_some();
+        let show_window = if (startup.hidden || this.config.start_minimized()) && has_mic {
+            Task::none()
+        } else {
+            Task::done(Msg::ShowWindow)
+        };
+
         let tasks = Task::batch([
-            Task::done(Msg::ShowWindow),
+            show_window,
+            // applied after the last session, so that the flags win
+            startup
+                .requests()
+                .into_iter()
+                .fold(restore, |task, request| {
+                    task.chain(Task::done(Msg::Request(request)))
+                }),
             Task::stream(tray_stream),
+            retry_tray,
             ipc_stream,
+            Task::stream(dbus_stream),
+            remote_control,
+            obs,
+            mqtt,
+            headset,
+            pause_media,
+            watched_apps,
+            typing,
+            lock,
+            suspend,
             Task::stream(
                 mundy::Preferences::stream(mundy::Interest::ColorScheme).map(|c| {
                     Msg::SetTheme(match c.color_scheme {
@@ -154,349 +830,3960 @@ impl App {
                 }),
             ),
             signal_handler,
+            Task::done(Msg::CheckForUpdates),
         ]);
         (this, tasks)
     }
 
     pub fn update(&mut self, msg: Msg) -> Task<Msg> {
+        let task = self.handle(msg);
+        // the hotkey thread saves as well, which is noticed with the next message
+        if let Some(e) = Config::take_save_error() {
+            self.push_toast(Severity::Error, tr!("save-failed", error = e));
+        }
+        Task::batch([task, self.publish_state()])
+    }
+
+    // one arm per message, the longer ones call out to methods of their own
+    #[allow(clippy::too_many_lines)]
+    fn handle(&mut self, msg: Msg) -> Task<Msg> {
         match msg {
             Msg::None => {}
-            Msg::ChooseMicrophone(mic) => return self.choose_microphone(&mic),
+            Msg::ChooseMicrophone(mic) => {
+                self.mic_search.clear();
+                return self.choose_microphone(&mic);
+            }
+            Msg::TalkButton(pressed) => return self.talk_button(pressed),
+            Msg::MicSearchInput(query) => {
+                self.mic_search = query;
+                self.mic_highlight = 0;
+            }
+            Msg::MicSearchMove(delta) => {
+                let count = self.mic_matches().len();
+                if count > 0 {
+                    self.mic_highlight = self
+                        .mic_highlight
+                        .saturating_add_signed(delta)
+                        .min(count - 1);
+                }
+            }
+            Msg::MicSearchSubmit => {
+                if let Some(dev) = self.mic_matches().into_iter().nth(self.mic_highlight) {
+                    return Task::done(Msg::ChooseMicrophone(dev.name));
+                }
+            }
             Msg::SetActive(a) => return self.set_active(a),
-            Msg::ToggleActive => return Task::done(Msg::SetActive(!self.active)),
-            Msg::SetMuted(m) => self.set_muted(m),
+            Msg::ToggleActive(cause) => {
+                self.cause = Some(cause);
+                // the watched applications don't undo what was done by hand
+                self.watched_overridden = true;
+                return Task::done(Msg::SetActive(!self.active));
+            }
+            Msg::SetMuted(m) => return self.set_muted(m),
+            Msg::ToggleMute(cause) => {
+                self.cause = Some(cause);
+                return self.set_muted(!self.muted);
+            }
+            Msg::UpdateTrayMuted => self.update_tray_muted(),
+            Msg::HoldTimeout => return self.hold_timeout(),
+            Msg::HotKeyEvent(action, pressed) => return self.hotkey_event(&action, pressed),
             Msg::GlobalShortcutsFail => self.global_shortcuts_fail(),
-            Msg::UpdateHotKeyDescriptions(descriptions) => self.hk_descriptions = descriptions,
+            Msg::UpdateHotKeyDescriptions(descriptions) => {
+                self.hk_descriptions = descriptions;
+                self.update_tray_info();
+            }
+            Msg::UpdateHotKeys(hotkeys) => {
+                self.hk_descriptions = HotKeyConfig {
+                    trigger: hotkeys.trigger.description(),
+                    toggle_active: hotkeys.toggle_active.description(),
+                    whisper: hotkeys.whisper.description(),
+                    next_profile: hotkeys.next_profile.description(),
+                };
+                self.hotkeys = hotkeys;
+                self.update_tray_info();
+            }
             Msg::ShowWindow => return self.show_window(),
-            Msg::Close => return Self::close_window(),
+            Msg::SetCompact(true) => return self.enter_compact(),
+            Msg::SetCompact(false) => return self.leave_compact(),
+            Msg::DragCompact => {
+                if let Some(id) = self.compact_window {
+                    return iced::window::drag(id);
+                }
+            }
+            Msg::SwitchPage(page) => {
+                self.page = page;
+                let size = iced::Size::from(self.scaled(page.window_size()));
+                let resize =
+                    iced::window::latest().and_then(move |id| iced::window::resize(id, size));
+                if matches!(page, Page::Settings | Page::Diagnostics) && !using_wayland() {
+                    return resize.chain(Task::done(Msg::ScanKeyboards));
+                }
+                return resize;
+            }
+            Msg::ShowAbout => {
+                return Task::done(Msg::SwitchPage(Page::About)).chain(Task::done(Msg::ShowWindow));
+            }
+            Msg::CopyAbout => {
+                if let BackendState::Loaded(b) = &self.backend {
+                    return iced::clipboard::write(self.about_text(b));
+                }
+            }
+            Msg::Command(Request::Subscribe, reply) => {
+                if let Some(subscriber) = reply.subscribe()
+                    && subscriber.send(&self.snapshot())
+                {
+                    self.subscribers.push(subscriber);
+                }
+            }
+            Msg::Command(Request::ListDevices, reply) => {
+                reply.send_devices(&self.snapshot(), self.devices());
+            }
+            Msg::Command(Request::Bind { action, binding }, reply) => {
+                match self.rebind(&action.into(), binding.as_deref()) {
+                    Ok(hotkeys) => {
+                        let bound = HotKeyTarget::ALL
+                            .into_iter()
+                            .map(|target| {
+                                let binding = HotKeyAction::from(target).binding(&hotkeys);
+                                BoundHotKey {
+                                    action: target,
+                                    binding: binding.to_string(),
+                                    description: binding.description(),
+                                }
+                            })
+                            .collect();
+                        reply.send_hotkeys(&self.snapshot(), bound);
+                        return self.change_hotkeys(hotkeys);
+                    }
+                    Err(e) => reply.send_error(&e),
+                }
+            }
+            Msg::Command(request, reply) => {
+                // answered once everything the command set off has been handled
+                return self
+                    .run_command(request)
+                    .chain(Task::done(Msg::Reply(reply)));
+            }
+            Msg::Reply(reply) => reply.send(&self.snapshot()),
+            Msg::Request(request) => return self.run_command(request),
+            Msg::OpenUrl(url) => {
+                if let Err(e) = std::process::Command::new("xdg-open").arg(&url).spawn() {
+                    events::record(Level::Warning, format!("Failed to open {url}: {e}"));
+                }
+            }
+            Msg::TraySpawned(handle) => {
+                events::record(Level::Info, "Tray host found");
+                self.tray = Some(handle.0);
+                self.sync_tray();
+            }
+            Msg::DBusPublished(res) => {
+                if let Err(e) = res {
+                    events::record(Level::Warning, format!("Failed to update D-Bus state: {e}"));
+                }
+            }
+            Msg::HookFinished(hook, res) => {
+                if let Err(e) = res {
+                    events::record(Level::Warning, format!("{} failed: {e}", hook.key()));
+                }
+            }
+            Msg::RetryBackend => return self.retry_backend(),
+            Msg::RetryHotKeys => self.retry_hotkeys(),
+            Msg::Close(id) if Some(id) == self.compact_window => {
+                self.compact_window = None;
+                self.focused_window = None;
+                return iced::window::close(id);
+            }
+            Msg::Close(id) => {
+                self.window_open = false;
+                self.focused_window = None;
+                return self.close_window(id);
+            }
+            Msg::PushToast(severity, text) => self.push_toast(severity, text),
+            Msg::DismissToast(i) => {
+                if i < self.toasts.len() {
+                    self.toasts.remove(i);
+                }
+            }
+            Msg::ExpireToasts => {
+                let now = Instant::now();
+                self.toasts
+                    .retain(|t| t.deadline.is_none_or(|deadline| deadline > now));
+            }
             Msg::Exit => self.exit(),
-            Msg::SetTheme(theme) => self.theme = theme,
+            Msg::SetTheme(theme) => {
+                self.system_theme = theme;
+                self.sync_overlay();
+            }
+            Msg::ChooseTheme(theme) => {
+                self.config.store_theme(theme);
+                self.sync_overlay();
+            }
+            Msg::CheckConfig => return self.check_config(),
+            Msg::CheckVirtualMic => return self.check_virtual_mic(),
+            Msg::RecreateVirtualMic => return self.recreate_virtual_mic(),
+            Msg::SetRecreateVirtualMic(recreate) => {
+                self.config.store_recreate_virtual_mic(recreate);
+            }
+            Msg::SetDeviceHidden(name, hidden) => self.config.store_device_hidden(&name, hidden),
+            Msg::ShowHiddenDevices(show) => self.show_hidden_devices = show,
+            Msg::SetAutoEnable(enabled) => self.config.store_auto_enable(enabled),
+            Msg::SetBuiltinTrayIcons(builtin) => {
+                self.config.store_builtin_tray_icons(builtin);
+                self.update_tray_info();
+            }
+            Msg::SetTrayLabel(label) => {
+                self.config.store_tray_label(label);
+                return self.publish_tray_label();
+            }
+            Msg::SetOverlay(enabled) => {
+                self.config.store_overlay(enabled);
+                self.sync_overlay();
+            }
+            Msg::ChooseOverlayCorner(corner) => {
+                self.config.store_overlay_corner(corner);
+                self.sync_overlay();
+            }
+            Msg::ChooseOverlayShow(show) => {
+                self.config.store_overlay_show(show);
+                self.update_overlay();
+            }
+            Msg::ChooseOverlayOutput(output) => {
+                self.config.store_overlay_output(match output {
+                    OverlayOutput::Any => None,
+                    OverlayOutput::Named(name) => Some(name),
+                });
+                self.sync_overlay();
+            }
+            // shown right away, saved on release
+            Msg::OverlayMarginChanged(margin) => {
+                self.config.set_overlay_margin(margin);
+                self.sync_overlay();
+            }
+            Msg::OverlayScaleChanged(percent) => {
+                self.config.set_overlay_scale(percent);
+                self.sync_overlay();
+            }
+            Msg::OverlayOpacityChanged(percent) => {
+                self.config.set_overlay_opacity(percent);
+                self.sync_overlay();
+            }
+            Msg::SaveOverlayLook => {
+                let (margin, scale, opacity) = (
+                    self.config.overlay_margin(),
+                    self.config.overlay_scale(),
+                    self.config.overlay_opacity(),
+                );
+                self.config.store_overlay_margin(margin);
+                self.config.store_overlay_scale(scale);
+                self.config.store_overlay_opacity(opacity);
+            }
+            Msg::MaxHoldChanged(minutes) => self.config.set_max_hold_minutes(minutes.into()),
+            Msg::SaveTiming => {
+                let minutes = self.max_hold_minutes();
+                self.config.store_max_hold_minutes(minutes.into());
+            }
+            Msg::ResetTiming => self.config.store_max_hold_minutes(0),
+            Msg::HotMicWarningChanged(minutes) => {
+                self.config.set_hot_mic_warning_minutes(minutes.into());
+            }
+            Msg::SaveHotMicWarning => {
+                let minutes = self.hot_mic_warning_minutes();
+                self.config.store_hot_mic_warning_minutes(minutes.into());
+            }
+            Msg::WhisperVolumeChanged(percent) => self.whisper_volume = percent,
+            Msg::SaveWhisperVolume => self.config.store_whisper_volume(self.whisper_volume),
+            Msg::UiScaleChanged(percent) => self.ui_scale = percent,
+            Msg::SaveUiScale => {
+                self.config.store_ui_scale(self.ui_scale);
+                // grow or shrink the window along with its content
+                let size = iced::Size::from(self.window_size());
+                return iced::window::latest().and_then(move |id| iced::window::resize(id, size));
+            }
+            Msg::SetAutostart(enabled) => {
+                return Task::perform(autostart::set_enabled(enabled), |res| {
+                    Msg::AutostartChanged(res.map_err(|e| e.to_string()))
+                });
+            }
+            Msg::AutostartChanged(res) => self.autostart_changed(res),
+            Msg::SwitchProfile(name) => return self.switch_profile(&name),
+            Msg::NextProfile => return self.next_profile(),
+            Msg::StartProfileEdit(edit) => {
+                let name = match edit {
+                    ProfileEdit::Create => String::new(),
+                    ProfileEdit::Rename => self.config.active_profile().to_string(),
+                };
+                self.profile_edit = Some((edit, name));
+            }
+            Msg::ProfileNameInput(input) => {
+                if let Some((_, name)) = &mut self.profile_edit {
+                    *name = input;
+                }
+            }
+            Msg::SubmitProfileEdit => return self.submit_profile_edit(),
+            Msg::CancelProfileEdit => self.profile_edit = None,
+            Msg::DeleteProfile => return self.delete_profile(),
+            Msg::ChooseRestingState(state) => {
+                self.config.store_when_disabled(state);
+                return Task::done(Msg::SetActive(self.active));
+            }
+            Msg::ChooseCloseBehavior(behavior) => self.config.store_close_behavior(behavior),
+            Msg::ChooseLockBehavior(behavior) => self.config.store_on_lock(behavior),
+            Msg::ChooseFailPolicy(policy) => {
+                self.config.store_fail_policy(policy);
+                self.sync_fail_policy();
+            }
+            Msg::SessionLocked(locked) => return self.session_locked(locked),
+            Msg::Suspending => return self.suspending(),
+            Msg::Resumed => return self.resumed(),
+            Msg::Typing => return self.typing(),
+            Msg::TypingHoldOffPassed => return self.typing_hold_off_passed(),
+            Msg::SetSuppressTyping(suppress) => {
+                self.config.store_suppress_typing(suppress);
+                return self.sync_typing();
+            }
+            Msg::WatchedApps(running) => return self.watched_apps_changed(running),
+            Msg::WatchedAppInput(input) => self.watched_app_input = input,
+            Msg::AddWatchedApp => {
+                let app = std::mem::take(&mut self.watched_app_input)
+                    .trim()
+                    .to_string();
+                let mut apps = self.config.watched_apps();
+                if app.is_empty() || apps.iter().any(|a| a.eq_ignore_ascii_case(&app)) {
+                    return Task::none();
+                }
+                apps.push(app);
+                self.config.store_watched_apps(apps);
+                return self.sync_watched_apps();
+            }
+            Msg::RemoveWatchedApp(app) => {
+                let mut apps = self.config.watched_apps();
+                apps.retain(|a| *a != app);
+                self.config.store_watched_apps(apps);
+                return self.sync_watched_apps();
+            }
+            Msg::SetDisableWithWatchedApps(disable) => {
+                self.config.store_disable_with_watched_apps(disable);
+            }
+            Msg::SetBackgroundReminder(remind) => self.config.store_background_reminder(remind),
+            Msg::OverwriteConfig => {
+                self.config.overwrite();
+                self.config_modified = config_modified();
+                self.config_error = None;
+            }
             Msg::InitChangeHotKeyTX(change_hotkey) => self.change_hotkey_tx = Some(change_hotkey),
-            Msg::StartHotKeyRecording(recording) => self.recording_hotkey = Some(recording),
+            Msg::HotKeyBackendReady(backend) => {
+                events::record(Level::Info, format!("Hotkeys started: {backend}"));
+                self.hotkey_backend = Some(backend);
+                self.notify_ready();
+            }
+            Msg::ControlSocketReady => {
+                self.control_socket_ready = true;
+                self.notify_ready();
+            }
+            Msg::StartHotKeyRecording(action) => {
+                self.recording_hotkey = Some(HotKeyRecording {
+                    action,
+                    keys: Vec::new(),
+                    modifiers: String::new(),
+                    captured: None,
+                });
+            }
+            Msg::RecordHotKeyPress(key, modifiers) => self.record_hotkey_press(key, modifiers),
+            Msg::RecordHotKeyRelease => self.record_hotkey_release(),
             Msg::FinishHotKeyRecording(hk_string) => {
-                return self.finish_hotkey_recording(&hk_string);
+                if self
+                    .recording_hotkey
+                    .as_ref()
+                    .is_some_and(|r| !r.keys.is_empty())
+                {
+                    self.record_hotkey_release();
+                } else {
+                    self.capture_hotkey(hk_string);
+                }
+            }
+            Msg::ConfirmHotKey => return self.finish_hotkey_recording(),
+            Msg::RetryHotKeyRecording => {
+                if let Some(recording) = &mut self.recording_hotkey {
+                    recording.keys.clear();
+                    recording.modifiers.clear();
+                    recording.captured = None;
+                }
+            }
+            Msg::CancelHotKeyRecording => self.recording_hotkey = None,
+            Msg::StartHotKeyTest(action) => {
+                self.hotkey_test = Some(HotKeyTest {
+                    action,
+                    seconds_left: HOTKEY_TEST_SECONDS,
+                    pressed: false,
+                    events: 0,
+                });
+            }
+            Msg::HotKeyTestEvent(pressed) => {
+                if let Some(test) = &mut self.hotkey_test {
+                    test.pressed = pressed;
+                    test.events += 1;
+                }
+            }
+            Msg::OpenTimerTick => {
+                self.update_tray_open_for();
+                self.check_hot_mic();
+            }
+            Msg::SetNotifyOnToggle(notify) => self.config.store_notify_on_toggle(notify),
+            Msg::SetSound(sound, play) => self.config.store_sound(sound, play),
+            Msg::SetAnnounce(announcement, announce) => {
+                self.config.store_announce(announcement, announce);
+            }
+            Msg::SetOsdOnToggle(show) => self.config.store_osd_on_toggle(show),
+            Msg::SetOsdOnPress(show) => self.config.store_osd_on_press(show),
+            Msg::SetAttentionOnChange(attention) => {
+                self.config.store_attention_on_change(attention);
+            }
+            Msg::WindowFocused(id, true) => self.focused_window = Some(id),
+            Msg::WindowFocused(id, false) => {
+                if self.focused_window == Some(id) {
+                    self.focused_window = None;
+                }
+            }
+            Msg::EndFlash => self.flash_until = None,
+            Msg::SetActivityHistory(keep) => {
+                self.config.store_activity_history(keep);
+                if !keep {
+                    self.history.clear();
+                }
+            }
+            Msg::SetActivityLog(log) => self.config.store_activity_log(log),
+            Msg::ExportActivity => {
+                let csv = activity::to_csv(self.history.sessions());
+                return Task::perform(activity::export(csv), |res| {
+                    Msg::ActivityExported(res.map_err(|e| e.to_string()))
+                });
+            }
+            Msg::ActivityExported(Ok(Some(path))) => self.push_toast(
+                Severity::Info,
+                tr!("activity-exported", path = path.display().to_string()),
+            ),
+            Msg::ActivityExported(Ok(None)) => (),
+            Msg::ActivityExported(Err(e)) => {
+                self.push_toast(Severity::Error, tr!("activity-export-failed", error = e));
+            }
+            Msg::SetRemoteControl(enabled) => {
+                self.config.store_remote_control(enabled);
+                return self.sync_remote_control();
+            }
+            Msg::SetObsIntegration(enabled) => {
+                self.config.store_obs_integration(enabled);
+                return self.sync_obs();
+            }
+            Msg::ObsStatus(status) => self.obs_status = Some(status),
+            Msg::SetMqtt(enabled) => {
+                self.config.store_mqtt(enabled);
+                return self.sync_mqtt();
+            }
+            Msg::MqttStatus(status) => self.mqtt_status = Some(status),
+            Msg::SetHeadsetButton(enabled) => {
+                self.config.store_headset_button(enabled);
+                return self.sync_headset();
+            }
+            Msg::ChooseHeadset(choice) => {
+                self.config.store_headset(match choice {
+                    headset::Choice::Any => None,
+                    headset::Choice::Named(name) => Some(name),
+                });
+                return self.sync_headset();
+            }
+            Msg::HeadsetScan(scan) => self.headset_scan = Some(scan),
+            Msg::ScanKeyboards => {
+                return Task::perform(
+                    async {
+                        tokio::task::spawn_blocking(input::scan)
+                            .await
+                            .unwrap_or_default()
+                    },
+                    Msg::KeyboardScan,
+                );
+            }
+            Msg::KeyboardScan(scan) => self.keyboard_scan = Some(scan),
+            Msg::ChooseTriggerKeyboard(choice) => {
+                self.config.store_trigger_keyboard(match choice {
+                    input::Choice::Any => None,
+                    input::Choice::Keyboard(keyboard) => Some(keyboard.id),
+                });
+                // grabbed again, or let go of to be read from the keyboard instead
+                return self.change_hotkeys(self.hotkeys.clone());
+            }
+            Msg::SetPauseMedia(pause) => {
+                self.config.store_pause_media(pause);
+                return self.sync_pause_media();
+            }
+            Msg::SetCheckForUpdates(check) => {
+                self.config.store_check_for_updates(check);
+                if check {
+                    return Task::done(Msg::CheckForUpdates);
+                }
+                self.update = None;
+            }
+            Msg::CheckForUpdates => {
+                if self.config.check_for_updates() {
+                    return Task::perform(updates::check(), Msg::UpdateChecked);
+                }
+            }
+            Msg::UpdateChecked(release) => self.update_checked(release),
+            Msg::ClearActivity => {
+                self.history.clear();
+                activity::clear_log();
+            }
+            Msg::ColorInput(which, input) => {
+                // stored as soon as it's a valid color, the text stays as typed
+                if let Some(color) = parse_color(&input) {
+                    self.config.store_color(which, Some(color));
+                    self.apply_colors();
+                }
+                self.color_drafts.insert(which, input);
+            }
+            Msg::SetColor(which, color) => {
+                self.config.store_color(which, color);
+                self.color_drafts.remove(&which);
+                self.apply_colors();
+            }
+            Msg::SetSaveStats(save) => {
+                self.config.store_save_stats(save);
+                if !save {
+                    stats::clear();
+                    self.today_talk = TalkTime::default();
+                }
+            }
+            Msg::EventFilterInput(filter) => self.event_filter = filter,
+            Msg::CopyEvents => {
+                let all = events::recent()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                return iced::clipboard::write(all);
+            }
+            // what OBS, qpwgraph and pactl call the virtual mic
+            Msg::CopyVirtualMicName => {
+                let name = pulse::virtual_mic_name();
+                self.push_toast(Severity::Info, tr!("copied", text = name));
+                return iced::clipboard::write(name.to_string());
+            }
+            Msg::ResetStats => {
+                stats::clear();
+                self.session_talk = TalkTime::default();
+                self.today_talk = TalkTime::default();
+            }
+            Msg::HotKeyTestTick => {
+                if let Some(test) = &mut self.hotkey_test {
+                    test.seconds_left = test.seconds_left.saturating_sub(1);
+                    if test.seconds_left == 0 {
+                        self.hotkey_test = None;
+                    }
+                }
             }
         }
         Task::none()
     }
 
+    // only hotkeys are lost, the mic can still be controlled from the window and the tray
     fn global_shortcuts_fail(&mut self) {
-        let msg = "Failed to load global shortcuts. Push-to-talk will not work. Make sure you are using a Wayland compositor with a portal implementation that supports global shortcuts.";
-        self.backend = BackendState::Error(msg.into());
+        events::record(Level::Error, "Global shortcuts failed to start");
+        self.hotkey_error = Some(tr!("shortcuts-failed"));
+        self.sync_tray();
+        self.notify_ready();
     }
 
-    fn set_muted(&mut self, muted: bool) {
-        let BackendState::Loaded(b) = &mut self.backend else {
+    // tells systemd once everything was set up, whether it worked or not. the audio server is
+    // connected to before anything else
+    fn notify_ready(&mut self) {
+        let hotkeys_started = self.hotkey_backend.is_some() || self.hotkey_error.is_some();
+        if self.systemd_ready || !self.control_socket_ready || !hotkeys_started {
             return;
-        };
+        }
+        systemd::ready(self.snapshot().name());
+        self.systemd_ready = true;
+    }
 
-        let res = b.pa_state.set_mute(self.active && muted);
-        if let Err(e) = res {
-            eprintln!("Failed to set mute: {e}");
+    fn retry_hotkeys(&mut self) {
+        // the hotkey listener has given up, so start a new one
+        if self.hotkey_error.take().is_some() {
+            self.hotkey_attempt += 1;
+            self.hotkey_backend = None;
         }
-        self.muted = self.active && muted;
+        self.sync_tray();
     }
 
-    fn set_active(&mut self, active: bool) -> Task<Msg> {
-        let BackendState::Loaded(b) = &mut self.backend else {
-            return Task::none();
-        };
+    fn retry_backend(&mut self) -> Task<Msg> {
+        self.retry_hotkeys();
+        self.backend = load_backend(self.dry_run);
+        self.sync_fail_policy();
+        let restore = self.restore_session();
+        self.sync_tray();
+        restore
+    }
 
-        self.active = active;
-        if let Some(tray) = &b.tray {
-            block_on(tray.update(|tray| tray.set_ptt_enabled(active)));
+    fn hotkey_event(&mut self, action: &HotKeyAction, pressed: bool) -> Task<Msg> {
+        // while testing, events for the tested action must not change any state
+        if self
+            .hotkey_test
+            .as_ref()
+            .is_some_and(|t| &t.action == action)
+        {
+            return Task::done(Msg::HotKeyTestEvent(pressed));
+        }
+
+        self.cause = Some(Cause::HotKey);
+        match action {
+            HotKeyAction::Trigger => self.trigger_held = pressed,
+            HotKeyAction::WhisperTrigger => self.whisper_held = pressed,
+            HotKeyAction::ToggleActive if pressed => {
+                return Task::done(Msg::ToggleActive(Cause::HotKey));
+            }
+            HotKeyAction::NextProfile if pressed => return Task::done(Msg::NextProfile),
+            HotKeyAction::ToggleActive | HotKeyAction::NextProfile => return Task::none(),
         }
+        Task::done(Msg::SetMuted(!self.talking()))
+    }
 
-        Task::done(Msg::SetMuted(active))
+    // whether anything is asking for the mic to be open
+    fn talking(&self) -> bool {
+        self.trigger_held || self.whisper_held || self.button_held
     }
 
-    fn choose_microphone(&mut self, mic: &str) -> Task<Msg> {
+    fn talk_button(&mut self, pressed: bool) -> Task<Msg> {
+        self.cause = Some(Cause::Window);
+        if pressed {
+            // a click while latched closes the mic again
+            if self.button_latched {
+                self.button_latched = false;
+                self.button_held = false;
+            } else {
+                self.button_held = true;
+                self.button_pressed_at = Some(Instant::now());
+            }
+        } else {
+            // only the release of a press counts, not leaving the button afterwards
+            let Some(pressed_at) = self.button_pressed_at.take() else {
+                return Task::none();
+            };
+            // a quick click keeps the mic open, for when holding isn't practical
+            if pressed_at.elapsed() < LATCH_CLICK {
+                self.button_latched = true;
+            } else {
+                self.button_held = false;
+            }
+        }
+        Task::done(Msg::SetMuted(!self.talking()))
+    }
+
+    fn set_muted(&mut self, muted: bool) -> Task<Msg> {
+        let BackendState::Loaded(b) = &mut self.backend else {
+            return Task::none();
+        };
+
+        let muted = requested_mute(muted, self.active, self.config.when_disabled());
+        // nothing gets through a locked screen or a suspend
+        let muted = muted || self.locked || self.suspended;
+
+        // whisper at a lower volume, unless the normal trigger is held as well
+        let whisper =
+            self.active && !muted && self.whisper_held && !self.trigger_held && !self.button_held;
+        let whisper_res = if whisper {
+            b.pa_state.start_whisper(self.config.whisper_volume())
+        } else {
+            b.pa_state.stop_whisper()
+        };
+
+        // holding the mic open takes over from typing
+        if muted || self.trigger_held || self.whisper_held || self.button_held {
+            self.typing_mute.stop();
+        }
+        let res = b.pa_state.set_mute(muted || self.typing_mute.is_muting());
+
+        // pushing a toast borrows the whole app, so it waits until the backend is done
+        if let Err(e) = whisper_res {
+            self.push_toast(
+                Severity::Warning,
+                tr!("whisper-volume-failed", error = e.to_string()),
+            );
+        }
+        self.mute_failed = res.is_err();
+        if let Err(e) = res {
+            self.push_toast(Severity::Error, tr!("mute-failed", error = e.to_string()));
+        }
+        let hook = match (self.muted, muted) {
+            (true, false) => self.run_hook(Hook::Unmute),
+            (false, true) => self.run_hook(Hook::Mute),
+            _ => Task::none(),
+        };
+        if self.muted != muted {
+            let sound = if muted { Sound::Mute } else { Sound::Unmute };
+            if self.config.sound(sound) {
+                self.sounds.play(sound);
+            }
+            // holding the trigger changes it all the time, the rest only now and then
+            let show_osd = match self.cause {
+                Some(Cause::HotKey) => self.config.osd_on_press(),
+                Some(Cause::Tray | Cause::Window | Cause::Headset | Cause::Command) => {
+                    self.config.osd_on_toggle()
+                }
+                // switching push-to-talk on or off shows its own, and nobody sees a locked screen
+                Some(Cause::Auto | Cause::Lock) | None => false,
+            };
+            if self.active && show_osd {
+                self.osd.show(if muted {
+                    Indication::Muted
+                } else {
+                    Indication::Live
+                });
+            }
+        }
+        self.muted = muted;
+        self.update_overlay();
+        // whatever last asked for a change, or else the app itself
+        let cause = self.cause.take().unwrap_or(Cause::Auto);
+        self.update_open_timer(cause);
+
+        // quick taps would make the panel flicker, so only the state after a short while is shown
+        let tray_update = if self.tray_update_pending {
+            Task::none()
+        } else {
+            self.tray_update_pending = true;
+            Task::future(tokio::time::sleep(TRAY_UPDATE_DELAY)).map(|()| Msg::UpdateTrayMuted)
+        };
+
+        // dropping the handle aborts any watchdog that is still running
+        self.hold_watchdog = None;
+        let Some(max_hold) = self
+            .config
+            .max_hold()
+            .filter(|_| self.active && !self.muted)
+        else {
+            return Task::batch([tray_update, hook]);
+        };
+
+        // safety net for when the release event is lost and the mic would stay open forever
+        let (watchdog, handle) = Task::future(tokio::time::sleep(max_hold))
+            .map(|()| Msg::HoldTimeout)
+            .abortable();
+        self.hold_watchdog = Some(handle.abort_on_drop());
+        Task::batch([tray_update, hook, watchdog])
+    }
+
+    // however the mic got opened, the timer starts then and stops once it is muted again
+    fn update_open_timer(&mut self, cause: Cause) {
+        if self.active && !self.muted {
+            if self.open_since.is_none() {
+                self.open_since = Some(Instant::now());
+                if self.config.activity_history() {
+                    self.history.opened(cause);
+                }
+            }
+        } else if let Some(since) = self.open_since.take() {
+            if let Some(session) = self.history.closed(cause)
+                && self.config.activity_log()
+            {
+                activity::log(session);
+            }
+            self.hot_mic_warnings = 0;
+            let open_for = since.elapsed();
+            self.session_talk.add(open_for);
+            if self.config.save_stats() {
+                self.today_talk = stats::record(open_for);
+            }
+        }
+        self.update_tray_open_for();
+    }
+
+    // reminds about a mic left open, again every time as much time has passed. quick presses
+    // never get anywhere near the threshold
+    fn check_hot_mic(&mut self) {
+        let (Some(since), Some(after)) = (self.open_since, self.config.hot_mic_warning()) else {
+            return;
+        };
+        let due = since.elapsed().as_secs() / after.as_secs();
+        if due <= self.hot_mic_warnings {
+            return;
+        }
+
+        self.hot_mic_warnings = due;
+        let minutes = since.elapsed().as_secs() / 60;
+        events::record(
+            Level::Warning,
+            format!("Microphone open for {minutes} minutes"),
+        );
+        let _ = Notification::new()
+            .appname("Global Push-to-Talk")
+            .summary(&tr!("hot-mic", minutes = minutes))
+            .show();
+    }
+
+    fn open_for(&self) -> Option<String> {
+        self.open_since.map(|since| format_elapsed(since.elapsed()))
+    }
+
+    fn update_tray_open_for(&self) {
+        if let Some(tray) = &self.tray {
+            let open_for = self.open_for();
+            block_on(tray.update(|tray| tray.set_open_for(open_for)));
+        }
+    }
+
+    fn overlay_state(&self) -> OverlayState {
+        match (self.active, self.muted) {
+            // typing keeps it muted for a moment
+            (true, false) if self.typing_mute.is_muting() => OverlayState::Muted,
+            (true, false) if matches!(self.backend, BackendState::Loaded(_)) => {
+                OverlayState::Transmitting
+            }
+            (true, _) if self.config.overlay_show() == OverlayShow::WhileActive => {
+                OverlayState::Muted
+            }
+            _ => OverlayState::Hidden,
+        }
+    }
+
+    fn update_overlay(&self) {
+        if let Some(overlay) = &self.overlay {
+            overlay.set_state(self.overlay_state());
+        }
+    }
+
+    // starts or stops the overlay to match the config
+    fn sync_overlay(&mut self) {
+        match (self.overlay.is_some(), self.config.overlay()) {
+            (false, true) => {
+                self.overlay = Some(Overlay::spawn(self.overlay_style(), self.overlay_state()));
+            }
+            // dropping the handle stops its thread
+            (true, false) => self.overlay = None,
+            (true, true) => {
+                if let Some(overlay) = &self.overlay {
+                    overlay.set_style(self.overlay_style());
+                }
+                self.update_overlay();
+            }
+            (false, false) => (),
+        }
+    }
+
+    fn overlay_style(&self) -> overlay::Style {
+        let rgb = |which| {
+            let [r, g, b, _] = self.indicator_color(which).into_rgba8();
+            [r, g, b]
+        };
+        overlay::Style {
+            corner: self.config.overlay_corner(),
+            margin: self.config.overlay_margin(),
+            scale: self.config.overlay_scale(),
+            opacity: self.config.overlay_opacity(),
+            colors: IconColors {
+                muted: rgb(IndicatorColor::OverlayMuted),
+                transmitting: rgb(IndicatorColor::OverlayTransmitting),
+                disabled: rgb(IndicatorColor::Disabled),
+            },
+            output: self.config.overlay_output().map(ToString::to_string),
+        }
+    }
+
+    // the colors from the config, or else the theme's danger and success colors
+    fn indicator_color(&self, which: IndicatorColor) -> Color {
+        if let Some([r, g, b]) = self.config.color(which) {
+            return Color::from_rgb8(r, g, b);
+        }
+
+        // iced falls back to the light theme as well when none is picked
+        let theme = self.effective_theme().unwrap_or(Theme::Light);
+        let palette = theme.extended_palette();
+        match which {
+            IndicatorColor::Muted => palette.danger.base.color,
+            IndicatorColor::Transmitting => palette.success.base.color,
+            IndicatorColor::Disabled => palette.secondary.strong.color,
+            // the overlay's follow the mic's
+            IndicatorColor::OverlayMuted => self.indicator_color(IndicatorColor::Muted),
+            IndicatorColor::OverlayTransmitting => {
+                self.indicator_color(IndicatorColor::Transmitting)
+            }
+        }
+    }
+
+    // the color of the mic right now
+    fn current_indicator_color(&self) -> Color {
+        self.indicator_color(if !self.active {
+            IndicatorColor::Disabled
+        } else if self.muted {
+            IndicatorColor::Muted
+        } else {
+            IndicatorColor::Transmitting
+        })
+    }
+
+    fn icon_colors(&self) -> IconColors {
+        let rgb = |which| {
+            let [r, g, b, _] = self.indicator_color(which).into_rgba8();
+            [r, g, b]
+        };
+        IconColors {
+            muted: rgb(IndicatorColor::Muted),
+            transmitting: rgb(IndicatorColor::Transmitting),
+            disabled: rgb(IndicatorColor::Disabled),
+        }
+    }
+
+    fn apply_colors(&mut self) {
+        self.sync_overlay();
+        self.update_tray_info();
+    }
+
+    fn update_tray_muted(&mut self) {
+        self.tray_update_pending = false;
+        if let Some(tray) = &self.tray {
+            let (muted, error) = (self.muted, self.tray_error());
+            block_on(tray.update(|tray| {
+                tray.set_muted(muted);
+                tray.set_error(error);
+            }));
+        }
+    }
+
+    // something is broken, but push-to-talk may still partly work
+    fn tray_error(&self) -> bool {
+        self.mute_failed || self.hotkey_error.is_some()
+    }
+
+    fn hold_timeout(&mut self) -> Task<Msg> {
+        if !self.active || self.muted {
+            return Task::none();
+        }
+
+        let minutes = self.config.max_hold().unwrap_or_default().as_secs() / 60;
+        let _ = Notification::new()
+            .appname("Global Push-to-Talk")
+            .summary(&tr!("auto-closed", minutes = minutes))
+            .show();
+        self.set_muted(true)
+    }
+
+    fn session_locked(&mut self, locked: bool) -> Task<Msg> {
+        if locked == self.locked {
+            return Task::none();
+        }
+
+        if locked {
+            let behavior = self.config.on_lock();
+            if behavior == LockBehavior::Ignore {
+                return Task::none();
+            }
+            events::record(Level::Info, "Screen locked, muting the microphone");
+            self.open_before_lock = (self.active && !self.muted).then_some(self.button_latched);
+            // held keys may be released behind the lock screen without anyone noticing
+            self.trigger_held = false;
+            self.whisper_held = false;
+            self.button_held = false;
+            self.button_latched = false;
+            self.button_pressed_at = None;
+            self.locked = true;
+            self.cause = Some(Cause::Lock);
+            // also drops the hold watchdog
+            return self.set_muted(true);
+        }
+
+        self.locked = false;
+        events::record(Level::Info, "Screen unlocked");
+        let open_before = self.open_before_lock.take();
+        self.cause = Some(Cause::Lock);
+        if !self.active {
+            // back to the resting state, which may be open
+            return self.set_muted(true);
+        }
+        match open_before {
+            Some(latched) if self.config.on_lock() == LockBehavior::Restore => {
+                self.button_latched = latched;
+                self.set_muted(false)
+            }
+            _ => {
+                self.cause = None;
+                Task::none()
+            }
+        }
+    }
+
+    fn suspending(&mut self) -> Task<Msg> {
+        events::record(Level::Info, "Suspending, muting the microphone");
+        self.suspended = true;
+        // releases during the suspend never arrive
+        self.trigger_held = false;
+        self.whisper_held = false;
+        self.button_held = false;
+        self.button_latched = false;
+        self.button_pressed_at = None;
+        let task = self.set_muted(true);
+        // the connection may be cut off before the server got to it otherwise
+        if let BackendState::Loaded(b) = &self.backend {
+            b.pa_state.flush();
+        }
+        task
+    }
+
+    fn resumed(&mut self) -> Task<Msg> {
+        events::record(Level::Info, "Resumed from suspend");
+        self.suspended = false;
+
+        if let BackendState::Loaded(b) = &self.backend
+            && b.pa_state.is_connected()
+        {
+            // it goes away with its microphone when that was unplugged in the meantime
+            if b.pa_state.get_active_source_name().is_some() && !b.pa_state.virtual_mic_exists() {
+                events::record(
+                    Level::Warning,
+                    "The virtual microphone disappeared during suspend",
+                );
+                return self.recreate_virtual_mic();
+            }
+            // the hardware may remember another mute state, so it's set again
+            return self.set_muted(true);
+        }
+
+        events::record(
+            Level::Warning,
+            "Lost the audio server during suspend, reconnecting",
+        );
+        self.backend = load_backend(self.dry_run);
+        self.sync_fail_policy();
+        let restore = self.restore_session();
+        self.sync_tray();
+        restore
+    }
+
+    // the panic hook and new virtual mics follow the config, except in a dry run, where a panic
+    // changes nothing either
+    fn sync_fail_policy(&mut self) {
+        let policy = self.config.fail_policy();
+        crash::set_policy((!self.dry_run).then_some(policy));
+        if let BackendState::Loaded(b) = &mut self.backend {
+            b.pa_state.set_fail_open(policy == FailPolicy::Open);
+        }
+    }
+
+    fn set_active(&mut self, active: bool) -> Task<Msg> {
+        if !matches!(self.backend, BackendState::Loaded(_)) {
+            return Task::none();
+        }
+
+        let (attention, hook, notification) = if self.active == active {
+            (Task::none(), Task::none(), Task::none())
+        } else {
+            let hook = self.run_hook(if active { Hook::Enable } else { Hook::Disable });
+            if self.config.osd_on_toggle() {
+                // turning it on mutes the mic until the trigger is pressed
+                self.osd.show(if active {
+                    Indication::Muted
+                } else {
+                    Indication::Disabled
+                });
+            }
+            (self.request_attention(), hook, self.notify_toggle(active))
+        };
+        self.active = active;
+        self.config.store_active(active);
+        // the talk button is hidden while disabled, so it can't stay latched
+        self.button_held = false;
+        self.button_latched = false;
+        if let Some(tray) = &self.tray {
+            let resting_muted = self.config.when_disabled() == RestingState::Muted;
+            block_on(tray.update(|tray| {
+                tray.set_ptt_enabled(active);
+                tray.set_resting_muted(resting_muted);
+            }));
+        }
+
+        Task::batch([
+            attention,
+            hook,
+            notification,
+            Task::done(Msg::SetMuted(active)),
+        ])
+    }
+
+    // confirms a change made with a hotkey, which may have happened with the window out of sight.
+    // the notification replaces the previous one, so that toggling quickly doesn't stack them
+    fn notify_toggle(&mut self, active: bool) -> Task<Msg> {
+        if self.cause != Some(Cause::HotKey) || !self.config.notify_on_toggle() {
+            return Task::none();
+        }
+
+        let mut notification = Notification::new();
+        notification.appname("Global Push-to-Talk");
+        if active {
+            notification
+                .summary(&tr!("ptt-enabled"))
+                .action(DISABLE, &tr!("disable"));
+        } else {
+            notification
+                .summary(&tr!("ptt-disabled"))
+                .action(OPEN_WINDOW, &tr!("open-window"));
+        }
+        if let Some(id) = self.toggle_notification {
+            notification.id(id);
+        }
+        let Ok(handle) = notification.show() else {
+            return Task::none();
+        };
+        self.toggle_notification = Some(handle.id());
+
+        // waiting for the notification to go away blocks
+        Task::future(async move {
+            tokio::task::spawn_blocking(move || {
+                let mut clicked = None;
+                handle.wait_for_action(|action| {
+                    clicked = match action {
+                        DISABLE => Some(Msg::Request(Request::SetActive(false))),
+                        OPEN_WINDOW => Some(Msg::ShowWindow),
+                        _ => None,
+                    };
+                });
+                clicked
+            })
+            .await
+            .ok()
+            .flatten()
+        })
+        .then(|clicked| clicked.map_or_else(Task::none, Task::done))
+    }
+
+    // runs the command the user set up for this change, if any
+    fn run_hook(&self, hook: Hook) -> Task<Msg> {
+        let Some(command) = self.config.hook(hook) else {
+            return Task::none();
+        };
+        Task::perform(
+            self.hooks.clone().run(hook, command.to_string()),
+            move |res| Msg::HookFinished(hook, res.map_err(|e| e.to_string())),
+        )
+    }
+
+    // points at an open but unfocused window, since the change came from the tray or a hotkey.
+    // rapid toggling only asks once in a while
+    fn request_attention(&mut self) -> Task<Msg> {
+        let window_exists = self.window_open || self.compact_window.is_some();
+        if !self.config.attention_on_change() || !window_exists || self.focused_window.is_some() {
+            return Task::none();
+        }
+        let now = Instant::now();
+        if self
+            .last_attention
+            .is_some_and(|last| now.duration_since(last) < ATTENTION_INTERVAL)
+        {
+            return Task::none();
+        }
+
+        self.last_attention = Some(now);
+        self.flash_until = Some(now + FLASH_DURATION);
+        let window = match self.compact_window {
+            Some(id) => Task::done(Some(id)),
+            None => iced::window::latest(),
+        };
+        window.then(|res| match res {
+            Some(id) => {
+                iced::window::request_user_attention(id, Some(UserAttention::Informational))
+            }
+            None => Task::none(),
+        })
+    }
+
+    fn choose_microphone(&mut self, mic: &str) -> Task<Msg> {
         let BackendState::Loaded(b) = &mut self.backend else {
             return Task::none();
         };
 
         let is_first_time = b.pa_state.get_active_source_name().is_none();
-        b.pa_state.set_virtual_mic(mic);
+        if let Err(e) = b.pa_state.set_virtual_mic(mic) {
+            return Task::done(Msg::PushToast(Severity::Error, e.to_string()));
+        }
+        events::record(Level::Info, format!("Using microphone {mic}"));
+        self.config.store_microphone(mic);
+        self.update_tray_info();
 
         // enable ptt automatically after choosing microphone for the first time
-        if is_first_time {
+        if is_first_time && self.config.auto_enable() {
+            let _ = Notification::new()
+                .appname("Global Push-to-Talk")
+                .summary(&tr!("auto-enabled"))
+                .body(&tr!("auto-enabled-body"))
+                .show();
             Task::done(Msg::SetActive(true))
         } else {
-            Task::none()
+            // the new virtual mic starts out muted
+            Task::done(Msg::SetMuted(true))
         }
     }
 
-    fn finish_hotkey_recording(&mut self, hk_string: &str) -> Task<Msg> {
-        let Some(recording_hotkey) = self.recording_hotkey.take() else {
+    // picks up where the last session left off, if its microphone is still around
+    fn restore_session(&mut self) -> Task<Msg> {
+        let BackendState::Loaded(b) = &mut self.backend else {
             return Task::none();
         };
 
-        let Ok(new_hk) = HotKey::from_str(hk_string) else {
+        let Some(mic) = self.config.microphone() else {
             return Task::none();
         };
+        if !b
+            .pa_state
+            .get_input_devices()
+            .iter()
+            .any(|dev| dev.name == mic)
+        {
+            return Task::none();
+        }
+
+        if let Err(e) = b.pa_state.set_virtual_mic(&mic) {
+            return Task::done(Msg::PushToast(Severity::Error, e.to_string()));
+        }
+        self.update_tray_info();
+        Task::done(Msg::SetActive(self.config.active()))
+    }
+
+    fn autostart_changed(&mut self, res: Result<bool, String>) {
+        match res {
+            Ok(enabled) => {
+                self.autostart = enabled;
+                self.config.store_autostart(enabled);
+            }
+            Err(e) => {
+                let _ = Notification::new()
+                    .appname("Global Push-to-Talk")
+                    .summary(&tr!("autostart-failed"))
+                    .body(&e)
+                    .show();
+            }
+        }
+    }
 
-        // get current hotkeys
-        let def = HotKeyConfig::default();
-        let mut hotkeys = HotKeyConfig {
-            trigger: HotKey::from_str(&self.hk_descriptions.trigger).unwrap_or(def.trigger),
-            toggle_active: HotKey::from_str(&self.hk_descriptions.toggle_active)
-                .unwrap_or(def.toggle_active),
+    fn check_virtual_mic(&mut self) -> Task<Msg> {
+        let BackendState::Loaded(b) = &self.backend else {
+            return Task::none();
         };
 
-        match recording_hotkey {
-            HotKeyAction::Trigger => hotkeys.trigger = new_hk,
-            HotKeyAction::ToggleActive => hotkeys.toggle_active = new_hk,
+        // there's only something to lose once a microphone has been chosen
+        let missing =
+            b.pa_state.get_active_source_name().is_some() && !b.pa_state.virtual_mic_exists();
+        if !missing || self.virtual_mic_missing {
+            self.virtual_mic_missing = missing;
+            return Task::none();
         }
 
-        if let Some(tx) = self.change_hotkey_tx.clone() {
-            Task::future(async move { tx.send(hotkeys).await }).discard()
-        } else {
-            Task::none()
+        events::record(Level::Warning, "The virtual microphone disappeared");
+        self.virtual_mic_missing = true;
+        if self.config.recreate_virtual_mic() {
+            self.push_toast(Severity::Warning, tr!("virtual-mic-recreated"));
+            return self.recreate_virtual_mic();
         }
+        Task::none()
     }
 
-    fn show_window(&mut self) -> Task<Msg> {
-        let size = match self.backend {
-            BackendState::Loaded(_) => (600, 300),
-            BackendState::Error(_) => (280, 180),
+    fn recreate_virtual_mic(&mut self) -> Task<Msg> {
+        let BackendState::Loaded(b) = &mut self.backend else {
+            return Task::none();
         };
-        iced::window::latest().then(move |res| {
-            if let Some(id) = res {
-                Task::batch([
-                    iced::window::request_user_attention(id, Some(UserAttention::Informational)),
-                    iced::window::gain_focus(id),
-                ])
-            } else {
-                iced::window::open(Settings {
-                    exit_on_close_request: false,
-                    size: size.into(),
-                    resizable: true,
-                    decorations: true,
-                    platform_specific: PlatformSpecific {
-                        application_id: APP_ID.to_string(),
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                })
-                .1
-                .discard()
+        let Some(mic) = b.pa_state.get_active_source_name().map(ToString::to_string) else {
+            return Task::none();
+        };
+
+        if let Err(e) = b.pa_state.set_virtual_mic(&mic) {
+            return Task::done(Msg::PushToast(Severity::Error, e.to_string()));
+        }
+        self.virtual_mic_missing = false;
+        // the new one starts out muted, which may not be where things were
+        Task::done(Msg::SetMuted(!self.talking()))
+    }
+
+    // picks up changes made to the config file by hand
+    fn check_config(&mut self) -> Task<Msg> {
+        let modified = config_modified();
+        if modified == self.config_modified {
+            return Task::none();
+        }
+        self.config_modified = modified;
+
+        let new = match Config::load() {
+            Ok(new) => new,
+            Err(e) => {
+                let e = config_error_message(&e);
+                let _ = Notification::new()
+                    .appname("Global Push-to-Talk")
+                    .summary(&tr!("config-reload-failed"))
+                    .body(&e)
+                    .show();
+                self.config_error = Some(e);
+                return Task::none();
             }
-        })
+        };
+        self.config_error = None;
+
+        // writes made by the app itself end up here too, but don't differ from what is in memory
+        if new == self.config {
+            return Task::none();
+        }
+
+        let old = std::mem::replace(&mut self.config, new);
+        self.apply_config(&old)
+    }
+
+    // brings the running state in line with the config after it changed as a whole
+    fn apply_config(&mut self, old: &Config) -> Task<Msg> {
+        let mut tasks = Vec::new();
+        let hotkeys = self.config.hotkeys();
+        if hotkeys != self.hotkeys
+            && let Some(tx) = self.change_hotkey_tx.clone()
+        {
+            tasks.push(Task::future(async move { tx.send(hotkeys).await }).discard());
+        }
+        if let BackendState::Loaded(b) = &self.backend
+            && let Some(mic) = self.config.microphone()
+            && b.pa_state.get_active_source_name() != Some(mic.as_str())
+        {
+            tasks.push(Task::done(Msg::ChooseMicrophone(mic)));
+        }
+        if self.config.active() != old.active() {
+            tasks.push(Task::done(Msg::SetActive(self.config.active())));
+        }
+
+        self.sync_fail_policy();
+        self.whisper_volume = self.config.whisper_volume();
+        self.ui_scale = self.config.ui_scale();
+        self.sync_overlay();
+        self.update_tray_profiles();
+        self.update_tray_info();
+        Task::batch(tasks)
     }
 
-    fn close_window() -> Task<Msg> {
+    fn switch_profile(&mut self, name: &str) -> Task<Msg> {
+        let old = self.config.clone();
+        self.config.store_active_profile(name);
+        self.apply_config(&old)
+    }
+
+    // cycles through the profiles, for switching without looking at the window
+    fn next_profile(&mut self) -> Task<Msg> {
+        let names = self.config.profile_names();
+        let next = names
+            .iter()
+            .position(|name| name == self.config.active_profile())
+            .map_or(0, |i| (i + 1) % names.len());
+        let Some(name) = names.get(next) else {
+            return Task::none();
+        };
+
         let _ = Notification::new()
             .appname("Global Push-to-Talk")
-            .summary("Global Push-to-Talk is running in the background")
+            .summary(&tr!("profile-switched", name = name.as_str()))
             .show();
-        iced::window::latest().and_then(iced::window::close)
+        self.switch_profile(name)
     }
 
-    fn exit(&mut self) {
-        if let BackendState::Loaded(b) = &mut self.backend {
-            b.pa_state.remove_virtual_mic();
+    fn submit_profile_edit(&mut self) -> Task<Msg> {
+        let Some((edit, name)) = self.profile_edit.take() else {
+            return Task::none();
+        };
+
+        let name = name.trim();
+        let old = self.config.clone();
+        let res = match edit {
+            ProfileEdit::Create => self.config.create_profile(name),
+            ProfileEdit::Rename => {
+                let active = self.config.active_profile().to_string();
+                self.config.rename_profile(&active, name)
+            }
+        };
+        if let Err(e) = res {
+            profile_error(&e);
         }
-        exit(0);
+        self.apply_config(&old)
     }
 
-    pub fn theme(&self, _: Id) -> Option<Theme> {
-        self.theme.clone()
+    fn delete_profile(&mut self) -> Task<Msg> {
+        let old = self.config.clone();
+        let active = self.config.active_profile().to_string();
+        if let Err(e) = self.config.delete_profile(&active) {
+            profile_error(&e);
+        }
+        self.apply_config(&old)
     }
 
-    pub fn subscription(&self) -> Subscription<Msg> {
-        Subscription::batch([
-            close_requests().map(|_| Msg::Close),
-            Subscription::run(hotkeys),
-            if self.recording_hotkey.is_some() {
-                Self::record_hotkey()
+    fn update_tray_profiles(&self) {
+        if let Some(tray) = &self.tray {
+            let names = self.config.profile_names();
+            let active = self.config.active_profile().to_string();
+            block_on(tray.update(|tray| tray.set_profiles(names, &active)));
+        }
+    }
+
+    // brings the tray up to date, e.g. after it was spawned or the backend changed
+    fn sync_tray(&self) {
+        if let Some(tray) = &self.tray {
+            let (active, muted, error) = (self.active, self.muted, self.tray_error());
+            let resting_muted = self.config.when_disabled() == RestingState::Muted;
+            let backend_error = match &self.backend {
+                BackendState::Loaded(_) => None,
+                BackendState::Error(e) => Some(e.clone()),
+            };
+            block_on(tray.update(|tray| {
+                tray.set_ptt_enabled(active);
+                tray.set_muted(muted);
+                tray.set_resting_muted(resting_muted);
+                tray.set_error(error);
+                tray.set_backend_error(backend_error);
+            }));
+        }
+        self.update_tray_profiles();
+        self.update_tray_info();
+    }
+
+    // the tray's tooltip shows the selected mic and how to talk
+    fn update_tray_info(&self) {
+        if let Some(tray) = &self.tray {
+            let mic = match &self.backend {
+                BackendState::Loaded(b) => get_selected_mic(b).map(|dev| dev.description),
+                BackendState::Error(_) => None,
+            };
+            let trigger = self.hk_descriptions.trigger.clone();
+            let builtin_icons = self.config.builtin_tray_icons();
+            // the panel has its own theme, so only colors the user picked are passed on
+            let colors = self.config.has_custom_colors().then(|| self.icon_colors());
+            // the portal only gives us descriptions, not the bindings themselves
+            let toggle_shortcut = if using_wayland() {
+                Vec::new()
             } else {
-                Subscription::none()
-            },
-        ])
+                self.hotkeys.toggle_active.shortcut()
+            };
+            block_on(tray.update(|tray| {
+                tray.set_info(mic, trigger, toggle_shortcut);
+                tray.set_builtin_icons(builtin_icons);
+                tray.set_colors(colors);
+            }));
+        }
+    }
+
+    fn record_hotkey_press(&mut self, key: String, modifiers: String) {
+        let Some(recording) = &mut self.recording_hotkey else {
+            return;
+        };
+
+        // modifiers only count when held before the first key
+        if recording.keys.is_empty() {
+            recording.modifiers = modifiers;
+        }
+        if !recording.keys.contains(&key) {
+            recording.keys.push(key);
+        }
+    }
+
+    // rule: recording finishes as soon as any of the held keys is released
+    fn record_hotkey_release(&mut self) {
+        let Some(recording) = &self.recording_hotkey else {
+            return;
+        };
+
+        let hk_string = match recording.keys.as_slice() {
+            [] => return,
+            [key] if !recording.modifiers.is_empty() => {
+                format!("{}+{key}", recording.modifiers)
+            }
+            // several keys make up a chord, which can't have modifiers
+            keys => keys.join("+"),
+        };
+        self.capture_hotkey(hk_string);
+    }
+
+    // stops listening and waits for the combination to be confirmed
+    fn capture_hotkey(&mut self, hk_string: String) {
+        if let Some(recording) = &mut self.recording_hotkey
+            && recording.captured.is_none()
+            && !hk_string.is_empty()
+        {
+            recording.captured = Some(hk_string);
+        }
+    }
+
+    // the binding the captured combination would become, or why it can't be used
+    fn validate_captured(&self, recording: &HotKeyRecording) -> Option<Result<Binding, String>> {
+        let captured = recording.captured.as_deref()?;
+        let binding = match Binding::from_str(captured) {
+            Ok(binding) => binding,
+            Err(e) => return Some(Err(tr!("hotkey-invalid", error = e.to_string()))),
+        };
+
+        Some(match self.taken_by(&recording.action, &binding) {
+            Some(action) => Err(tr!("hotkey-duplicate", action = action.name())),
+            None => Ok(binding),
+        })
+    }
+
+    // another action that already has `binding`
+    fn taken_by(&self, action: &HotKeyAction, binding: &Binding) -> Option<HotKeyAction> {
+        // any number of actions can go without
+        if *binding == Binding::Unbound {
+            return None;
+        }
+        HotKeyAction::ALL
+            .into_iter()
+            .filter(|other| other != action)
+            .find(|other| other.binding(&self.hotkeys) == binding)
+    }
+
+    fn finish_hotkey_recording(&mut self) -> Task<Msg> {
+        let Some(recording) = self.recording_hotkey.take() else {
+            return Task::none();
+        };
+        let Some(Ok(new_hk)) = self.validate_captured(&recording) else {
+            // keep showing the problem
+            self.recording_hotkey = Some(recording);
+            return Task::none();
+        };
+
+        let mut hotkeys = self.hotkeys.clone();
+        *recording.action.binding_mut(&mut hotkeys) = new_hk;
+        self.change_hotkeys(hotkeys)
+    }
+
+    // registered by the hotkey thread, which also saves them once that worked
+    fn change_hotkeys(&self, hotkeys: HotKeyConfig<Binding>) -> Task<Msg> {
+        if let Some(tx) = self.change_hotkey_tx.clone() {
+            Task::future(async move { tx.send(hotkeys).await }).discard()
+        } else {
+            Task::none()
+        }
+    }
+
+    // the hotkeys with `action` bound from the command line, or unbound without a binding
+    fn rebind(
+        &self,
+        action: &HotKeyAction,
+        binding: Option<&str>,
+    ) -> Result<HotKeyConfig<Binding>, String> {
+        if using_wayland() {
+            return Err("On Wayland, hotkeys are managed by the compositor. \
+                 Change them in the system settings instead."
+                .into());
+        }
+        if self.change_hotkey_tx.is_none() {
+            return Err("Hotkeys are not available".into());
+        }
+
+        let binding = match binding {
+            Some(binding) => {
+                Binding::from_str(binding).map_err(|e| format!("Invalid hotkey: {e}"))?
+            }
+            None => Binding::Unbound,
+        };
+        if let Some(other) = self.taken_by(action, &binding) {
+            return Err(format!("{binding} is already bound to {}", other.name()));
+        }
+
+        let mut hotkeys = self.hotkeys.clone();
+        *action.binding_mut(&mut hotkeys) = binding;
+        Ok(hotkeys)
+    }
+
+    fn run_command(&mut self, request: Request) -> Task<Msg> {
+        let msg = match request {
+            Request::Show => Msg::ShowWindow,
+            Request::ToggleActive => Msg::ToggleActive(Cause::Command),
+            Request::ToggleMute => Msg::ToggleMute(Cause::Command),
+            Request::SetActive(active) => {
+                self.cause = Some(Cause::Command);
+                self.watched_overridden = true;
+                Msg::SetActive(active)
+            }
+            Request::SetMuted(muted) => {
+                self.cause = Some(Cause::Command);
+                Msg::SetMuted(muted)
+            }
+            Request::SelectMicrophone(query) => return self.select_microphone(&query),
+            Request::Bind { action, binding } => {
+                return match self.rebind(&action.into(), binding.as_deref()) {
+                    Ok(hotkeys) => self.change_hotkeys(hotkeys),
+                    Err(e) => {
+                        events::record(Level::Warning, e);
+                        Task::none()
+                    }
+                };
+            }
+            Request::Status | Request::Subscribe | Request::ListDevices => return Task::none(),
+            Request::TriggerPress => Msg::HotKeyEvent(HotKeyAction::Trigger, true),
+            Request::TriggerRelease => Msg::HotKeyEvent(HotKeyAction::Trigger, false),
+            Request::TriggerTap(ms) => {
+                return Task::done(Msg::HotKeyEvent(HotKeyAction::Trigger, true)).chain(
+                    Task::future(tokio::time::sleep(Duration::from_millis(ms)))
+                        .map(|()| Msg::HotKeyEvent(HotKeyAction::Trigger, false)),
+                );
+            }
+        };
+        self.update(msg)
+    }
+
+    // picks a source by its exact name, or else the only one whose name or description contains
+    // `query`
+    fn select_microphone(&mut self, query: &str) -> Task<Msg> {
+        let devices = self.devices();
+        let e = match ipc::find_microphone(&devices, query) {
+            Ok(dev) => return Task::done(Msg::ChooseMicrophone(dev.name.clone())),
+            Err(e) => e,
+        };
+
+        events::record(Level::Warning, e.to_string());
+        let body = match &e {
+            MatchError::NoMatch(_) => tr!("mic-not-found-body", query = query),
+            MatchError::Ambiguous(_, matches) => tr!(
+                "mic-ambiguous-body",
+                query = query,
+                matches = matches.join(", ")
+            ),
+        };
+        let _ = Notification::new()
+            .appname("Global Push-to-Talk")
+            .summary(&tr!("mic-not-found"))
+            .body(&body)
+            .show();
+        Task::none()
+    }
+
+    // starts or stops listening for remote control to match the config
+    fn sync_remote_control(&mut self) -> Task<Msg> {
+        self.remote_control = None;
+        drop_remote_subscribers(&mut self.subscribers);
+        if !self.config.remote_control() {
+            return Task::none();
+        }
+
+        let Some(token) = self.config.remote_token() else {
+            events::record(
+                Level::Error,
+                "Remote control is on, but there is no remote_token to check requests against",
+            );
+            return Task::none();
+        };
+        let (task, handle) = remote::listen(
+            self.config.remote_address(),
+            self.config.remote_port(),
+            token.to_string(),
+        )
+        .abortable();
+        self.remote_control = Some(handle.abort_on_drop());
+        task
+    }
+
+    fn sync_obs(&mut self) -> Task<Msg> {
+        self.obs = None;
+        self.obs_status = None;
+        if !self.config.obs_integration() {
+            return Task::none();
+        }
+
+        let Some(input) = self.config.obs_input() else {
+            events::record(
+                Level::Error,
+                "The OBS integration is on, but there is no obs_input to mute",
+            );
+            return Task::none();
+        };
+        let settings = obs::Settings {
+            host: self.config.obs_host(),
+            port: self.config.obs_port(),
+            password: self.config.obs_password().map(ToString::to_string),
+            input: input.to_string(),
+        };
+        let (task, handle) =
+            Task::stream(obs::sync(settings, self.published.subscribe())).abortable();
+        self.obs = Some(handle.abort_on_drop());
+        task
+    }
+
+    fn sync_mqtt(&mut self) -> Task<Msg> {
+        self.mqtt = None;
+        self.mqtt_status = None;
+        if !self.config.mqtt() {
+            return Task::none();
+        }
+
+        let settings = mqtt::Settings {
+            broker: self.config.mqtt_broker(),
+            username: self.config.mqtt_username().map(ToString::to_string),
+            password: self.config.mqtt_password().map(ToString::to_string),
+            prefix: self.config.mqtt_topic_prefix(),
+            commands: self.config.mqtt_commands(),
+            discovery: self.config.mqtt_discovery(),
+        };
+        let (task, handle) =
+            Task::stream(mqtt::sync(settings, self.published.subscribe())).abortable();
+        self.mqtt = Some(handle.abort_on_drop());
+        task
+    }
+
+    fn sync_headset(&mut self) -> Task<Msg> {
+        self.headset = None;
+        self.headset_scan = None;
+        if !self.config.headset_button() {
+            return Task::none();
+        }
+
+        let only = self.config.headset().map(ToString::to_string);
+        let (task, handle) =
+            Task::stream(headset::watch(only, self.published.subscribe())).abortable();
+        self.headset = Some(handle.abort_on_drop());
+        task
+    }
+
+    fn sync_typing(&mut self) -> Task<Msg> {
+        self.typing = None;
+        if !self.config.suppress_typing() {
+            return self.typing_hold_off_passed();
+        }
+
+        let (task, handle) = Task::stream(typing::watch()).abortable();
+        self.typing = Some(handle.abort_on_drop());
+        task
+    }
+
+    // mutes the open mic until the keyboard was quiet for a while, unless something holds it open.
+    // the trigger is always held by the time it matters, so its own key never mutes
+    fn typing(&mut self) -> Task<Msg> {
+        let held = self.trigger_held || self.whisper_held || self.button_held;
+        if !self.config.suppress_typing() {
+            return Task::none();
+        }
+        let BackendState::Loaded(b) = &mut self.backend else {
+            return Task::none();
+        };
+
+        let hold_off = self.config.typing_hold_off();
+        if !self
+            .typing_mute
+            .key(Instant::now(), hold_off, !self.muted && !held)
+        {
+            return Task::none();
+        }
+        if let Err(e) = b.pa_state.set_mute(true) {
+            self.typing_mute.stop();
+            self.push_toast(Severity::Error, tr!("mute-failed", error = e.to_string()));
+            return Task::none();
+        }
+        self.update_overlay();
+        Task::future(tokio::time::sleep(hold_off)).map(|()| Msg::TypingHoldOffPassed)
+    }
+
+    // opens the mic again once no key was pressed for the hold-off
+    fn typing_hold_off_passed(&mut self) -> Task<Msg> {
+        if !self.typing_mute.is_muting() {
+            return Task::none();
+        }
+        if self.config.suppress_typing()
+            && let Some(left) = self.typing_mute.remaining(Instant::now())
+        {
+            return Task::future(tokio::time::sleep(left)).map(|()| Msg::TypingHoldOffPassed);
+        }
+
+        self.typing_mute.stop();
+        if let BackendState::Loaded(b) = &mut self.backend
+            && let Err(e) = b.pa_state.set_mute(self.muted)
+        {
+            self.push_toast(Severity::Error, tr!("mute-failed", error = e.to_string()));
+        }
+        self.update_overlay();
+        Task::none()
+    }
+
+    fn sync_watched_apps(&mut self) -> Task<Msg> {
+        self.watched_apps = None;
+        let watchlist = self.config.watched_apps();
+        // otherwise kept until the new watcher reports, so that editing the list doesn't count as
+        // a start
+        if watchlist.is_empty() {
+            self.watched_running.clear();
+            return Task::none();
+        }
+        let (task, handle) = Task::stream(apps::watch(watchlist)).abortable();
+        self.watched_apps = Some(handle.abort_on_drop());
+        task
+    }
+
+    // enables push-to-talk when a watched application starts, and disables it again after the
+    // last one quit, if wanted and it wasn't toggled by hand in between
+    fn watched_apps_changed(&mut self, running: Vec<String>) -> Task<Msg> {
+        let started = running
+            .iter()
+            .find(|app| !self.watched_running.contains(app))
+            .cloned();
+        let was_running = !self.watched_running.is_empty();
+        self.watched_running = running;
+
+        if let Some(app) = started {
+            self.watched_overridden = false;
+            if self.active {
+                return Task::none();
+            }
+            events::record(Level::Info, format!("{app} started, enabling push-to-talk"));
+            return self.set_active(true);
+        }
+
+        let all_quit = was_running && self.watched_running.is_empty();
+        if all_quit
+            && self.active
+            && !self.watched_overridden
+            && self.config.disable_with_watched_apps()
+        {
+            events::record(
+                Level::Info,
+                "The watched applications quit, disabling push-to-talk",
+            );
+            return self.set_active(false);
+        }
+        Task::none()
+    }
+
+    fn sync_pause_media(&mut self) -> Task<Msg> {
+        self.pause_media = None;
+        if !self.config.pause_media() {
+            return Task::none();
+        }
+
+        let settings = mpris::Settings {
+            players: self.config.pause_media_players(),
+            min_open: self.config.pause_media_after(),
+        };
+        let (task, handle) = Task::perform(
+            mpris::pause_while_transmitting(settings, self.published.subscribe()),
+            |()| Msg::None,
+        )
+        .abortable();
+        self.pause_media = Some(handle.abort_on_drop());
+        task
+    }
+
+    // tells subscribers, the bus, OBS and MQTT whenever the state changed, dropping subscribers that
+    // went away
+    fn publish_state(&mut self) -> Task<Msg> {
+        let state = self.snapshot();
+        if self.published_state.as_ref() == Some(&state) {
+            return Task::none();
+        }
+        self.published.send_replace(state.clone());
+        // the state at startup isn't news
+        if let Some(old) = &self.published_state {
+            let announcement = Announcement::of(&state);
+            if Announcement::of(old) != announcement && self.config.announce(announcement) {
+                self.announcer.announce(announcement);
+            }
+        }
+        systemd::status(state.name());
+        self.subscribers
+            .retain(|subscriber| subscriber.send(&state));
+        self.published_state = Some(state.clone());
+
+        let Some(connection) = &self.dbus else {
+            return Task::none();
+        };
+        Task::perform(dbus::publish(connection.clone(), state), |res| {
+            Msg::DBusPublished(res.map_err(|e| e.to_string()))
+        })
+        .chain(self.publish_tray_label())
+    }
+
+    // the state as text next to the tray icon, for hosts that draw labels
+    fn publish_tray_label(&self) -> Task<Msg> {
+        let Some(connection) = &self.dbus else {
+            return Task::none();
+        };
+        let label = self.config.tray_label().then(|| {
+            let label = tr!("tray-label", state = self.snapshot().name());
+            let guide = ["transmitting", "muted", "disabled"]
+                .map(|state| tr!("tray-label", state = state))
+                .into_iter()
+                .max_by_key(|label| label.chars().count())
+                .unwrap_or_default();
+            (label, guide)
+        });
+        Task::perform(dbus::publish_tray_label(connection.clone(), label), |res| {
+            Msg::DBusPublished(res.map_err(|e| e.to_string()))
+        })
+    }
+
+    // what list-devices is answered with
+    fn devices(&self) -> Vec<Device> {
+        let BackendState::Loaded(b) = &self.backend else {
+            return Vec::new();
+        };
+        let selected = b.pa_state.get_active_source_name();
+        b.pa_state
+            .get_input_devices()
+            .into_iter()
+            .map(|dev| Device {
+                selected: selected == Some(dev.name.as_str()),
+                is_default: dev.is_default,
+                name: dev.name,
+                description: dev.description,
+            })
+            .collect()
+    }
+
+    // what requests from other programs are answered with
+    fn snapshot(&self) -> Snapshot {
+        let microphone = match &self.backend {
+            BackendState::Loaded(b) => get_selected_mic(b).map(|dev| dev.description),
+            BackendState::Error(_) => None,
+        };
+        Snapshot {
+            active: self.active,
+            muted: self.muted,
+            microphone,
+        }
+    }
+
+    fn update_checked(&mut self, release: Option<Release>) {
+        // the check only runs once a day, so a release found earlier stays known
+        let Some(release) = release else {
+            return;
+        };
+        if self.update.as_ref() == Some(&release) {
+            return;
+        }
+
+        events::record(
+            Level::Info,
+            format!("Version {} is available", release.version),
+        );
+        let _ = Notification::new()
+            .appname("Global Push-to-Talk")
+            .summary(&tr!("update-available", version = release.version.as_str()))
+            .body(&release.url)
+            .show();
+        self.update = Some(release);
+    }
+
+    fn push_toast(&mut self, severity: Severity, text: String) {
+        let level = match severity {
+            Severity::Info => Level::Info,
+            Severity::Warning => Level::Warning,
+            Severity::Error => Level::Error,
+        };
+        events::record(level, text.as_str());
+        let deadline = self.window_open.then(|| Instant::now() + TOAST_DURATION);
+        self.toasts.push(Toast {
+            text,
+            severity,
+            deadline,
+        });
+    }
+
+    fn show_window(&mut self) -> Task<Msg> {
+        // the full window replaces the compact one
+        if self.compact_window.is_some() {
+            return self.leave_compact();
+        }
+
+        self.mark_window_open();
+        let (size, min_size) = (self.window_size(), self.scaled(MIN_WINDOW_SIZE));
+        iced::window::latest().then(move |res| {
+            if let Some(id) = res {
+                Task::batch([
+                    iced::window::request_user_attention(id, Some(UserAttention::Informational)),
+                    iced::window::gain_focus(id),
+                ])
+            } else {
+                open_window(size, min_size)
+            }
+        })
+    }
+
+    fn mark_window_open(&mut self) {
+        // toasts queued up while hidden start counting down now
+        self.window_open = true;
+        for toast in &mut self.toasts {
+            toast
+                .deadline
+                .get_or_insert_with(|| Instant::now() + TOAST_DURATION);
+        }
+    }
+
+    fn window_size(&self) -> (f32, f32) {
+        self.scaled(self.page.window_size())
+    }
+
+    // window sizes are unaffected by the scale factor, so they have to grow with the content
+    fn scaled(&self, (width, height): (f32, f32)) -> (f32, f32) {
+        let scale = self.scale();
+        (width * scale, height * scale)
+    }
+
+    fn scale(&self) -> f32 {
+        f32::from(self.config.ui_scale()) / 100.0
+    }
+
+    pub fn scale_factor(&self, _: Id) -> f32 {
+        self.scale()
+    }
+
+    fn close_window(&mut self, id: Id) -> Task<Msg> {
+        if self.config.close_behavior() == CloseBehavior::Exit {
+            self.exit();
+        }
+
+        let close = iced::window::close(id);
+        if !self.config.background_reminder() {
+            return close;
+        }
+
+        let Ok(handle) = Notification::new()
+            .appname("Global Push-to-Talk")
+            .summary(&tr!("running-in-background"))
+            .action(DONT_SHOW_AGAIN, &tr!("dont-show-again"))
+            .show()
+        else {
+            return close;
+        };
+
+        // waiting for the notification to go away blocks
+        let dont_show_again = Task::future(async move {
+            tokio::task::spawn_blocking(move || {
+                let mut clicked = false;
+                handle.wait_for_action(|action| clicked = action == DONT_SHOW_AGAIN);
+                clicked
+            })
+            .await
+            .unwrap_or(false)
+        })
+        .then(|clicked| {
+            if clicked {
+                Task::done(Msg::SetBackgroundReminder(false))
+            } else {
+                Task::none()
+            }
+        });
+        Task::batch([close, dont_show_again])
+    }
+
+    fn enter_compact(&mut self) -> Task<Msg> {
+        if self.compact_window.is_some() {
+            return Task::none();
+        }
+
+        let (id, open) = iced::window::open(Settings {
+            exit_on_close_request: false,
+            size: self.scaled(COMPACT_SIZE).into(),
+            // some window managers resize undecorated windows anyway
+            min_size: Some(self.scaled(COMPACT_SIZE).into()),
+            resizable: false,
+            decorations: false,
+            level: iced::window::Level::AlwaysOnTop,
+            // bottom right corner, where it's out of the way. wayland compositors decide this
+            // themselves
+            position: Position::SpecificWith(|window, monitor| {
+                Point::new(
+                    monitor.width - window.width - COMPACT_MARGIN,
+                    monitor.height - window.height - COMPACT_MARGIN,
+                )
+            }),
+            platform_specific: PlatformSpecific {
+                application_id: APP_ID.to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        self.compact_window = Some(id);
+        self.window_open = false;
+
+        iced::window::latest()
+            .and_then(iced::window::close)
+            .chain(open.discard())
+    }
+
+    fn leave_compact(&mut self) -> Task<Msg> {
+        let Some(id) = self.compact_window.take() else {
+            return Task::none();
+        };
+        self.mark_window_open();
+        iced::window::close(id).chain(open_window(
+            self.window_size(),
+            self.scaled(MIN_WINDOW_SIZE),
+        ))
+    }
+
+    fn exit(&mut self) {
+        systemd::stopping();
+        if let BackendState::Loaded(b) = &mut self.backend {
+            b.pa_state.remove_virtual_mic();
+        }
+        exit(0);
+    }
+
+    // shows the state in the taskbar and window switchers too
+    pub fn title(&self, _: Id) -> String {
+        let state = match (&self.backend, self.active, self.muted) {
+            (BackendState::Error(_), ..) => return "Global Push-to-Talk".to_string(),
+            (_, false, _) => "disabled",
+            (_, true, true) => "muted",
+            (_, true, false) => "live",
+        };
+        tr!("window-title", state = state)
+    }
+
+    pub fn theme(&self, _: Id) -> Option<Theme> {
+        self.effective_theme()
+    }
+
+    // the config overrides whatever the desktop asks for
+    fn effective_theme(&self) -> Option<Theme> {
+        match self.config.theme() {
+            ThemePreference::System => self.system_theme.clone(),
+            ThemePreference::Light => Some(Theme::Light),
+            ThemePreference::Dark => Some(Theme::KanagawaDragon),
+            // a theme that no longer exists falls back to following the system
+            ThemePreference::Named(name) => Theme::ALL
+                .iter()
+                .find(|theme| theme.to_string() == name)
+                .cloned()
+                .or_else(|| self.system_theme.clone()),
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<Msg> {
+        Subscription::batch([
+            close_requests().map(Msg::Close),
+            Subscription::run_with(self.hotkey_attempt, |_| hotkeys()),
+            // X11 can only grab chords one key at a time and can't tell keyboards apart, so
+            // those are read from the keyboards
+            if !using_wayland()
+                && !input::passive(&self.hotkeys, self.config.trigger_keyboard()).is_empty()
+            {
+                let trigger_keyboard = self.config.trigger_keyboard().map(ToString::to_string);
+                Subscription::run_with((self.hotkeys.clone(), trigger_keyboard), input::bindings)
+            } else {
+                Subscription::none()
+            },
+            // keyboards plugged in while choosing one, or while looking at the ones hotkeys are
+            // read from
+            if matches!(self.page, Page::Settings | Page::Diagnostics) && !using_wayland() {
+                iced::time::every(Duration::from_secs(2)).map(|_| Msg::ScanKeyboards)
+            } else {
+                Subscription::none()
+            },
+            if self
+                .recording_hotkey
+                .as_ref()
+                .is_some_and(|r| r.captured.is_none())
+            {
+                Self::record_hotkey()
+            } else {
+                Subscription::none()
+            },
+            // the search and recording take over the keyboard while they're going
+            if !self.mic_search.is_empty() {
+                Self::mic_search_keys()
+            } else if self.recording_hotkey.is_none() {
+                Self::window_shortcuts()
+            } else {
+                Subscription::none()
+            },
+            if self.hotkey_test.is_some() {
+                iced::time::every(Duration::from_secs(1)).map(|_| Msg::HotKeyTestTick)
+            } else {
+                Subscription::none()
+            },
+            if self.flash_until.is_some() {
+                iced::time::every(FLASH_DURATION).map(|_| Msg::EndFlash)
+            } else {
+                Subscription::none()
+            },
+            Self::window_focus(),
+            // the check itself skips days that were already checked
+            if self.config.check_for_updates() {
+                iced::time::every(UPDATE_CHECK_INTERVAL).map(|_| Msg::CheckForUpdates)
+            } else {
+                Subscription::none()
+            },
+            if self.open_since.is_some() {
+                iced::time::every(Duration::from_secs(1)).map(|_| Msg::OpenTimerTick)
+            } else {
+                Subscription::none()
+            },
+            Subscription::run(config::changes).map(|()| Msg::CheckConfig),
+            if matches!(self.backend, BackendState::Loaded(_)) {
+                iced::time::every(VIRTUAL_MIC_CHECK_INTERVAL).map(|_| Msg::CheckVirtualMic)
+            } else {
+                Subscription::none()
+            },
+            if self.toasts.iter().any(|t| t.deadline.is_some()) {
+                iced::time::every(Duration::from_millis(500)).map(|_| Msg::ExpireToasts)
+            } else {
+                Subscription::none()
+            },
+        ])
+    }
+
+    fn record_hotkey() -> Subscription<Msg> {
+        fn key_to_str(key: Key, physical_key: Physical) -> String {
+            // physical codes keep keys sharing a symbol apart, e.g. Insert and Numpad0 with
+            // NumLock off, so the grab ends up on the key that was actually pressed
+            if let Physical::Code(code) = physical_key {
+                return format!("{code:?}");
+            }
+
+            match key {
+                keyboard::Key::Named(named) => format!("{named:?}"),
+                keyboard::Key::Character(c) => c.into(),
+                keyboard::Key::Unidentified => String::new(),
+            }
+        }
+
+        fn mod_to_str(modifiers: Modifiers) -> String {
+            // otherwise, finish recording
+            let mut s = Vec::with_capacity(4);
+            if modifiers.control() {
+                s.push("CTRL");
+            }
+            if modifiers.shift() {
+                s.push("SHIFT");
+            }
+            if modifiers.alt() {
+                s.push("ALT");
+            }
+            if modifiers.logo() {
+                s.push("SUPER");
+            }
+            s.join("+")
+        }
+
+        use iced::keyboard::key::Named as N;
+        use keyboard::Key::Named;
+        keyboard::listen().map(|k_ev| match k_ev {
+            keyboard::Event::KeyReleased { key, modifiers, .. } => {
+                // rule: if the key released is a modifier key, then finish
+                match key {
+                    Named(N::Control | N::Alt | N::AltGraph | N::Shift | N::Super) => {
+                        Msg::FinishHotKeyRecording(mod_to_str(modifiers))
+                    }
+                    _ => Msg::RecordHotKeyRelease,
+                }
+            }
+            keyboard::Event::KeyPressed {
+                key,
+                physical_key,
+                modifiers,
+                ..
+            } => {
+                // rule: non-modifier keys are collected until one is released
+                match key {
+                    Named(N::Escape) => Msg::CancelHotKeyRecording,
+                    Named(N::Control | N::Alt | N::AltGraph | N::Shift | N::Super) => Msg::None,
+                    _ => {
+                        Msg::RecordHotKeyPress(key_to_str(key, physical_key), mod_to_str(modifiers))
+                    }
+                }
+            }
+            keyboard::Event::ModifiersChanged(_) => Msg::None,
+        })
+    }
+
+    // shortcuts for whichever window has focus. keys going to a text field are left alone
+    fn window_shortcuts() -> Subscription<Msg> {
+        use iced::keyboard::key::Named as N;
+        iced::event::listen_with(|event, status, window| {
+            if status == iced::event::Status::Captured {
+                return None;
+            }
+            let iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) = event
+            else {
+                return None;
+            };
+            match (key.as_ref(), modifiers.command()) {
+                (Key::Named(N::Space), false) => Some(Msg::ToggleMute(Cause::Window)),
+                (Key::Named(N::Escape), false) => Some(Msg::Close(window)),
+                (Key::Character("e"), true) => Some(Msg::ToggleActive(Cause::Window)),
+                // the device list is read again on every redraw
+                (Key::Character("r"), true) => Some(Msg::None),
+                (Key::Character("q"), true) => Some(Msg::Exit),
+                _ => None,
+            }
+        })
+    }
+
+    fn window_focus() -> Subscription<Msg> {
+        iced::event::listen_with(|event, _, window| match event {
+            iced::Event::Window(iced::window::Event::Focused) => {
+                Some(Msg::WindowFocused(window, true))
+            }
+            iced::Event::Window(iced::window::Event::Unfocused) => {
+                Some(Msg::WindowFocused(window, false))
+            }
+            _ => None,
+        })
+    }
+
+    // arrow keys move through the search results, escape clears the search
+    fn mic_search_keys() -> Subscription<Msg> {
+        use iced::keyboard::key::Named as N;
+        keyboard::listen().map(|k_ev| match k_ev {
+            keyboard::Event::KeyPressed {
+                key: Key::Named(N::ArrowUp),
+                ..
+            } => Msg::MicSearchMove(-1),
+            keyboard::Event::KeyPressed {
+                key: Key::Named(N::ArrowDown),
+                ..
+            } => Msg::MicSearchMove(1),
+            keyboard::Event::KeyPressed {
+                key: Key::Named(N::Escape),
+                ..
+            } => Msg::MicSearchInput(String::new()),
+            _ => Msg::None,
+        })
+    }
+
+    pub fn view(&self, window: Id) -> Element<'_, Msg> {
+        if self.compact_window == Some(window) {
+            return self.compact_view();
+        }
+
+        if let Some(recording) = &self.recording_hotkey {
+            return self.recording_hotkey(recording);
+        }
+
+        let title = title("Global Push-to-Talk");
+        let sep = rule::horizontal(1.0);
+
+        // without audio none of the pages can do anything
+        let page = match (&self.backend, self.page) {
+            (BackendState::Error(e), _) => audio_error(e),
+            (BackendState::Loaded(backend), Page::Main) => self.main_page(backend),
+            (BackendState::Loaded(backend), Page::Settings) => self.settings_page(backend),
+            (BackendState::Loaded(backend), Page::Diagnostics) => self.diagnostics_page(backend),
+            (BackendState::Loaded(backend), Page::About) => self.about_page(backend),
+        };
+
+        // wraps onto a second line rather than cutting off Exit in narrow windows
+        let footer = row![
+            self.page_switcher(),
+            button(text(tr!("page-about"))).on_press(Msg::SwitchPage(Page::About)),
+            button(text(tr!("compact-mode"))).on_press(Msg::SetCompact(true)),
+            shortcut_tooltip(button(text(tr!("exit"))).on_press(Msg::Exit), "Ctrl+Q")
+        ]
+        .spacing(SPACING)
+        .wrap();
+
+        let content = column![
+            title,
+            sep,
+            self.config_error_banner(),
+            self.hotkey_error_banner(),
+            self.tray_missing_banner(),
+            self.virtual_mic_banner(),
+            scrollable(container(page).padding(PADDING)).height(Length::Fill),
+            footer
+        ]
+        .padding(PADDING)
+        .spacing(SPACING);
+
+        stack![content, self.toasts()].into()
+    }
+
+    fn toasts(&self) -> Element<'_, Msg> {
+        let toasts = self.toasts.iter().enumerate().map(|(i, toast)| {
+            let style = match toast.severity {
+                Severity::Info => container::rounded_box,
+                Severity::Warning => container::warning,
+                Severity::Error => container::danger,
+            };
+            container(
+                row![
+                    text(&toast.text)
+                        .wrapping(text::Wrapping::Word)
+                        .width(Length::Fill),
+                    button("×")
+                        .style(button::text)
+                        .on_press(Msg::DismissToast(i))
+                ]
+                .spacing(SPACING)
+                .align_y(Vertical::Center),
+            )
+            .padding(PADDING)
+            .width(320)
+            .style(style)
+            .into()
+        });
+
+        container(column(toasts).spacing(SPACING))
+            .padding(PADDING * 2.0)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_x(Horizontal::Right)
+            .align_y(Vertical::Bottom)
+            .into()
+    }
+
+    // just the mute indicator and the enable toggle, for keeping an eye on during calls
+    fn compact_view(&self) -> Element<'_, Msg> {
+        let BackendState::Loaded(_) = &self.backend else {
+            return button(text(tr!("error")))
+                .on_press(Msg::SetCompact(false))
+                .into();
+        };
+
+        // clicking the mic latches it open, right-clicking goes back to the full window
+        let mic = mouse_area(self.mute_indicator(28.0))
+            .on_press(Msg::ToggleMute(Cause::Window))
+            .on_right_press(Msg::SetCompact(false));
+        let mic = shortcut_tooltip(mic, self.mute_state());
+        let expand = shortcut_tooltip(
+            button(lucide::maximize())
+                .style(button::text)
+                .on_press(Msg::SetCompact(false)),
+            tr!("show-full-window"),
+        );
+
+        let content = row![
+            mic,
+            text(self.open_for().unwrap_or_default()),
+            shortcut_tooltip(
+                checkbox(self.active).on_toggle(|_| Msg::ToggleActive(Cause::Window)),
+                tr!("enable-ptt")
+            ),
+            space().width(Length::Fill),
+            expand
+        ]
+        .spacing(SPACING)
+        .align_y(Vertical::Center);
+
+        // there is no title bar, so the window is moved by dragging its background
+        mouse_area(
+            container(content)
+                .padding([0.0, PADDING])
+                .center_y(Length::Fill)
+                .style(container::rounded_box),
+        )
+        .on_press(Msg::DragCompact)
+        .into()
+    }
+
+    fn page_switcher(&self) -> Element<'_, Msg> {
+        let buttons = Page::ALL.into_iter().map(|page| {
+            button(text(page.to_string()))
+                .style(if page == self.page {
+                    button::primary
+                } else {
+                    button::secondary
+                })
+                .on_press(Msg::SwitchPage(page))
+                .into()
+        });
+        row(buttons).spacing(SPACING).into()
+    }
+
+    fn main_page(&self, backend: &Backend) -> Element<'_, Msg> {
+        column![
+            self.toggle_controls(backend),
+            self.select_mic(backend),
+            self.talk_button_view()
+        ]
+        .spacing(SPACING)
+        .into()
+    }
+
+    // opens the mic while pressed, for trying things out without a hotkey
+    fn talk_button_view(&self) -> Element<'_, Msg> {
+        if !self.active {
+            let content = container(text(tr!("talk-button")).size(18))
+                .padding(PADDING)
+                .center_x(Length::Fill)
+                .style(|theme: &Theme| container::Style {
+                    background: Some(theme.extended_palette().background.strong.color.into()),
+                    border: iced::border::rounded(8),
+                    ..Default::default()
+                });
+            return inert(content, Inert::PttDisabled);
+        }
+
+        let muted = self.muted;
+        let label = if muted {
+            tr!("talk-button")
+        } else {
+            tr!("talk-button-live")
+        };
+        let content = container(
+            row![self.mute_indicator(20.0), text(label).size(18)]
+                .spacing(SPACING)
+                .align_y(Vertical::Center),
+        )
+        .padding(PADDING)
+        .center_x(Length::Fill)
+        .style(move |theme: &Theme| {
+            let palette = theme.extended_palette();
+            let pair = if muted {
+                palette.danger.weak
+            } else {
+                palette.success.base
+            };
+            container::Style {
+                background: Some(pair.color.into()),
+                text_color: Some(pair.text),
+                border: iced::border::rounded(8),
+                ..Default::default()
+            }
+        });
+
+        // a button would swallow the press, so the mouse is tracked directly. leaving the area
+        // counts as letting go, as the release wouldn't be seen otherwise
+        let button = mouse_area(content)
+            .on_press(Msg::TalkButton(true))
+            .on_release(Msg::TalkButton(false))
+            .on_exit(Msg::TalkButton(false))
+            .interaction(iced::mouse::Interaction::Pointer);
+        shortcut_tooltip(button, "Space")
+    }
+
+    fn settings_page(&self, backend: &Backend) -> Element<'_, Msg> {
+        column![
+            self.select_profile(),
+            self.hotkeys_section(),
+            self.hidden_devices(backend),
+            self.select_resting_state(),
+            self.select_lock_behavior(),
+            self.select_fail_policy(),
+            self.close_settings(),
+            self.timing(),
+            self.select_whisper_volume(),
+            self.select_ui_scale(),
+            self.select_theme(),
+            self.color_settings(),
+            row![
+                text(tr!("start-at-login")),
+                checkbox(self.autostart).on_toggle(Msg::SetAutostart)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            self.feedback_settings(),
+            self.activity_settings(),
+            self.remote_control_settings(),
+            self.obs_settings(),
+            self.mqtt_settings(),
+            self.headset_settings(),
+            row![
+                text(tr!("suppress-typing")),
+                checkbox(self.config.suppress_typing()).on_toggle(Msg::SetSuppressTyping)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            self.watched_apps_settings(),
+            row![
+                text(tr!("pause-media")),
+                checkbox(self.config.pause_media()).on_toggle(Msg::SetPauseMedia)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            row![
+                text(tr!("check-for-updates")),
+                checkbox(self.config.check_for_updates()).on_toggle(Msg::SetCheckForUpdates)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            row![
+                text(tr!("save-stats")),
+                checkbox(self.config.save_stats()).on_toggle(Msg::SetSaveStats)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            row![
+                text(tr!("builtin-tray-icons")),
+                checkbox(self.config.builtin_tray_icons()).on_toggle(Msg::SetBuiltinTrayIcons)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            row![
+                text(tr!("tray-label-setting")),
+                checkbox(self.config.tray_label()).on_toggle(Msg::SetTrayLabel)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            row![
+                text(tr!("auto-enable")),
+                checkbox(self.config.auto_enable()).on_toggle(Msg::SetAutoEnable)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            row![
+                text(tr!("recreate-virtual-mic")),
+                checkbox(self.config.recreate_virtual_mic()).on_toggle(Msg::SetRecreateVirtualMic)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            self.overlay_settings()
+        ]
+        .spacing(SPACING)
+        .into()
+    }
+
+    // what toggling and talking look and sound like
+    fn feedback_settings(&self) -> Element<'_, Msg> {
+        column![
+            row![
+                text(tr!("attention-on-change")),
+                checkbox(self.config.attention_on_change()).on_toggle(Msg::SetAttentionOnChange)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            row![
+                text(tr!("notify-on-toggle")),
+                checkbox(self.config.notify_on_toggle()).on_toggle(Msg::SetNotifyOnToggle)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            Announcement::ALL
+                .into_iter()
+                .fold(row![text(tr!("announce"))], |row, announcement| {
+                    row.push(text(announcement.text())).push(
+                        checkbox(self.config.announce(announcement))
+                            .on_toggle(move |announce| Msg::SetAnnounce(announcement, announce)),
+                    )
+                })
+                .spacing(SPACING)
+                .align_y(Vertical::Center),
+            row![
+                text(tr!("osd-on-toggle")),
+                checkbox(self.config.osd_on_toggle()).on_toggle(Msg::SetOsdOnToggle),
+                text(tr!("osd-on-press")),
+                checkbox(self.config.osd_on_press()).on_toggle(Msg::SetOsdOnPress)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            row![
+                text(tr!("sound-on-unmute")),
+                checkbox(self.config.sound(Sound::Unmute))
+                    .on_toggle(|play| Msg::SetSound(Sound::Unmute, play)),
+                text(tr!("sound-on-mute")),
+                checkbox(self.config.sound(Sound::Mute))
+                    .on_toggle(|play| Msg::SetSound(Sound::Mute, play))
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center)
+        ]
+        .spacing(SPACING)
+        .into()
+    }
+
+    fn toggle_controls(&self, backend: &Backend) -> Element<'_, Msg> {
+        if get_selected_mic(backend).is_none() {
+            return row![
+                text(tr!("enable")),
+                inert(checkbox(false), Inert::NoMicrophone),
+                text(tr!("select-mic-hint"))
+                    .font(Font {
+                        style: Style::Italic,
+                        ..Default::default()
+                    })
+                    .style(weak_text_style)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center)
+            .into();
+        }
+
+        let label = text(tr!("enable"));
+        let checkbox = shortcut_tooltip(
+            checkbox(self.active).on_toggle(|_| Msg::ToggleActive(Cause::Window)),
+            format!("{} (Ctrl+E)", tr!("enable-ptt")),
+        );
+
+        let info = text(tr!(
+            "virtual-mic-hint",
+            device = pulse::virtual_mic_description()
+        ))
+        .font(Font {
+            style: Style::Italic,
+            ..Default::default()
+        })
+        .style(weak_text_style)
+        .width(Length::Fill);
+        let copy_name = shortcut_tooltip(
+            button(lucide::copy())
+                .style(button::text)
+                .on_press(Msg::CopyVirtualMicName),
+            tr!("copy-virtual-mic-name", name = pulse::virtual_mic_name()),
+        );
+        let info = row![info, copy_name]
+            .spacing(SPACING)
+            .align_y(Vertical::Center);
+
+        column![
+            row![
+                label,
+                checkbox,
+                self.mute_indicator(16.0),
+                text(self.mute_state()).style(weak_text_style),
+                text(self.open_for().unwrap_or_default()).style(weak_text_style)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            info
+        ]
+        .spacing(SPACING)
+        .into()
+    }
+
+    fn config_error_banner(&self) -> Element<'_, Msg> {
+        let Some(e) = &self.config_error else {
+            return space().into();
+        };
+
+        let message = text(tr!("config-unreadable", error = e.as_str()))
+            .wrapping(text::Wrapping::Word)
+            .width(Length::Fill);
+
+        container(
+            row![
+                message,
+                button(text(tr!("overwrite"))).on_press(Msg::OverwriteConfig)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+        )
+        .padding(PADDING)
+        .style(container::warning)
+        .into()
+    }
+
+    fn hotkey_error_banner(&self) -> Element<'_, Msg> {
+        let Some(e) = &self.hotkey_error else {
+            return space().into();
+        };
+
+        let message = text(tr!("hotkeys-unavailable", error = e.as_str()))
+            .wrapping(text::Wrapping::Word)
+            .width(Length::Fill);
+
+        container(
+            row![
+                message,
+                button(text(tr!("retry"))).on_press(Msg::RetryHotKeys)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+        )
+        .padding(PADDING)
+        .style(container::warning)
+        .into()
+    }
+
+    // the tray keeps being retried in the background, so there is nothing to click here
+    fn tray_missing_banner(&self) -> Element<'_, Msg> {
+        if self.tray.is_some() {
+            return space().into();
+        }
+
+        container(text(tr!("tray-missing")).wrapping(text::Wrapping::Word))
+            .width(Length::Fill)
+            .padding(PADDING)
+            .style(container::warning)
+            .into()
+    }
+
+    fn virtual_mic_banner(&self) -> Element<'_, Msg> {
+        if !self.virtual_mic_missing {
+            return space().into();
+        }
+
+        let message = text(tr!("virtual-mic-missing"))
+            .wrapping(text::Wrapping::Word)
+            .width(Length::Fill);
+
+        container(
+            row![
+                message,
+                button(text(tr!("recreate"))).on_press(Msg::RecreateVirtualMic)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+        )
+        .padding(PADDING)
+        .style(container::danger)
+        .into()
+    }
+
+    fn select_profile(&self) -> Element<'_, Msg> {
+        let label = text(tr!("profile"));
+
+        if let Some((_, name)) = &self.profile_edit {
+            let input = text_input(&tr!("profile-name"), name)
+                .on_input(Msg::ProfileNameInput)
+                .on_submit(Msg::SubmitProfileEdit)
+                .width(Length::Fill);
+            return row![
+                label,
+                input,
+                button(text(tr!("save"))).on_press(Msg::SubmitProfileEdit),
+                button(text(tr!("cancel"))).on_press(Msg::CancelProfileEdit),
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center)
+            .into();
+        }
+
+        let pick_list = pick_list(
+            self.config.profile_names(),
+            Some(self.config.active_profile().to_string()),
+            Msg::SwitchProfile,
+        )
+        .width(Length::Fill);
+
+        row![
+            label,
+            pick_list,
+            button(text(tr!("new"))).on_press(Msg::StartProfileEdit(ProfileEdit::Create)),
+            button(text(tr!("rename"))).on_press(Msg::StartProfileEdit(ProfileEdit::Rename)),
+            button(text(tr!("delete"))).on_press(Msg::DeleteProfile),
+        ]
+        .spacing(SPACING)
+        .align_y(Vertical::Center)
+        .into()
+    }
+
+    // devices shown in the microphone picker
+    fn visible_mics(&self) -> Vec<InputDevice> {
+        let BackendState::Loaded(b) = &self.backend else {
+            return Vec::new();
+        };
+        b.pa_state
+            .get_input_devices()
+            .into_iter()
+            .filter(|dev| !self.config.is_device_hidden(&dev.name))
+            .collect()
+    }
+
+    // devices whose description or source name contain the search, ignoring case
+    fn mic_matches(&self) -> Vec<InputDevice> {
+        let query = self.mic_search.to_lowercase();
+        self.visible_mics()
+            .into_iter()
+            .filter(|dev| {
+                dev.description.to_lowercase().contains(&query)
+                    || dev.name.to_lowercase().contains(&query)
+            })
+            .collect()
+    }
+
+    fn select_mic(&self, backend: &Backend) -> Element<'_, Msg> {
+        let label = text(tr!("microphone"));
+        let input_devs = self.visible_mics();
+        let selected = get_selected_mic(backend);
+        let refresh_btn = shortcut_tooltip(
+            button("⟳").on_press(Msg::None),
+            format!("{} (Ctrl+R)", tr!("refresh-mics")),
+        );
+
+        // a handful of devices is quicker to pick from a plain list
+        if input_devs.len() <= MIC_SEARCH_THRESHOLD {
+            // pick list entries can't have tooltips of their own, so this describes the chosen one
+            let details = selected.as_ref().map(device_details);
+            let pick_list = pick_list(input_devs, selected, |dev| Msg::ChooseMicrophone(dev.name))
+                .width(Length::Fill)
+                .placeholder(tr!("choose-microphone"));
+            let pick_list = match details {
+                Some(details) => shortcut_tooltip(pick_list, details),
+                None => pick_list.into(),
+            };
+
+            return row![label, pick_list, refresh_btn]
+                .spacing(SPACING)
+                .width(Length::Fill)
+                .align_y(Vertical::Center)
+                .into();
+        }
+
+        let placeholder = selected.map_or_else(|| tr!("search-microphones"), |dev| dev.description);
+        let search = text_input(&placeholder, &self.mic_search)
+            .on_input(Msg::MicSearchInput)
+            .on_submit(Msg::MicSearchSubmit)
+            .width(Length::Fill);
+        let search = row![label, search, refresh_btn]
+            .spacing(SPACING)
+            .width(Length::Fill)
+            .align_y(Vertical::Center);
+
+        if self.mic_search.is_empty() {
+            return search.into();
+        }
+
+        let results = self.mic_matches().into_iter().enumerate().map(|(i, dev)| {
+            let in_description = dev
+                .description
+                .to_lowercase()
+                .contains(&self.mic_search.to_lowercase());
+            let details = device_details(&dev);
+            let mut spans = highlight(dev.description, &self.mic_search);
+            // only power users search by source name, so it's only shown when it matched
+            if !in_description {
+                spans.push(span("  "));
+                spans.extend(highlight(dev.name.clone(), &self.mic_search));
+            }
+            let btn = button(rich_text(spans))
+                .width(Length::Fill)
+                .style(if i == self.mic_highlight {
+                    button::primary
+                } else {
+                    button::text
+                })
+                .on_press(Msg::ChooseMicrophone(dev.name));
+            shortcut_tooltip(btn, details)
+        });
+
+        column![search, column(results)].spacing(SPACING).into()
+    }
+
+    // every input device with a button to hide it from or return it to the microphone picker
+    fn hidden_devices(&self, backend: &Backend) -> Element<'_, Msg> {
+        let toggle = row![
+            text(tr!("show-hidden-devices")),
+            checkbox(self.show_hidden_devices).on_toggle(Msg::ShowHiddenDevices)
+        ]
+        .spacing(SPACING)
+        .align_y(Vertical::Center);
+
+        if !self.show_hidden_devices {
+            return toggle.into();
+        }
+
+        let devices = backend.pa_state.get_input_devices().into_iter().map(|dev| {
+            let hidden = self.config.is_device_hidden(&dev.name);
+            let label = text(dev.description).font(Font {
+                style: if hidden { Style::Italic } else { Style::Normal },
+                ..Default::default()
+            });
+            let btn = button(text(if hidden { tr!("unhide") } else { tr!("hide") }))
+                .on_press(Msg::SetDeviceHidden(dev.name, !hidden));
+
+            row![label, space().width(Length::Fill), btn]
+                .spacing(SPACING)
+                .align_y(Vertical::Center)
+                .into()
+        });
+
+        column![toggle, column(devices).spacing(SPACING).padding(PADDING)]
+            .spacing(SPACING)
+            .into()
+    }
+
+    fn hot_mic_warning_minutes(&self) -> u32 {
+        self.config.hot_mic_warning().map_or(0, |d| {
+            u32::try_from(d.as_secs() / 60).unwrap_or(MAX_HOLD_LIMIT)
+        })
+    }
+
+    fn max_hold_minutes(&self) -> u32 {
+        self.config.max_hold().map_or(0, |d| {
+            u32::try_from(d.as_secs() / 60).unwrap_or(MAX_HOLD_LIMIT)
+        })
+    }
+
+    fn timing(&self) -> Element<'_, Msg> {
+        let minutes = self.max_hold_minutes();
+        let value = if minutes == 0 {
+            tr!("max-hold-off")
+        } else {
+            tr!("max-hold-minutes", minutes = minutes)
+        };
+
+        // only save once the slider is let go of
+        let max_hold_slider = slider(0..=MAX_HOLD_LIMIT, minutes, Msg::MaxHoldChanged)
+            .on_release(Msg::SaveTiming)
+            .width(Length::Fill);
+        let reset_btn = if minutes == 0 {
+            inert(button(text(tr!("reset"))), Inert::AlreadyDefault)
+        } else {
+            button(text(tr!("reset"))).on_press(Msg::ResetTiming).into()
+        };
+
+        let warn_minutes = self.hot_mic_warning_minutes();
+        let warn_value = if warn_minutes == 0 {
+            tr!("max-hold-off")
+        } else {
+            tr!("max-hold-minutes", minutes = warn_minutes)
+        };
+        let warn_slider = slider(0..=MAX_HOLD_LIMIT, warn_minutes, Msg::HotMicWarningChanged)
+            .on_release(Msg::SaveHotMicWarning)
+            .width(Length::Fill);
+
+        column![
+            text(tr!("timing")).size(18),
+            row![
+                text(tr!("auto-mute-after")),
+                max_hold_slider,
+                text(value).width(60),
+                reset_btn
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            row![
+                text(tr!("hot-mic-warning-after")),
+                warn_slider,
+                text(warn_value).width(60)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center)
+        ]
+        .spacing(SPACING)
+        .into()
+    }
+
+    fn select_whisper_volume(&self) -> Element<'_, Msg> {
+        let slider = slider(0..=100, self.whisper_volume, Msg::WhisperVolumeChanged)
+            .on_release(Msg::SaveWhisperVolume)
+            .width(Length::Fill);
+
+        row![
+            text(tr!("whisper-volume")),
+            slider,
+            text(format!("{}%", self.whisper_volume)).width(60)
+        ]
+        .spacing(SPACING)
+        .align_y(Vertical::Center)
+        .into()
+    }
+
+    fn select_ui_scale(&self) -> Element<'_, Msg> {
+        // only applied once let go of, as the slider would move away under the cursor
+        let slider = slider(
+            MIN_UI_SCALE..=MAX_UI_SCALE,
+            self.ui_scale,
+            Msg::UiScaleChanged,
+        )
+        .step(25u16)
+        .on_release(Msg::SaveUiScale)
+        .width(Length::Fill);
+
+        row![
+            text(tr!("ui-scale")),
+            slider,
+            text(format!("{}%", self.ui_scale)).width(60)
+        ]
+        .spacing(SPACING)
+        .align_y(Vertical::Center)
+        .into()
+    }
+
+    fn select_resting_state(&self) -> Element<'_, Msg> {
+        let label = text(tr!("when-disabled"));
+        let pick_list = pick_list(
+            RestingState::ALL,
+            Some(self.config.when_disabled()),
+            Msg::ChooseRestingState,
+        );
+
+        row![label, pick_list]
+            .spacing(SPACING)
+            .align_y(Vertical::Center)
+            .into()
+    }
+
+    fn select_fail_policy(&self) -> Element<'_, Msg> {
+        let pick_list = pick_list(
+            FailPolicy::ALL,
+            Some(self.config.fail_policy()),
+            Msg::ChooseFailPolicy,
+        );
+
+        row![text(tr!("on-crash")), pick_list]
+            .spacing(SPACING)
+            .align_y(Vertical::Center)
+            .into()
+    }
+
+    fn select_lock_behavior(&self) -> Element<'_, Msg> {
+        let pick_list = pick_list(
+            LockBehavior::ALL,
+            Some(self.config.on_lock()),
+            Msg::ChooseLockBehavior,
+        );
+
+        row![text(tr!("on-lock")), pick_list]
+            .spacing(SPACING)
+            .align_y(Vertical::Center)
+            .into()
+    }
+
+    fn close_settings(&self) -> Element<'_, Msg> {
+        let behavior = self.config.close_behavior();
+        let pick_list = pick_list(CloseBehavior::ALL, Some(behavior), Msg::ChooseCloseBehavior);
+        let select = row![text(tr!("on-close")), pick_list]
+            .spacing(SPACING)
+            .align_y(Vertical::Center);
+
+        if behavior == CloseBehavior::Exit {
+            return select.into();
+        }
+
+        column![
+            select,
+            row![
+                text(tr!("background-reminder")),
+                checkbox(self.config.background_reminder()).on_toggle(Msg::SetBackgroundReminder)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center)
+        ]
+        .spacing(SPACING)
+        .into()
+    }
+
+    fn activity_settings(&self) -> Element<'_, Msg> {
+        let toggle = row![
+            text(tr!("activity-history")),
+            checkbox(self.config.activity_history()).on_toggle(Msg::SetActivityHistory)
+        ]
+        .spacing(SPACING)
+        .align_y(Vertical::Center);
+
+        if !self.config.activity_history() {
+            return toggle.into();
+        }
+
+        column![
+            toggle,
+            row![
+                text(tr!("activity-log")),
+                checkbox(self.config.activity_log()).on_toggle(Msg::SetActivityLog)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center)
+        ]
+        .spacing(SPACING)
+        .into()
+    }
+
+    fn remote_control_settings(&self) -> Element<'_, Msg> {
+        let toggle = row![
+            text(tr!("remote-control")),
+            checkbox(self.config.remote_control()).on_toggle(Msg::SetRemoteControl)
+        ]
+        .spacing(SPACING)
+        .align_y(Vertical::Center);
+
+        if !self.config.remote_control() {
+            return toggle.into();
+        }
+
+        let status = if self.config.remote_token().is_none() {
+            text(tr!("remote-control-no-token")).style(text::danger)
+        } else {
+            let address = format!(
+                "{}:{}",
+                self.config.remote_address(),
+                self.config.remote_port()
+            );
+            text(tr!("remote-control-address", address = address)).style(weak_text_style)
+        };
+        column![toggle, status].spacing(SPACING).into()
+    }
+
+    fn obs_settings(&self) -> Element<'_, Msg> {
+        let toggle = row![
+            text(tr!("obs-integration")),
+            checkbox(self.config.obs_integration()).on_toggle(Msg::SetObsIntegration)
+        ]
+        .spacing(SPACING)
+        .align_y(Vertical::Center);
+
+        if !self.config.obs_integration() {
+            return toggle.into();
+        }
+
+        let status = match &self.obs_status {
+            _ if self.config.obs_input().is_none() => text(tr!("obs-no-input")).style(text::danger),
+            Some(status @ obs::Status::Connected) => text(status.to_string()).style(text::success),
+            Some(status @ obs::Status::Disconnected(_)) => {
+                text(status.to_string()).style(text::danger)
+            }
+            Some(status) => text(status.to_string()).style(weak_text_style),
+            None => text(obs::Status::Connecting.to_string()).style(weak_text_style),
+        };
+        column![toggle, status].spacing(SPACING).into()
+    }
+
+    fn mqtt_settings(&self) -> Element<'_, Msg> {
+        let toggle = row![
+            text(tr!("mqtt")),
+            checkbox(self.config.mqtt()).on_toggle(Msg::SetMqtt)
+        ]
+        .spacing(SPACING)
+        .align_y(Vertical::Center);
+
+        if !self.config.mqtt() {
+            return toggle.into();
+        }
+
+        let status = match &self.mqtt_status {
+            Some(status @ mqtt::Status::Connected) => text(status.to_string()).style(text::success),
+            Some(status @ mqtt::Status::Disconnected(_)) => {
+                text(status.to_string()).style(text::danger)
+            }
+            Some(status) => text(status.to_string()).style(weak_text_style),
+            None => text(mqtt::Status::Connecting.to_string()).style(weak_text_style),
+        };
+        column![toggle, status].spacing(SPACING).into()
+    }
+
+    fn watched_apps_settings(&self) -> Element<'_, Msg> {
+        let apps = self.config.watched_apps().into_iter().map(|app| {
+            let label = if self.watched_running.contains(&app) {
+                text(tr!("watched-app-running", app = app.clone()))
+            } else {
+                text(app.clone())
+            };
+            row![
+                label,
+                space().width(Length::Fill),
+                button(text(tr!("delete"))).on_press(Msg::RemoveWatchedApp(app))
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center)
+            .into()
+        });
+
+        let input = text_input(&tr!("watched-app-placeholder"), &self.watched_app_input)
+            .on_input(Msg::WatchedAppInput)
+            .on_submit(Msg::AddWatchedApp)
+            .width(Length::Fill);
+        let add = button(text(tr!("add"))).on_press_maybe(
+            (!self.watched_app_input.trim().is_empty()).then_some(Msg::AddWatchedApp),
+        );
+
+        column![
+            text(tr!("watched-apps")),
+            column(apps).spacing(SPACING).padding(PADDING),
+            row![input, add].spacing(SPACING).align_y(Vertical::Center),
+            row![
+                text(tr!("disable-with-watched-apps")),
+                checkbox(self.config.disable_with_watched_apps())
+                    .on_toggle(Msg::SetDisableWithWatchedApps)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center)
+        ]
+        .spacing(SPACING)
+        .into()
+    }
+
+    fn headset_settings(&self) -> Element<'_, Msg> {
+        let toggle = row![
+            text(tr!("headset-button")),
+            checkbox(self.config.headset_button()).on_toggle(Msg::SetHeadsetButton)
+        ]
+        .spacing(SPACING)
+        .align_y(Vertical::Center);
+
+        let Some(scan) = self
+            .headset_scan
+            .as_ref()
+            .filter(|_| self.config.headset_button())
+        else {
+            return toggle.into();
+        };
+
+        // the chosen one stays listed while it's unplugged
+        let mut choices = vec![headset::Choice::Any];
+        choices.extend(
+            scan.headsets
+                .iter()
+                .map(|headset| headset.name.clone())
+                .chain(self.config.headset().map(ToString::to_string))
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .map(headset::Choice::Named),
+        );
+        let selected = match self.config.headset() {
+            Some(name) => headset::Choice::Named(name.to_string()),
+            None => headset::Choice::Any,
+        };
+        let choose = pick_list(choices, Some(selected), Msg::ChooseHeadset);
+
+        let found = if scan.headsets.is_empty() {
+            text(tr!("headset-none")).style(weak_text_style)
+        } else {
+            text(tr!("headset-found", count = scan.headsets.len())).style(weak_text_style)
+        };
+        let mut settings = column![toggle, choose, found].spacing(SPACING);
+        if scan.denied > 0 {
+            settings =
+                settings.push(text(tr!("headset-denied", count = scan.denied)).style(text::danger));
+        }
+        settings.into()
+    }
+
+    fn color_settings(&self) -> Element<'_, Msg> {
+        let rows = IndicatorColor::ALL
+            .into_iter()
+            .map(|which| self.color_row(which));
+        column![text(tr!("colors")).size(18), column(rows).spacing(SPACING)]
+            .spacing(SPACING)
+            .into()
+    }
+
+    // a hex field and a few swatches, falling back to the default when reset
+    fn color_row(&self, which: IndicatorColor) -> Element<'_, Msg> {
+        let custom = self.config.color(which);
+        let draft = self.color_drafts.get(&which);
+        let input = draft
+            .cloned()
+            .or_else(|| custom.map(format_color))
+            .unwrap_or_default();
+        let invalid = draft.is_some_and(|d| !d.is_empty() && parse_color(d).is_none());
+
+        let [r, g, b, _] = self.indicator_color(which).into_rgba8();
+        let field = text_input(&format_color([r, g, b]), &input)
+            .on_input(move |input| Msg::ColorInput(which, input))
+            .width(100);
+        let swatches = SWATCHES.into_iter().map(|rgb| {
+            let [r, g, b] = rgb;
+            button(space().width(16).height(16))
+                .padding(0)
+                .style(move |_: &Theme, _| button::Style {
+                    background: Some(Color::from_rgb8(r, g, b).into()),
+                    border: Border {
+                        radius: 4.0.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .on_press(Msg::SetColor(which, Some(rgb)))
+                .into()
+        });
+        let reset_btn = if custom.is_none() && draft.is_none() {
+            inert(button(text(tr!("reset"))), Inert::AlreadyDefault)
+        } else {
+            button(text(tr!("reset")))
+                .on_press(Msg::SetColor(which, None))
+                .into()
+        };
+
+        let mut line = row![
+            text(which.to_string()).width(120),
+            color_swatch(self.indicator_color(which)),
+            field,
+            row(swatches).spacing(4)
+        ];
+        if invalid {
+            line = line.push(text(tr!("color-invalid")).style(text::danger));
+        }
+        line.push(reset_btn)
+            .spacing(SPACING)
+            .align_y(Vertical::Center)
+            .wrap()
+            .into()
+    }
+
+    fn overlay_settings(&self) -> Element<'_, Msg> {
+        let toggle = row![
+            text(tr!("overlay")),
+            checkbox(self.config.overlay()).on_toggle(Msg::SetOverlay)
+        ]
+        .spacing(SPACING)
+        .align_y(Vertical::Center);
+
+        if !self.config.overlay() {
+            return toggle.into();
+        }
+
+        let corner = pick_list(
+            Corner::ALL,
+            Some(self.config.overlay_corner()),
+            Msg::ChooseOverlayCorner,
+        );
+        let show = pick_list(
+            OverlayShow::ALL,
+            Some(self.config.overlay_show()),
+            Msg::ChooseOverlayShow,
+        );
+
+        // the chosen one stays listed while it's disconnected
+        let connected = self.overlay.as_ref().map(Overlay::outputs);
+        let chosen = self.config.overlay_output().map(ToString::to_string);
+        let mut names: Vec<_> = connected.into_iter().flatten().chain(chosen).collect();
+        names.sort();
+        names.dedup();
+        let outputs: Vec<_> = std::iter::once(OverlayOutput::Any)
+            .chain(names.into_iter().map(OverlayOutput::Named))
+            .collect();
+        let selected = self
+            .config
+            .overlay_output()
+            .map_or(OverlayOutput::Any, |name| OverlayOutput::Named(name.into()));
+        let output = pick_list(outputs, Some(selected), Msg::ChooseOverlayOutput);
+
+        // shown while dragged, saved on release
+        let margin = slider(
+            0..=MAX_OVERLAY_MARGIN,
+            self.config.overlay_margin(),
+            Msg::OverlayMarginChanged,
+        )
+        .step(4u16)
+        .on_release(Msg::SaveOverlayLook);
+        let scale = slider(
+            MIN_OVERLAY_SCALE..=MAX_OVERLAY_SCALE,
+            self.config.overlay_scale(),
+            Msg::OverlayScaleChanged,
+        )
+        .step(25u16)
+        .on_release(Msg::SaveOverlayLook);
+        let opacity = slider(
+            MIN_OVERLAY_OPACITY..=100,
+            self.config.overlay_opacity(),
+            Msg::OverlayOpacityChanged,
+        )
+        .step(5u8)
+        .on_release(Msg::SaveOverlayLook);
+        let sliders = [
+            (
+                tr!("overlay-margin"),
+                Element::from(margin.width(Length::Fill)),
+                format!("{} px", self.config.overlay_margin()),
+            ),
+            (
+                tr!("overlay-scale"),
+                scale.width(Length::Fill).into(),
+                format!("{}%", self.config.overlay_scale()),
+            ),
+            (
+                tr!("overlay-opacity"),
+                opacity.width(Length::Fill).into(),
+                format!("{}%", self.config.overlay_opacity()),
+            ),
+        ]
+        .into_iter()
+        .map(|(label, slider, value)| {
+            row![text(label), slider, text(value).width(60)]
+                .spacing(SPACING)
+                .align_y(Vertical::Center)
+                .into()
+        });
+        let colors = IndicatorColor::OVERLAY
+            .into_iter()
+            .map(|which| self.color_row(which));
+
+        column![
+            toggle,
+            row![text(tr!("overlay-corner")), corner]
+                .spacing(SPACING)
+                .align_y(Vertical::Center),
+            row![text(tr!("overlay-show")), show]
+                .spacing(SPACING)
+                .align_y(Vertical::Center),
+            row![text(tr!("overlay-output")), output]
+                .spacing(SPACING)
+                .align_y(Vertical::Center),
+            column(sliders).spacing(SPACING),
+            column(colors).spacing(SPACING)
+        ]
+        .spacing(SPACING)
+        .into()
+    }
+
+    fn select_theme(&self) -> Element<'_, Msg> {
+        let label = text(tr!("theme"));
+        // light and dark already stand for these
+        let named = Theme::ALL
+            .iter()
+            .filter(|theme| !matches!(theme, Theme::Light | Theme::Dark | Theme::KanagawaDragon))
+            .map(|theme| ThemePreference::Named(theme.to_string()));
+        let options: Vec<_> = ThemePreference::ALL.into_iter().chain(named).collect();
+        let pick_list = pick_list(options, Some(self.config.theme()), Msg::ChooseTheme);
+
+        // explains why the window looks the way it does
+        let current = self
+            .effective_theme()
+            .map_or_else(|| tr!("theme-default"), |theme| theme.to_string());
+        let source = match self.config.theme() {
+            ThemePreference::System => "system",
+            _ => "setting",
+        };
+        let current =
+            text(tr!("theme-current", theme = current, source = source)).style(weak_text_style);
+
+        row![label, pick_list, current]
+            .spacing(SPACING)
+            .align_y(Vertical::Center)
+            .into()
     }
 
-    fn record_hotkey() -> Subscription<Msg> {
-        fn key_to_str(key: Key) -> String {
-            match key {
-                keyboard::Key::Named(named) => format!("{named:?}"),
-                keyboard::Key::Character(c) => c.into(),
-                keyboard::Key::Unidentified => String::new(),
-            }
+    // spelled out next to the indicator, which otherwise only differs by icon and color
+    fn mute_state(&self) -> String {
+        if self.typing_mute.is_muting() {
+            tr!("mic-typing")
+        } else if self.muted {
+            tr!("mic-muted")
+        } else {
+            tr!("mic-transmitting")
         }
+    }
 
-        fn mod_to_str(modifiers: Modifiers) -> String {
-            // otherwise, finish recording
-            let mut s = Vec::with_capacity(4);
-            if modifiers.control() {
-                s.push("CTRL");
-            }
-            if modifiers.shift() {
-                s.push("SHIFT");
-            }
-            if modifiers.alt() {
-                s.push("ALT");
-            }
-            if modifiers.logo() {
-                s.push("SUPER");
-            }
-            s.join("+")
+    fn mute_indicator(&self, size: f32) -> Element<'_, Msg> {
+        let icon = if self.muted || self.typing_mute.is_muting() {
+            lucide::mic_off()
+        } else {
+            lucide::mic()
         }
+        .size(size)
+        .align_y(Vertical::Bottom);
 
-        use iced::keyboard::key::Named as N;
-        use keyboard::Key::Named;
-        keyboard::listen().map(|k_ev| match k_ev {
-            keyboard::Event::KeyReleased { key, modifiers, .. } => {
-                // rule: if the key released is a modifier key, then finish
-                match key {
-                    Named(N::Control | N::Alt | N::AltGraph | N::Shift | N::Super) => {
-                        Msg::FinishHotKeyRecording(mod_to_str(modifiers))
-                    }
-                    _ => Msg::None,
-                }
-            }
-            keyboard::Event::KeyPressed { key, modifiers, .. } => {
-                // rule: if the key pressed is not a modifier key, then finish
-                match key {
-                    Named(N::Control | N::Alt | N::AltGraph | N::Shift | N::Super) => Msg::None,
-                    _ => {
-                        if modifiers.is_empty() {
-                            Msg::FinishHotKeyRecording(key_to_str(key))
-                        } else {
-                            Msg::FinishHotKeyRecording(
-                                [mod_to_str(modifiers), key_to_str(key)].join("+"),
-                            )
-                        }
-                    }
-                }
-            }
-            keyboard::Event::ModifiersChanged(_) => Msg::None,
-        })
+        let color = self.current_indicator_color();
+        let icon = icon.color(color);
+
+        // a ring around the mic shows at a glance that it's live
+        let live = self.active && !self.muted && !self.typing_mute.is_muting();
+        let flash = self.flash_until.map(|_| Color { a: 0.3, ..color });
+        container(icon)
+            .padding(size / 5.0)
+            .style(move |_: &Theme| container::Style {
+                background: flash.map(Into::into),
+                border: iced::Border {
+                    color: if live { color } else { Color::TRANSPARENT },
+                    width: 2.0,
+                    radius: size.into(),
+                },
+                ..Default::default()
+            })
+            .into()
     }
 
-    pub fn view(&self, _window: Id) -> Element<'_, Msg> {
-        let backend = match &self.backend {
-            BackendState::Loaded(backend) => backend,
-            BackendState::Error(e) => return show_error(e.clone()),
+    fn hotkey_backend(&self) -> String {
+        let backend = match (&self.hotkey_backend, &self.hotkey_error) {
+            (Some(backend), _) => backend.to_string(),
+            (None, Some(_)) => return tr!("diagnostics-hotkeys-failed"),
+            (None, None) => return tr!("diagnostics-hotkeys-starting"),
         };
-
-        if self.recording_hotkey.is_some() {
-            return recording_hotkey();
+        // chords and a trigger kept to one keyboard are read from /dev/input next to the grabs
+        let passive = input::passive(&self.hotkeys, self.config.trigger_keyboard());
+        if using_wayland() || passive.is_empty() {
+            return backend;
         }
-
-        let title = title("Global Push-to-Talk");
-        let sep = rule::horizontal(1.0);
-
-        let main = container(
-            column![self.toggle_controls(backend), select_mic(backend),].spacing(SPACING),
+        let Some(scan) = &self.keyboard_scan else {
+            return tr!("diagnostics-hotkeys-evdev-unscanned", backend = backend);
+        };
+        let keyboards = scan.read_for(&passive);
+        if keyboards.is_empty() {
+            return tr!("diagnostics-hotkeys-evdev-none", backend = backend);
+        }
+        let keyboards: Vec<_> = keyboards.iter().map(|k| k.name.as_str()).collect();
+        tr!(
+            "diagnostics-hotkeys-evdev",
+            backend = backend,
+            keyboards = keyboards.join(", ")
         )
-        .padding(PADDING);
+    }
 
-        let footer = row![
-            self.hotkey_indicator(),
-            space().width(Length::Fill),
-            button("Exit").on_press(Msg::Exit)
+    fn diagnostics_page(&self, backend: &Backend) -> Element<'_, Msg> {
+        let source = backend
+            .pa_state
+            .get_active_source_name()
+            .map_or_else(|| tr!("diagnostics-none"), ToString::to_string);
+        let config_path = Config::path().map_or_else(
+            || tr!("diagnostics-unknown"),
+            |path| path.display().to_string(),
+        );
+
+        let rows = [
+            (tr!("diagnostics-hotkeys"), self.hotkey_backend()),
+            (tr!("diagnostics-session"), session_type()),
+            (
+                tr!("diagnostics-talk-session"),
+                format_talk_time(self.session_talk),
+            ),
+            (
+                tr!("diagnostics-talk-today"),
+                if self.config.save_stats() {
+                    format_talk_time(self.today_talk)
+                } else {
+                    tr!("diagnostics-not-saved")
+                },
+            ),
+            (
+                tr!("diagnostics-virtual-mic"),
+                format!(
+                    "{} ({})",
+                    pulse::virtual_mic_description(),
+                    pulse::virtual_mic_name()
+                ),
+            ),
+            (tr!("diagnostics-source-mic"), source),
+            (tr!("diagnostics-config-file"), config_path),
+            (tr!("diagnostics-sandbox"), sandbox()),
         ]
-        .align_y(Vertical::Bottom);
+        .into_iter()
+        .map(|(label, value)| {
+            row![text(label).width(160), text(value).style(weak_text_style)]
+                .spacing(SPACING)
+                .into()
+        });
 
-        column![title, sep, main, space().height(Length::Fill), footer]
-            .padding(PADDING)
+        column(rows)
+            .push(button(text(tr!("reset-stats"))).on_press(Msg::ResetStats))
+            .push(self.activity_history())
+            .push(self.event_log())
             .spacing(SPACING)
             .into()
     }
 
-    fn toggle_controls(&self, backend: &Backend) -> Element<'_, Msg> {
-        if get_selected_mic(backend).is_none() {
-            return row![
-                text("Select a microphone to enable push-to-talk")
-                    .font(Font {
-                        style: Style::Italic,
-                        ..Default::default()
-                    })
-                    .style(weak_text_style)
+    // the most recent sessions first, only once keeping them was turned on
+    fn activity_history(&self) -> Element<'_, Msg> {
+        if !self.config.activity_history() {
+            return space().into();
+        }
+
+        let time = |t: chrono::DateTime<chrono::Local>| t.format("%Y-%m-%d %H:%M:%S").to_string();
+        let cell = |content: String, width: f32| text(content).size(12).width(width);
+        let header = row![
+            cell(tr!("activity-opened"), 140.0),
+            cell(tr!("activity-closed"), 140.0),
+            cell(tr!("activity-duration"), 70.0),
+            cell(tr!("activity-cause"), 120.0),
+        ]
+        .spacing(SPACING);
+        let entries = self.history.sessions().rev().map(|session| {
+            let (closed, cause) = match session.closed {
+                Some((closed, closed_by)) => {
+                    (time(closed), format!("{} / {closed_by}", session.opened_by))
+                }
+                None => (tr!("activity-still-open"), session.opened_by.to_string()),
+            };
+            row![
+                cell(time(session.opened), 140.0),
+                cell(closed, 140.0),
+                cell(
+                    session.duration().map(format_elapsed).unwrap_or_default(),
+                    70.0
+                ),
+                cell(cause, 120.0),
             ]
             .spacing(SPACING)
-            .align_y(Vertical::Center)
-            .into();
-        }
+            .into()
+        });
 
-        let label = text("Enable");
-        let checkbox = checkbox(self.active).on_toggle(Msg::SetActive);
+        column![
+            row![
+                text(tr!("activity")).size(18),
+                space().width(Length::Fill),
+                button(text(tr!("activity-export"))).on_press(Msg::ExportActivity),
+                button(text(tr!("activity-clear"))).on_press(Msg::ClearActivity)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            header,
+            scrollable(column(entries).spacing(2)).height(Length::Fixed(160.0))
+        ]
+        .spacing(SPACING)
+        .into()
+    }
 
-        let info = text(format!(
-            "Select \"{VIRTUALMIC_DESCRIPTION}\" in any application to use push-to-talk"
-        ))
-        .font(Font {
-            style: Style::Italic,
-            ..Default::default()
-        })
-        .style(weak_text_style);
+    // the most recent events first, colored by level
+    fn event_log(&self) -> Element<'_, Msg> {
+        let filter = self.event_filter.to_lowercase();
+        let entries = events::recent()
+            .into_iter()
+            .rev()
+            .filter(|event| event.text.to_lowercase().contains(&filter))
+            .map(|event| {
+                let level = event.level;
+                text(event.to_string())
+                    .size(12)
+                    .font(Font::MONOSPACE)
+                    .style(move |theme: &Theme| {
+                        let palette = theme.extended_palette();
+                        text::Style {
+                            color: match level {
+                                Level::Info => None,
+                                Level::Warning => Some(palette.warning.base.color),
+                                Level::Error => Some(palette.danger.base.color),
+                            },
+                        }
+                    })
+                    .into()
+            });
 
         column![
-            row![label, checkbox, self.mute_indicator()]
-                .spacing(SPACING)
-                .align_y(Vertical::Center),
-            info
+            text(tr!("events")).size(18),
+            row![
+                text_input(&tr!("events-filter"), &self.event_filter)
+                    .on_input(Msg::EventFilterInput)
+                    .width(Length::Fill),
+                button(text(tr!("about-copy"))).on_press(Msg::CopyEvents)
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center),
+            column(entries).spacing(2)
         ]
         .spacing(SPACING)
         .into()
     }
 
-    fn mute_indicator(&self) -> Element<'_, Msg> {
-        let icon = if self.muted {
-            lucide::mic_off()
-        } else {
-            lucide::mic()
-        }
-        .align_y(Vertical::Bottom);
+    // everything worth mentioning in a bug report, as plain text
+    fn about_text(&self, backend: &Backend) -> String {
+        let server = backend.pa_state.server_info().map_or_else(
+            || tr!("diagnostics-unknown"),
+            |info| format!("{} {}", info.name, info.version),
+        );
+        [
+            format!(
+                "Global Push-to-Talk {} ({})",
+                env!("CARGO_PKG_VERSION"),
+                env!("GIT_HASH")
+            ),
+            format!("{}: {server}", tr!("about-audio-server")),
+            format!("{}: {}", tr!("diagnostics-hotkeys"), self.hotkey_backend()),
+            format!("{}: {}", tr!("diagnostics-session"), session_type()),
+            format!("{}: {}", tr!("diagnostics-sandbox"), sandbox()),
+        ]
+        .join("\n")
+    }
 
-        icon.color(if self.muted {
-            [0.8, 0.0, 0.0]
-        } else {
-            [0.0, 0.8, 0.0]
-        })
+    fn about_page(&self, backend: &Backend) -> Element<'_, Msg> {
+        let link = |label: String, url: &'static str| {
+            rich_text([span(label).underline(true).link(Msg::OpenUrl(url.into()))])
+                .on_link_click(std::convert::identity)
+        };
+
+        let update = self.update.as_ref().map(|release| {
+            rich_text([
+                span(tr!("update-available", version = release.version.as_str()))
+                    .underline(true)
+                    .link(Msg::OpenUrl(release.url.clone())),
+            ])
+            .on_link_click(std::convert::identity)
+        });
+
+        column![
+            text(self.about_text(backend)),
+            column(update.map(Element::from)),
+            link(tr!("about-repository"), REPOSITORY_URL),
+            link(tr!("about-report-issue"), ISSUES_URL),
+            button(text(tr!("about-copy"))).on_press(Msg::CopyAbout)
+        ]
+        .spacing(SPACING)
         .into()
     }
 
-    fn hotkey_indicator(&self) -> Element<'_, Msg> {
-        if using_wayland() {
-            let trigger_label = hk_label("Trigger", &self.hk_descriptions.trigger, None);
-            let toggle_active_label =
-                hk_label("Enable/Disable", &self.hk_descriptions.toggle_active, None);
+    fn recording_hotkey<'a>(&self, recording: &'a HotKeyRecording) -> Element<'a, Msg> {
+        let space1 = space().width(Length::Fill).height(Length::Fill);
+        let space2 = space().width(Length::Fill).height(Length::Fill);
 
-            let all = row![trigger_label, toggle_active_label]
-                .spacing(SPACING)
-                .align_y(Vertical::Center);
+        let (Some(captured), Some(validated)) = (
+            recording.captured.as_deref(),
+            self.validate_captured(recording),
+        ) else {
+            let txt = text(tr!("hotkey-recording"));
+            return column![space1, txt, space2]
+                .align_x(Horizontal::Center)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into();
+        };
 
-            tooltip(
-                all,
-                "Configure these hotkeys in your system's settings",
-                tooltip::Position::Top,
-            )
+        let heading = text(tr!("hotkey-captured", action = recording.action.name()));
+        let combination = text(match &validated {
+            Ok(binding) => binding.description(),
+            Err(_) => captured.to_string(),
+        })
+        .size(32);
+
+        let buttons = row![
+            button(text(tr!("hotkey-use")))
+                .on_press_maybe(validated.is_ok().then_some(Msg::ConfirmHotKey)),
+            button(text(tr!("hotkey-retry")))
+                .on_press(Msg::RetryHotKeyRecording)
+                .style(button::secondary),
+            button(text(tr!("cancel")))
+                .on_press(Msg::CancelHotKeyRecording)
+                .style(button::secondary),
+        ]
+        .spacing(8);
+
+        let mut content = column![space1, heading, combination];
+        if let Err(e) = validated {
+            content = content.push(text(e).style(text::danger));
+        }
+        content
+            .push(buttons)
+            .push(space2)
+            .spacing(12)
+            .align_x(Horizontal::Center)
+            .width(Length::Fill)
+            .height(Length::Fill)
             .into()
-        } else {
-            let d = &self.hk_descriptions;
+    }
 
-            let trigger_label = hk_label("Trigger", &d.trigger, Some(HotKeyAction::Trigger));
-            let toggle_active_label = hk_label(
-                "Enable/Disable",
-                &d.toggle_active,
-                Some(HotKeyAction::ToggleActive),
-            );
+    // one row per action, with its binding drawn as a key cap and buttons to test and change it
+    fn hotkeys_section(&self) -> Element<'_, Msg> {
+        let rows = HotKeyAction::ALL.into_iter().map(|action| {
+            let held = match action {
+                HotKeyAction::Trigger => self.trigger_held,
+                HotKeyAction::WhisperTrigger => self.whisper_held,
+                HotKeyAction::ToggleActive | HotKeyAction::NextProfile => false,
+            };
+            let test = self.hotkey_test.as_ref().filter(|t| t.action == action);
+            // green while held, also during a test
+            let lit = test.map_or(held, |t| t.pressed);
+            let description = action.binding(&self.hk_descriptions);
 
-            let all = row![trigger_label, toggle_active_label]
-                .spacing(SPACING)
-                .align_y(Vertical::Center);
+            let test_btn: Element<'_, Msg> = match test {
+                Some(t) => text(tr!(
+                    "hotkey-test-progress",
+                    events = t.events,
+                    seconds = t.seconds_left
+                ))
+                .style(weak_text_style)
+                .into(),
+                None => button(text(tr!("hotkey-test")))
+                    .on_press(Msg::StartHotKeyTest(action.clone()))
+                    .style(button::secondary)
+                    .into(),
+            };
+            // the portal lets the compositor decide, the app can't change anything there
+            let edit_btn = if using_wayland() {
+                inert(button(text(tr!("edit"))), Inert::CompositorHotKeys)
+            } else {
+                button(text(tr!("edit")))
+                    .on_press(Msg::StartHotKeyRecording(action.clone()))
+                    .into()
+            };
 
-            tooltip(
-                all,
-                "Click on any hotkey to change it...",
-                tooltip::Position::Top,
-            )
+            row![
+                self.registration_dot(),
+                text(action.name()).width(160),
+                key_cap(description, lit),
+                space().width(Length::Fill),
+                test_btn,
+                edit_btn
+            ]
+            .spacing(SPACING)
+            .align_y(Vertical::Center)
             .into()
+        });
+
+        column![
+            text(tr!("hotkeys")).size(18),
+            column(rows).spacing(SPACING),
+            self.trigger_keyboard_settings()
+        ]
+        .spacing(SPACING)
+        .into()
+    }
+
+    // the portal doesn't say which keyboard a shortcut came from
+    fn trigger_keyboard_settings(&self) -> Element<'_, Msg> {
+        let Some(scan) = self.keyboard_scan.as_ref().filter(|_| !using_wayland()) else {
+            return space().into();
+        };
+
+        // the chosen one stays listed while it's unplugged, by its id
+        let selected = match self.config.trigger_keyboard() {
+            Some(id) => input::Choice::Keyboard(
+                scan.keyboards
+                    .iter()
+                    .find(|keyboard| keyboard.id == id)
+                    .cloned()
+                    .unwrap_or_else(|| input::Keyboard {
+                        id: id.to_string(),
+                        name: id.to_string(),
+                    }),
+            ),
+            None => input::Choice::Any,
+        };
+        let mut choices = vec![input::Choice::Any];
+        choices.extend(scan.keyboards.iter().cloned().map(input::Choice::Keyboard));
+        if !choices.contains(&selected) {
+            choices.push(selected.clone());
+        }
+
+        let choose = row![
+            text(tr!("trigger-keyboard")),
+            pick_list(choices, Some(selected), Msg::ChooseTriggerKeyboard)
+        ]
+        .spacing(SPACING)
+        .align_y(Vertical::Center);
+        let mut settings = column![choose].spacing(SPACING);
+        if scan.denied > 0 {
+            settings = settings
+                .push(text(tr!("keyboard-denied", count = scan.denied)).style(text::danger));
+        }
+        settings.into()
+    }
+
+    // whether the hotkeys are registered: grey while starting, red when they failed
+    fn registration_dot(&self) -> Element<'_, Msg> {
+        let (status, failed) = match (&self.hotkey_error, &self.hotkey_backend) {
+            (Some(e), _) => (tr!("hotkeys-failed", error = e.as_str()), Some(true)),
+            (None, Some(_)) => (tr!("hotkeys-registered"), Some(false)),
+            (None, None) => (tr!("hotkeys-starting"), None),
+        };
+        let dot = text("●").size(12).style(move |theme: &Theme| {
+            let palette = theme.extended_palette();
+            text::Style {
+                color: Some(match failed {
+                    Some(true) => palette.danger.base.color,
+                    Some(false) => palette.success.base.color,
+                    None => palette.secondary.strong.color,
+                }),
+            }
+        });
+        shortcut_tooltip(dot, status)
+    }
+}
+
+fn load_backend(dry_run: bool) -> BackendState {
+    match PulseAudioState::init() {
+        Ok(mut pa_state) => {
+            events::record(Level::Info, "Connected to the audio server");
+            pa_state.set_dry_run(dry_run);
+            BackendState::Loaded(Backend { pa_state })
+        }
+        Err(e) => {
+            events::record(Level::Error, format!("Audio server unavailable: {e}"));
+            BackendState::Error(e.to_string())
         }
     }
 }
 
+fn open_window(size: (f32, f32), min_size: (f32, f32)) -> Task<Msg> {
+    iced::window::open(Settings {
+        exit_on_close_request: false,
+        size: size.into(),
+        min_size: Some(min_size.into()),
+        resizable: true,
+        decorations: true,
+        platform_specific: PlatformSpecific {
+            application_id: APP_ID.to_string(),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .1
+    .discard()
+}
+
+fn profile_error(e: &ProfileError) {
+    let _ = Notification::new()
+        .appname("Global Push-to-Talk")
+        .summary(&tr!("profile-change-failed"))
+        .body(&e.to_string())
+        .show();
+}
+
+fn config_error_message(e: &ConfyError) -> String {
+    match Config::path() {
+        Some(path) => format!("{}: {e}", path.display()),
+        None => e.to_string(),
+    }
+}
+
+fn config_modified() -> Option<SystemTime> {
+    let path = Config::path()?;
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
 fn get_selected_mic(backend: &Backend) -> Option<InputDevice> {
     backend
         .pa_state
@@ -506,50 +4793,139 @@ fn get_selected_mic(backend: &Backend) -> Option<InputDevice> {
         .cloned()
 }
 
-fn recording_hotkey<'a>() -> Element<'a, Msg> {
-    let txt = text("Enter a key combination...");
-    let space1 = space().width(Length::Fill).height(Length::Fill);
-    let space2 = space().width(Length::Fill).height(Length::Fill);
-    column![space1, txt, space2]
-        .align_x(Horizontal::Center)
-        .width(Length::Fill)
-        .height(Length::Fill)
-        .into()
+// splits `content` so that the first case-insensitive match of `query` is in bold
+fn highlight<'a>(content: String, query: &str) -> Vec<text::Span<'a, Msg>> {
+    let lower = content.to_lowercase();
+    // lowercasing can change the length of some characters, which would throw off the indices
+    let found = (lower.len() == content.len())
+        .then(|| lower.find(&query.to_lowercase()))
+        .flatten()
+        .filter(|_| !query.is_empty());
+    let Some(start) = found else {
+        return vec![span(content)];
+    };
+
+    let end = start + query.len();
+    let bold = Font {
+        weight: Weight::Bold,
+        ..Default::default()
+    };
+    vec![
+        span(content[..start].to_string()),
+        span(content[start..end].to_string()).font(bold),
+        span(content[end..].to_string()),
+    ]
 }
 
-fn select_mic(backend: &Backend) -> Element<'_, Msg> {
-    let label = text("Microphone");
-    let input_devs = backend.pa_state.get_input_devices();
-    let selected = get_selected_mic(backend);
-    let pick_list = pick_list(input_devs, selected, |dev| Msg::ChooseMicrophone(dev.name))
-        .width(Length::Fill)
-        .placeholder("Choose Microphone...");
+// also used to name controls that only show an icon
+fn shortcut_tooltip<'a>(
+    content: impl Into<Element<'a, Msg>>,
+    keys: impl text::IntoFragment<'a>,
+) -> Element<'a, Msg> {
+    tooltip(
+        content,
+        container(text(keys).size(12))
+            .padding(4)
+            .style(container::rounded_box),
+        tooltip::Position::Bottom,
+    )
+    .into()
+}
 
-    let refresh_btn = button("⟳").on_press(Msg::None);
+// m:ss, e.g. 2:05, or h:mm:ss from an hour on
+fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
 
-    row![label, pick_list, refresh_btn]
-        .spacing(SPACING)
-        .width(Length::Fill)
-        .align_y(Vertical::Center)
-        .into()
+fn format_talk_time(talk: TalkTime) -> String {
+    tr!(
+        "talk-time",
+        time = format_elapsed(Duration::from_secs(talk.seconds)),
+        activations = talk.activations
+    )
 }
 
-fn show_error<'a>(message: String) -> Element<'a, Msg> {
-    let title = title("Error");
-    let sep = rule::horizontal(1.0);
-    let message = text(message).wrapping(text::Wrapping::Word);
+// why a control can't be used right now, worded the same wherever it shows up
+#[derive(Debug, Clone, Copy)]
+enum Inert {
+    NoMicrophone,
+    PttDisabled,
+    CompositorHotKeys,
+    AlreadyDefault,
+}
 
-    let close_btn = button("Close").on_press(Msg::Exit);
-    let close_btn = column![close_btn]
-        .align_x(Horizontal::Right)
-        .width(Length::Fill);
+impl Inert {
+    fn reason(self) -> String {
+        match self {
+            Self::NoMicrophone => tr!("inert-no-microphone"),
+            Self::PttDisabled => tr!("inert-ptt-disabled"),
+            Self::CompositorHotKeys => tr!("hotkeys-configured-by-system"),
+            Self::AlreadyDefault => tr!("inert-already-default"),
+        }
+    }
+}
 
-    column![title, sep, message, space().height(Length::Fill), close_btn]
-        .spacing(SPACING)
-        .padding(PADDING)
-        .height(Length::Fill)
-        .width(Length::Fill)
-        .into()
+// shows a disabled control along with why it is disabled, rather than hiding it
+// everything known about a device, to tell apart ones with similar descriptions
+fn device_details(dev: &InputDevice) -> String {
+    let mut lines = vec![
+        tr!("device-source", name = dev.name.as_str()),
+        tr!("device-format", spec = dev.sample_spec.as_str()),
+    ];
+    if let Some(port) = &dev.active_port {
+        lines.push(tr!("device-port", port = port.as_str()));
+    }
+    if dev.is_default {
+        lines.push(tr!("device-default"));
+    }
+    lines.join("\n")
+}
+
+fn inert<'a>(content: impl Into<Element<'a, Msg>>, why: Inert) -> Element<'a, Msg> {
+    shortcut_tooltip(content, why.reason())
+}
+
+// X11 grabs only see XWayland windows on a Wayland session, which is easy to miss
+fn session_type() -> String {
+    if !using_wayland() {
+        tr!("session-x11")
+    } else if std::env::var_os("DISPLAY").is_some_and(|display| !display.is_empty()) {
+        tr!("session-wayland-xwayland")
+    } else {
+        tr!("session-wayland")
+    }
+}
+
+fn sandbox() -> String {
+    flatpak::id().map_or_else(
+        || tr!("diagnostics-none"),
+        |id| tr!("diagnostics-flatpak", id = id),
+    )
+}
+
+const REPOSITORY_URL: &str = "https://github.com/Adamskye/global-ptt";
+const ISSUES_URL: &str = "https://github.com/Adamskye/global-ptt/issues";
+
+fn audio_error(message: &str) -> Element<'_, Msg> {
+    let message = text(tr!("audio-unavailable", error = message)).wrapping(text::Wrapping::Word);
+
+    container(
+        column![
+            message,
+            button(text(tr!("retry"))).on_press(Msg::RetryBackend)
+        ]
+        .spacing(SPACING),
+    )
+    .width(Length::Fill)
+    .padding(PADDING)
+    .style(container::danger)
+    .into()
 }
 
 fn title<'a>(content: impl text::IntoFragment<'a>) -> Element<'a, Msg> {
@@ -564,37 +4940,138 @@ fn title<'a>(content: impl text::IntoFragment<'a>) -> Element<'a, Msg> {
         .into()
 }
 
+// a filled square showing a color
+fn color_swatch<'a>(color: Color) -> Element<'a, Msg> {
+    container(space().width(20).height(20))
+        .style(move |theme: &Theme| container::Style {
+            background: Some(color.into()),
+            border: Border {
+                color: theme.extended_palette().background.strong.color,
+                width: 1.0,
+                radius: 4.0.into(),
+            },
+            ..Default::default()
+        })
+        .into()
+}
+
+// a binding in a bordered box, like the keys on a keyboard
+fn key_cap(description: &str, lit: bool) -> Element<'_, Msg> {
+    let label = if description.is_empty() {
+        tr!("hotkey-unbound")
+    } else {
+        description.to_string()
+    };
+    container(
+        text(label)
+            .font(Font::MONOSPACE)
+            .style(move |theme: &Theme| text::Style {
+                color: lit.then(|| theme.extended_palette().success.base.color),
+            }),
+    )
+    .padding([2, 8])
+    .style(|theme: &Theme| {
+        let palette = theme.extended_palette();
+        container::Style {
+            background: Some(palette.background.weak.color.into()),
+            border: Border {
+                color: palette.background.strong.color,
+                width: 1.0,
+                radius: 4.0.into(),
+            },
+            ..Default::default()
+        }
+    })
+    .into()
+}
+
 fn weak_text_style(theme: &Theme) -> text::Style {
     let color = theme.extended_palette().secondary.strong.color;
     text::Style { color: Some(color) }
 }
 
-fn hk_label<'a>(
-    name: &'a str,
-    description: &'a str,
-    recording: Option<HotKeyAction>,
-) -> Element<'a, Msg> {
-    let italic = Font {
-        style: Style::Italic,
-        ..Default::default()
-    };
-    let bold_italic = Font {
-        weight: Weight::Bold,
-        style: Style::Italic,
-        ..Default::default()
-    };
+// whoever followed the state over the network loses it along with remote control
+fn drop_remote_subscribers(subscribers: &mut Vec<Subscriber>) {
+    subscribers.retain(|subscriber| !subscriber.is_remote());
+}
 
-    let link = recording.as_ref().map(|_| ());
-    rich_text([
-        span(name).link_maybe(link),
-        span(": "),
-        span(description).font(bold_italic),
-    ])
-    .on_link_click(move |()| match recording.clone() {
-        Some(recording) => Msg::StartHotKeyRecording(recording.clone()),
-        None => Msg::None,
-    })
-    .font(italic)
-    .style(weak_text_style)
-    .into()
+// the trigger only matters while active, otherwise the mic stays in its resting state
+fn requested_mute(muted: bool, active: bool, resting: RestingState) -> bool {
+    if active {
+        muted
+    } else {
+        resting == RestingState::Muted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabling_while_the_trigger_is_held_goes_to_the_resting_state() {
+        for (resting, muted) in [(RestingState::Open, false), (RestingState::Muted, true)] {
+            // held down, so the trigger asks for an open mic
+            assert!(!requested_mute(false, true, resting));
+            assert_eq!(requested_mute(false, false, resting), muted);
+            // released while disabled
+            assert_eq!(requested_mute(true, false, resting), muted);
+        }
+    }
+
+    #[test]
+    fn turning_remote_control_off_drops_remote_subscribers() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("port can be bound");
+        let remote = std::net::TcpStream::connect(listener.local_addr().expect("port is bound"))
+            .expect("port accepts connections");
+        let (local, _other_end) = std::os::unix::net::UnixStream::pair().expect("pair of sockets");
+        let mut subscribers = [ipc::Connection::Tcp(remote), ipc::Connection::Unix(local)]
+            .into_iter()
+            .map(|connection| {
+                Reply::new(connection, false)
+                    .subscribe()
+                    .expect("connection can subscribe")
+            })
+            .collect();
+
+        drop_remote_subscribers(&mut subscribers);
+        assert_eq!(subscribers.len(), 1);
+        assert!(!subscribers[0].is_remote());
+    }
+
+    #[test]
+    fn typing_mutes_until_the_keyboard_is_quiet() {
+        let hold_off = Duration::from_millis(300);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut typing = TypingMute::default();
+
+        assert!(typing.key(at(0), hold_off, true));
+        assert!(typing.is_muting());
+        // more keys keep it muted for longer without muting again
+        assert!(!typing.key(at(200), hold_off, true));
+        assert_eq!(typing.remaining(at(300)), Some(Duration::from_millis(200)));
+        assert!(typing.is_muting());
+        assert_eq!(typing.remaining(at(500)), None);
+        assert!(!typing.is_muting());
+        // and the next key starts over
+        assert!(typing.key(at(600), hold_off, true));
+    }
+
+    #[test]
+    fn typing_leaves_held_and_muted_mics_alone() {
+        let hold_off = Duration::from_millis(300);
+        let now = Instant::now();
+        let mut typing = TypingMute::default();
+
+        // the trigger is held or the mic is muted anyway
+        assert!(!typing.key(now, hold_off, false));
+        assert!(!typing.is_muting());
+
+        // pressing the trigger while muted for typing opens the mic right away
+        assert!(typing.key(now, hold_off, true));
+        typing.stop();
+        assert!(!typing.is_muting());
+        assert_eq!(typing.remaining(now), None);
+    }
 }
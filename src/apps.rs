@@ -0,0 +1,141 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    rc::Rc,
+};
+
+use iced::{
+    futures::{SinkExt, Stream, channel::mpsc::Sender, executor::block_on},
+    stream,
+};
+use libpulse_binding::{
+    callbacks::ListResult,
+    context::{
+        introspect::ClientInfo,
+        subscribe::{Facility, InterestMaskSet, Operation},
+    },
+    mainloop::standard::Mainloop,
+    proplist::properties,
+    time::MicroSeconds,
+};
+
+use crate::{
+    app::Msg,
+    events::{self, Level},
+    pulse,
+};
+
+// how long to wait for the server before checking whether the app still listens
+const WAKE_UP: MicroSeconds = MicroSeconds(250_000);
+
+/// Reports the watched applications that are connected to the audio server, by the names in
+/// `watchlist` they matched, whenever one comes or goes. Applications connect as soon as they
+/// touch audio, before they open any microphone.
+pub fn watch(watchlist: Vec<String>) -> impl Stream<Item = Msg> {
+    stream::channel(4, async move |tx| {
+        // the audio server's mainloop blocks, so it gets a thread of its own
+        let res = tokio::task::spawn_blocking(move || run(&watchlist, tx)).await;
+        if let Ok(Err(e)) = res {
+            events::record(
+                Level::Warning,
+                format!("Applications can't be watched for: {e}"),
+            );
+        }
+    })
+}
+
+// until the app stops listening, which is noticed within WAKE_UP
+fn run(watchlist: &[String], mut tx: Sender<Msg>) -> Result<(), pulse::Error> {
+    let (mainloop, context) = pulse::connect("GlobalPushToTalkWatcher")?;
+    // client index to the watchlist entry it matched
+    let running = Rc::new(RefCell::new(BTreeMap::new()));
+    let changed = Rc::new(Cell::new(true));
+
+    let introspect = context.borrow().introspect();
+    let on_client = {
+        let watchlist = watchlist.to_vec();
+        let running = running.clone();
+        let changed = changed.clone();
+        move |item: ListResult<&ClientInfo>| {
+            if let ListResult::Item(client) = item
+                && let Some(name) = matching(&watchlist, client)
+            {
+                running.borrow_mut().insert(client.index, name);
+                changed.set(true);
+            }
+        }
+    };
+
+    // the ones that were already running
+    let _ = introspect.get_client_info_list(on_client.clone());
+    {
+        let running = running.clone();
+        let changed = changed.clone();
+        context.borrow_mut().set_subscribe_callback(Some(Box::new(
+            move |facility, operation, index| {
+                if facility != Some(Facility::Client) {
+                    return;
+                }
+                match operation {
+                    Some(Operation::New) => {
+                        let _ = introspect.get_client_info(index, on_client.clone());
+                    }
+                    Some(Operation::Removed) if running.borrow_mut().remove(&index).is_some() => {
+                        changed.set(true);
+                    }
+                    _ => {}
+                }
+            },
+        )));
+    }
+    let _ = context
+        .borrow_mut()
+        .subscribe(InterestMaskSet::CLIENT, |_| {});
+
+    let res = loop {
+        if !tick(&mut mainloop.borrow_mut()) {
+            break Err(pulse::Error::MainloopTick);
+        }
+        if tx.is_closed() {
+            break Ok(());
+        }
+        if !changed.replace(false) {
+            continue;
+        }
+        // one entry for each application, however many connections it has
+        let mut names: Vec<_> = running.borrow().values().cloned().collect();
+        names.sort();
+        names.dedup();
+        if block_on(tx.send(Msg::WatchedApps(names))).is_err() {
+            break Ok(());
+        }
+    };
+    context.borrow_mut().disconnect();
+    res
+}
+
+// handles whatever the server sent, waiting for it no longer than WAKE_UP
+fn tick(mainloop: &mut Mainloop) -> bool {
+    mainloop.prepare(Some(WAKE_UP)).is_ok()
+        && mainloop.poll().is_ok()
+        && mainloop.dispatch().is_ok()
+}
+
+// the watchlist entry matching the client's binary or application name, ignoring case
+fn matching(watchlist: &[String], client: &ClientInfo) -> Option<String> {
+    let ids = [
+        client
+            .proplist
+            .get_str(properties::APPLICATION_PROCESS_BINARY),
+        client.proplist.get_str(properties::APPLICATION_NAME),
+        client.name.as_deref().map(ToString::to_string),
+    ];
+    watchlist
+        .iter()
+        .find(|wanted| {
+            ids.iter()
+                .flatten()
+                .any(|id| id.eq_ignore_ascii_case(wanted.trim()))
+        })
+        .cloned()
+}
@@ -0,0 +1,88 @@
+use std::{io, path::PathBuf};
+
+use ashpd::desktop::background::Background;
+
+use crate::{APP_ID, flatpak};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("could not find the config directory")]
+    NoConfigDir,
+    #[error("failed to update autostart entry: {0}")]
+    Io(#[from] io::Error),
+    #[error("background portal request failed: {0}")]
+    Portal(#[from] ashpd::Error),
+}
+
+// ~/.config/autostart/<APP_ID>.desktop
+fn desktop_file_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(
+        config_dir
+            .join("autostart")
+            .join(format!("{APP_ID}.desktop")),
+    )
+}
+
+fn desktop_file() -> String {
+    let exec = std::env::current_exe()
+        .ok()
+        .and_then(|path| path.to_str().map(ToString::to_string))
+        .unwrap_or_else(|| env!("CARGO_PKG_NAME").into());
+
+    format!(
+        "[Desktop Entry]
+Type=Application
+Name=Global Push-to-Talk
+Comment=Use push-to-talk with any microphone and application.
+Exec=\"{exec}\" --hidden
+Icon=microphone
+Terminal=false
+X-GNOME-Autostart-enabled=true
+"
+    )
+}
+
+/// Whether an enabled autostart entry exists. Sandboxed apps can't see the entry the background
+/// portal creates, so this is only meaningful outside of Flatpak.
+pub fn is_enabled() -> bool {
+    let Some(contents) = desktop_file_path().and_then(|path| std::fs::read_to_string(path).ok())
+    else {
+        return false;
+    };
+
+    !contents.lines().any(|line| {
+        matches!(
+            line.replace(' ', "").as_str(),
+            "X-GNOME-Autostart-enabled=false" | "Hidden=true"
+        )
+    })
+}
+
+/// Adds or removes the autostart entry, returning whether autostart ended up enabled.
+pub async fn set_enabled(enabled: bool) -> Result<bool, Error> {
+    // sandboxed apps can't write to the host's autostart directory, so ask the portal instead
+    if flatpak::is_sandboxed() {
+        let response = Background::request()
+            .reason("Start push-to-talk when you log in")
+            .auto_start(enabled)
+            .command([env!("CARGO_PKG_NAME"), "--hidden"])
+            .send()
+            .await?
+            .response()?;
+        return Ok(response.auto_start());
+    }
+
+    let path = desktop_file_path().ok_or(Error::NoConfigDir)?;
+    if enabled {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, desktop_file())?;
+    } else if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    Ok(enabled)
+}
@@ -1,47 +1,2007 @@
-use std::str::FromStr;
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Write as _},
+    fs::OpenOptions,
+    io::{self, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        Mutex, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
 
 use confy::ConfyError;
-use global_hotkey::hotkey::HotKey;
+use iced::{futures::Stream, stream};
+use notify::{Event, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
 
-use crate::hotkey::HotKeyConfig;
+use crate::{
+    announce::Announcement,
+    events::{self, Level},
+    hotkey::{Binding, HotKeyConfig},
+    i18n::tr,
+    instance,
+    sounds::Sound,
+};
 
 const APP_NAME: &str = "global-push-to-talk";
+const PATH_ENV: &str = "GLOBAL_PTT_CONFIG";
 
-#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+// set from --config, takes precedence over PATH_ENV
+static PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+// e.g. config-work.toml for the instance "work"
+fn config_name() -> String {
+    instance::suffixed("config", "-")
+}
+
+/// Yields whenever the config file may have changed. Its directory is watched rather than the
+/// file, so that editors replacing the file with a renamed copy are noticed too.
+pub fn changes() -> impl Stream<Item = ()> {
+    changes_of(Config::path())
+}
+
+fn changes_of(path: Option<PathBuf>) -> impl Stream<Item = ()> {
+    stream::channel(1, async move |mut tx| {
+        let Some((dir, name)) = path
+            .as_deref()
+            .and_then(|path| Some((path.parent()?, path.file_name()?.to_owned())))
+        else {
+            return;
+        };
+        let notice = move |event: notify::Result<Event>| {
+            if event.is_ok_and(|event| {
+                !event.kind.is_access() && event.paths.iter().any(|p| p.file_name() == Some(&name))
+            }) {
+                // a change is already waiting when it's full, which is enough
+                let _ = tx.try_send(());
+            }
+        };
+
+        // the directory is made anyway once the config is saved
+        let watched = std::fs::create_dir_all(dir).and_then(|()| {
+            let mut watcher = notify::recommended_watcher(notice).map_err(io::Error::other)?;
+            watcher
+                .watch(dir, RecursiveMode::NonRecursive)
+                .map_err(io::Error::other)?;
+            Ok(watcher)
+        });
+        match watched {
+            // kept until the subscription ends
+            Ok(_watcher) => std::future::pending().await,
+            Err(e) => events::record(
+                Level::Warning,
+                format!(
+                    "Changes to {} won't be noticed until restarting: {e}",
+                    dir.display()
+                ),
+            ),
+        }
+    })
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub enum ThemePreference {
+    #[default]
+    System,
+    Light,
+    Dark,
+    // any other of iced's built-in themes, by name
+    Named(String),
+}
+
+impl ThemePreference {
+    pub const ALL: [Self; 3] = [Self::System, Self::Light, Self::Dark];
+}
+
+impl Display for ThemePreference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&match self {
+            Self::System => tr!("theme-system"),
+            Self::Light => tr!("theme-light"),
+            Self::Dark => tr!("theme-dark"),
+            Self::Named(name) => name.clone(),
+        })
+    }
+}
+
+// where on the screen the overlay is shown
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    pub const ALL: [Self; 4] = [
+        Self::TopLeft,
+        Self::TopRight,
+        Self::BottomLeft,
+        Self::BottomRight,
+    ];
+}
+
+impl Display for Corner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&match self {
+            Self::TopLeft => tr!("corner-top-left"),
+            Self::TopRight => tr!("corner-top-right"),
+            Self::BottomLeft => tr!("corner-bottom-left"),
+            Self::BottomRight => tr!("corner-bottom-right"),
+        })
+    }
+}
+
+// when the overlay is on screen
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverlayShow {
+    #[default]
+    WhileTransmitting,
+    // dimmed while muted
+    WhileActive,
+}
+
+impl OverlayShow {
+    pub const ALL: [Self; 2] = [Self::WhileTransmitting, Self::WhileActive];
+}
+
+impl Display for OverlayShow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&match self {
+            Self::WhileTransmitting => tr!("overlay-show-transmitting"),
+            Self::WhileActive => tr!("overlay-show-active"),
+        })
+    }
+}
+
+// what the microphone does while push-to-talk is disabled
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RestingState {
+    #[default]
+    Open,
+    Muted,
+}
+
+impl RestingState {
+    pub const ALL: [Self; 2] = [Self::Open, Self::Muted];
+}
+
+impl Display for RestingState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&match self {
+            Self::Open => tr!("resting-open"),
+            Self::Muted => tr!("resting-muted"),
+        })
+    }
+}
+
+// what locking the screen does to the microphone
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockBehavior {
+    // mute it, and open it again on unlock if it was open before
+    #[default]
+    Restore,
+    StayMuted,
+    Ignore,
+}
+
+impl LockBehavior {
+    pub const ALL: [Self; 3] = [Self::Restore, Self::StayMuted, Self::Ignore];
+}
+
+impl Display for LockBehavior {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&match self {
+            Self::Restore => tr!("lock-restore"),
+            Self::StayMuted => tr!("lock-stay-muted"),
+            Self::Ignore => tr!("lock-ignore"),
+        })
+    }
+}
+
+// what a crash leaves the virtual mic in
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailPolicy {
+    #[default]
+    Closed,
+    Open,
+}
+
+impl FailPolicy {
+    pub const ALL: [Self; 2] = [Self::Closed, Self::Open];
+}
+
+impl Display for FailPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&match self {
+            Self::Closed => tr!("fail-closed"),
+            Self::Open => tr!("fail-open"),
+        })
+    }
+}
+
+// what closing the main window does
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CloseBehavior {
+    #[default]
+    Background,
+    Exit,
+}
+
+impl CloseBehavior {
+    pub const ALL: [Self; 2] = [Self::Background, Self::Exit];
+}
+
+impl Display for CloseBehavior {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&match self {
+            Self::Background => tr!("close-background"),
+            Self::Exit => tr!("close-exit"),
+        })
+    }
+}
+
+// the states of the mic that can be given a color of their own
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IndicatorColor {
+    Muted,
+    Transmitting,
+    // push-to-talk is off
+    Disabled,
+    // the overlay's own, the mic's colors when unset
+    OverlayTransmitting,
+    OverlayMuted,
+}
+
+impl IndicatorColor {
+    pub const ALL: [Self; 3] = [Self::Transmitting, Self::Muted, Self::Disabled];
+    pub const OVERLAY: [Self; 2] = [Self::OverlayTransmitting, Self::OverlayMuted];
+}
+
+impl Display for IndicatorColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&match self {
+            Self::Muted | Self::OverlayMuted => tr!("color-muted"),
+            Self::Transmitting | Self::OverlayTransmitting => tr!("color-transmitting"),
+            Self::Disabled => tr!("color-disabled"),
+        })
+    }
+}
+
+// state changes that can run a command
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Hook {
+    Unmute,
+    Mute,
+    Enable,
+    Disable,
+}
+
+impl Hook {
+    pub const ALL: [Self; 4] = [Self::Unmute, Self::Mute, Self::Enable, Self::Disable];
+
+    /// The name of its field in the config file.
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Unmute => "on_unmute",
+            Self::Mute => "on_mute",
+            Self::Enable => "on_enable",
+            Self::Disable => "on_disable",
+        }
+    }
+}
+
+pub const DEFAULT_PROFILE: &str = "Default";
+const DEFAULT_WHISPER_VOLUME: u8 = 40;
+const DEFAULT_REMOTE_ADDRESS: &str = "127.0.0.1";
+const DEFAULT_REMOTE_PORT: u16 = 7441;
+const DEFAULT_OBS_HOST: &str = "localhost";
+// obs-websocket's own default
+const DEFAULT_OBS_PORT: u16 = 4455;
+const DEFAULT_MQTT_BROKER: &str = "mqtt://localhost:1883";
+const DEFAULT_MQTT_PREFIX: &str = "ptt";
+const DEFAULT_PAUSE_MEDIA_AFTER_MS: u64 = 500;
+const DEFAULT_TYPING_HOLD_OFF_MS: u64 = 300;
+pub const MIN_UI_SCALE: u16 = 75;
+pub const MAX_UI_SCALE: u16 = 200;
+const DEFAULT_OVERLAY_MARGIN: u16 = 24;
+pub const MAX_OVERLAY_MARGIN: u16 = 200;
+pub const MIN_OVERLAY_SCALE: u16 = 50;
+pub const MAX_OVERLAY_SCALE: u16 = 300;
+pub const MIN_OVERLAY_OPACITY: u8 = 10;
+
+const HOTKEY_FORMAT: &[&str] = &[
+    "Hotkeys are modifiers and a key joined by \"+\", e.g. \"Ctrl+Super+P\".",
+    "Modifiers are Ctrl, Shift, Alt and Super. Keys use their names, e.g. \"KeyA\", \"Digit1\",",
+    "\"F5\" or \"Insert\". Several keys without modifiers form a chord that is held together,",
+    "e.g. \"KeyA+KeyS\". \"none\" leaves the action without a hotkey.",
+];
+
+// descriptions of every config field and an example for those that are unset by default, written
+// out by `--write-default-config`
+const CONFIG_DOCS: &[(&str, &str, Option<&str>)] = &[
+    (
+        "active",
+        "Whether push-to-talk is enabled. true or false.",
+        None,
+    ),
+    (
+        "theme",
+        "\"System\", \"Light\", \"Dark\" or any other built-in theme, e.g. { Named = \"Nord\" }.",
+        None,
+    ),
+    (
+        "language",
+        "Language of the interface, \"en\" or \"de\". Taken from $LANG when unset.",
+        Some("\"de\""),
+    ),
+    (
+        "when_disabled",
+        "What the microphone does while push-to-talk is disabled. \"Open\" or \"Muted\".",
+        None,
+    ),
+    (
+        "on_lock",
+        "What locking the screen does. \"Restore\" mutes the microphone and opens it again on \
+         unlock if it was open, \"StayMuted\" leaves it muted, \"Ignore\" does nothing.",
+        None,
+    ),
+    (
+        "fail_policy",
+        "What the virtual microphone is left in when the app crashes. \"Closed\" mutes it, \
+         \"Open\" unmutes it.",
+        None,
+    ),
+    (
+        "close_behavior",
+        "What closing the window does. \"Background\" keeps running, \"Exit\" quits.",
+        None,
+    ),
+    (
+        "background_reminder",
+        "Notify that the app is still running after closing the window to the background. \
+         true or false.",
+        None,
+    ),
+    (
+        "attention_on_change",
+        "Ask for attention on the window when push-to-talk is turned on or off from elsewhere \
+         while the window is open but not focused. true or false.",
+        None,
+    ),
+    (
+        "sound_on_unmute",
+        "Play the sound theme's sound for opening the microphone whenever it opens. true or false.",
+        None,
+    ),
+    (
+        "sound_on_mute",
+        "Play the sound theme's sound for closing the microphone whenever it closes. true or \
+         false.",
+        None,
+    ),
+    (
+        "announce_live",
+        "Announce it through a notification, which screen readers read out, whenever the \
+         microphone goes live. true or false.",
+        None,
+    ),
+    (
+        "announce_muted",
+        "Announce it whenever the microphone is muted while push-to-talk is on. true or false.",
+        None,
+    ),
+    (
+        "announce_disabled",
+        "Announce it whenever push-to-talk is turned off. true or false.",
+        None,
+    ),
+    (
+        "notify_on_toggle",
+        "Show a notification when push-to-talk is turned on or off with a hotkey, e.g. in a \
+         fullscreen game. true or false.",
+        None,
+    ),
+    (
+        "osd_on_toggle",
+        "Show the desktop's on-screen display when push-to-talk is turned on or off, or the \
+         microphone is opened or closed for longer from the tray, the window or a command. \
+         true or false.",
+        None,
+    ),
+    (
+        "osd_on_press",
+        "Also show it whenever holding the trigger opens or closes the microphone. true or \
+         false.",
+        None,
+    ),
+    (
+        "headset_button",
+        "Toggle the microphone with the mute button of a USB or Bluetooth headset, and set its \
+         mute LED. Needs read and write access to its /dev/input device. true or false.",
+        None,
+    ),
+    (
+        "headset",
+        "Name of the headset to listen to, as shown in the settings. Any headset when unset.",
+        Some("\"Jabra Evolve2 65\""),
+    ),
+    (
+        "trigger_keyboard",
+        "Only hear the trigger from this keyboard, by its name in /dev/input/by-id or else the \
+         name it reports. It's then read from the keyboard instead of grabbed, so it still \
+         reaches other applications. Needs read access to its /dev/input device. X11 only. Any \
+         keyboard when unset.",
+        Some("\"usb-Keebio_Macro_Pad-event-kbd\""),
+    ),
+    (
+        "pause_media",
+        "Pause media players that are playing while the microphone is open, and play them \
+         again when it closes. true or false.",
+        None,
+    ),
+    (
+        "pause_media_players",
+        "Only pause these players, named like their MPRIS bus name without \
+         org.mpris.MediaPlayer2. in front. Every player when unset.",
+        Some("[\"spotify\", \"vlc\"]"),
+    ),
+    (
+        "pause_media_after_ms",
+        "How long the microphone has to be open before players are paused, so that quick taps \
+         don't interrupt the music.",
+        Some("500"),
+    ),
+    (
+        "suppress_typing",
+        "Mute the open microphone briefly while typing, unless a trigger or the talk button is \
+         held. Reads the keyboards like headset buttons do. true or false.",
+        None,
+    ),
+    (
+        "typing_hold_off_ms",
+        "How long after the last keystroke the microphone stays muted.",
+        Some("300"),
+    ),
+    (
+        "watched_apps",
+        "Enable push-to-talk when one of these applications starts, matched against the binary \
+         or application name they give the audio server, ignoring case.",
+        Some("[\"Discord\", \"zoom\"]"),
+    ),
+    (
+        "disable_with_watched_apps",
+        "Disable push-to-talk again once the last of the watched applications has quit. \
+         true or false.",
+        None,
+    ),
+    (
+        "start_minimized",
+        "Start in the tray without opening the window. true or false.",
+        None,
+    ),
+    (
+        "auto_enable",
+        "Enable push-to-talk after choosing a microphone for the first time. true or false.",
+        None,
+    ),
+    (
+        "recreate_virtual_mic",
+        "Create the virtual microphone again when something else removes it. true or false.",
+        None,
+    ),
+    (
+        "builtin_tray_icons",
+        "Use the tray icons built into the app instead of the icon theme. true or false.",
+        None,
+    ),
+    (
+        "tray_label",
+        "Show ON, OFF or LIVE next to the tray icon, on hosts that draw labels like Ayatana's \
+         indicators. true or false.",
+        None,
+    ),
+    (
+        "muted_color",
+        "Color of the microphone while muted, e.g. \"#9e9e9e\". Follows the theme when unset.",
+        Some("\"#9e9e9e\""),
+    ),
+    (
+        "transmitting_color",
+        "Color of the microphone while transmitting. Follows the theme when unset.",
+        Some("\"#2196f3\""),
+    ),
+    (
+        "disabled_color",
+        "Color of the microphone while push-to-talk is disabled. Follows the theme when unset.",
+        Some("\"#616161\""),
+    ),
+    (
+        "overlay",
+        "Show a microphone on top of all windows while transmitting. Needs a Wayland \
+         compositor with wlr-layer-shell. true or false.",
+        None,
+    ),
+    (
+        "overlay_corner",
+        "Corner of the screen for the overlay. \"TopLeft\", \"TopRight\", \"BottomLeft\" or \
+         \"BottomRight\".",
+        None,
+    ),
+    (
+        "overlay_margin",
+        "Distance of the overlay from the edges of the screen in pixels, up to 200.",
+        Some("24"),
+    ),
+    (
+        "overlay_scale",
+        "Size of the overlay in percent, from 50 to 300.",
+        Some("150"),
+    ),
+    (
+        "overlay_opacity",
+        "Opacity of the overlay in percent, from 10 to 100.",
+        Some("80"),
+    ),
+    (
+        "overlay_show",
+        "When the overlay is shown. \"WhileTransmitting\", or \"WhileActive\" to keep it up, \
+         dimmed, while muted.",
+        None,
+    ),
+    (
+        "overlay_transmitting_color",
+        "Color of the overlay while transmitting. Same as transmitting_color when unset.",
+        Some("\"#2196f3\""),
+    ),
+    (
+        "overlay_muted_color",
+        "Color of the overlay while muted. Same as muted_color when unset.",
+        Some("\"#616161\""),
+    ),
+    (
+        "overlay_output",
+        "Name of the monitor the overlay is shown on, e.g. \"DP-1\". The compositor picks one \
+         when unset or when it isn't connected.",
+        Some("\"DP-1\""),
+    ),
+    (
+        "hot_mic_warning_minutes",
+        "Remind with a notification when the microphone has been open this many minutes, and \
+         again every time as much time passes. Off when unset or 0.",
+        Some("10"),
+    ),
+    (
+        "on_unmute",
+        "Shell command run with sh -c whenever the microphone opens. Commands running longer \
+         than 10 seconds are killed.",
+        Some("\"~/bin/desk-led red\""),
+    ),
+    (
+        "on_mute",
+        "Shell command run with sh -c whenever the microphone is muted again.",
+        Some("\"~/bin/desk-led off\""),
+    ),
+    (
+        "on_enable",
+        "Shell command run with sh -c whenever push-to-talk is turned on.",
+        None,
+    ),
+    (
+        "on_disable",
+        "Shell command run with sh -c whenever push-to-talk is turned off.",
+        None,
+    ),
+    (
+        "allowed_clients",
+        "Paths of the only programs allowed to send commands, besides global-ptt itself. Anything \
+         running as you may when unset. Read at startup.",
+        Some("[\"/usr/bin/waybar\"]"),
+    ),
+    (
+        "remote_control",
+        "Accept the JSON protocol of the control socket over TCP, e.g. from another machine. \
+         Needs remote_token. true or false.",
+        None,
+    ),
+    (
+        "remote_address",
+        "Address to listen on for remote control. Only this machine can connect unless this \
+         is changed, e.g. to \"0.0.0.0\".",
+        Some("\"127.0.0.1\""),
+    ),
+    (
+        "remote_port",
+        "Port to listen on for remote control.",
+        Some("7441"),
+    ),
+    (
+        "remote_token",
+        "Secret that every remote control request has to carry as \"token\". Remote control \
+         doesn't start without one.",
+        Some("\"change-me\""),
+    ),
+    (
+        "obs_integration",
+        "Mute and unmute an input in OBS Studio along with the microphone, through \
+         obs-websocket. Needs obs_input. true or false.",
+        None,
+    ),
+    (
+        "obs_host",
+        "Host running OBS Studio.",
+        Some("\"localhost\""),
+    ),
+    (
+        "obs_port",
+        "Port of obs-websocket, see Tools > WebSocket Server Settings in OBS.",
+        Some("4455"),
+    ),
+    (
+        "obs_password",
+        "Password of obs-websocket, when authentication is turned on.",
+        Some("\"secret\""),
+    ),
+    (
+        "obs_input",
+        "Name of the OBS input (audio source) to mute and unmute.",
+        Some("\"Mic/Aux\""),
+    ),
+    (
+        "mqtt",
+        "Publish the state to an MQTT broker as retained messages, e.g. for Home Assistant. \
+         true or false.",
+        None,
+    ),
+    (
+        "mqtt_broker",
+        "Address of the MQTT broker, without TLS.",
+        Some("\"mqtt://localhost:1883\""),
+    ),
+    (
+        "mqtt_username",
+        "User to log in to the broker as, when it asks for one.",
+        Some("\"ptt\""),
+    ),
+    (
+        "mqtt_password",
+        "Password of mqtt_username.",
+        Some("\"secret\""),
+    ),
+    (
+        "mqtt_topic_prefix",
+        "Topics are published below it, e.g. ptt/active, ptt/muted and ptt/microphone.",
+        Some("\"ptt\""),
+    ),
+    (
+        "mqtt_commands",
+        "Accept enable, disable, toggle, mute, unmute and toggle_mute on <prefix>/set. Anyone \
+         who can publish to the broker can then control the microphone. true or false.",
+        None,
+    ),
+    (
+        "mqtt_discovery",
+        "Announce the state to Home Assistant through MQTT discovery, so that the entities \
+         show up by themselves. On unless false.",
+        None,
+    ),
+    (
+        "activity_history",
+        "List when the microphone was opened and closed, and by what, on the Diagnostics page. \
+         Kept in memory only. true or false.",
+        None,
+    ),
+    (
+        "activity_log",
+        "Also append the activity history to activity.log next to this file. true or false.",
+        None,
+    ),
+    (
+        "check_for_updates",
+        "Ask GitHub once a day whether there is a newer release. true or false.",
+        None,
+    ),
+    (
+        "save_stats",
+        "Keep daily totals of talk time in stats.toml next to this file. Off when unset. true or \
+         false.",
+        None,
+    ),
+    (
+        "autostart",
+        "Start at login. Only read inside Flatpak; elsewhere the autostart entry is used.",
+        None,
+    ),
+    (
+        "whisper_volume",
+        "Volume of the microphone while whispering, in percent from 0 to 100.",
+        None,
+    ),
+    (
+        "ui_scale",
+        "Size of the interface in percent, from 75 to 200.",
+        Some("150"),
+    ),
+    (
+        "active_profile",
+        "Name of the profile in use, one of the [profiles.*] tables below.",
+        None,
+    ),
+    (
+        "hidden_devices",
+        "PulseAudio source names left out of the microphone picker.",
+        None,
+    ),
+];
+
+const PROFILE_DOCS: &[(&str, &str, Option<&str>)] = &[
+    (
+        "trigger_hotkey",
+        "Hold to talk. See the hotkey format above.",
+        None,
+    ),
+    (
+        "toggle_active_hotkey",
+        "Enables or disables push-to-talk. See the hotkey format above.",
+        None,
+    ),
+    (
+        "whisper_hotkey",
+        "Hold to talk at the whisper volume. See the hotkey format above.",
+        None,
+    ),
+    (
+        "next_profile_hotkey",
+        "Switches to the next profile. See the hotkey format above.",
+        None,
+    ),
+    (
+        "microphone",
+        "PulseAudio source name of the microphone, chosen in the app when unset.",
+        Some("\"alsa_input.pci-0000_00_1f.3.analog-stereo\""),
+    ),
+    (
+        "max_hold_minutes",
+        "Force-mute if the trigger is held for this many minutes, e.g. after a lost key release. \
+         Off when unset or 0.",
+        Some("10"),
+    ),
+];
+
+// the last failed save, until the app picks it up to show it
+static SAVE_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+// set while the config file exists but can't be read, so that it isn't overwritten by accident
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+// settings that are switched between as a whole
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct Profile {
+    trigger_hotkey: Option<String>,
+    toggle_active_hotkey: Option<String>,
+    whisper_hotkey: Option<String>,
+    next_profile_hotkey: Option<String>,
+    microphone: Option<String>,
+    // force-mute if the trigger is held for longer than this, off when unset
+    max_hold_minutes: Option<u64>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum StoreError {
+    #[error("could not find the config file location")]
+    NoPath,
+    #[error("{} already exists", .0.display())]
+    Exists(PathBuf),
+    #[error("failed to serialize config: {0}")]
+    Serialize(#[from] toml::ser::Error),
+    #[error("failed to write config: {0}")]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileError {
+    #[error("profile names cannot be empty")]
+    EmptyName,
+    #[error("a profile called \"{0}\" already exists")]
+    Exists(String),
+    #[error("the last profile cannot be deleted")]
+    LastProfile,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
 pub struct Config {
+    // whether push-to-talk was enabled when the app was last used
+    active: Option<bool>,
+    theme: Option<ThemePreference>,
+    // overrides the language picked from the environment
+    language: Option<String>,
+    when_disabled: Option<RestingState>,
+    start_minimized: Option<bool>,
+    // flag the unfocused window when push-to-talk is toggled from the tray or a hotkey, on when
+    // unset
+    attention_on_change: Option<bool>,
+    // notify when push-to-talk is turned on or off with a hotkey, off when unset
+    notify_on_toggle: Option<bool>,
+    // play a sound from the desktop's sound theme, off when unset
+    sound_on_unmute: Option<bool>,
+    sound_on_mute: Option<bool>,
+    announce_live: Option<bool>,
+    announce_muted: Option<bool>,
+    announce_disabled: Option<bool>,
+    osd_on_toggle: Option<bool>,
+    osd_on_press: Option<bool>,
+    // the mute button of a headset, off when unset
+    headset_button: Option<bool>,
+    headset: Option<String>,
+    // the trigger from any keyboard when unset
+    trigger_keyboard: Option<String>,
+    // pausing media players while transmitting, off when unset
+    pause_media: Option<bool>,
+    pause_media_players: Option<Vec<String>>,
+    pause_media_after_ms: Option<u64>,
+    // muting the open mic while typing, off when unset
+    suppress_typing: Option<bool>,
+    typing_hold_off_ms: Option<u64>,
+    // applications that enable push-to-talk when they start
+    watched_apps: Option<Vec<String>>,
+    // and disable it when the last of them quits, off when unset
+    disable_with_watched_apps: Option<bool>,
+    on_lock: Option<LockBehavior>,
+    fail_policy: Option<FailPolicy>,
+    close_behavior: Option<CloseBehavior>,
+    // notify that the app keeps running after closing the window, on when unset
+    background_reminder: Option<bool>,
+    // enable push-to-talk after choosing a microphone for the first time, on when unset
+    auto_enable: Option<bool>,
+    // create the virtual mic again when something else removes it, instead of only saying so
+    recreate_virtual_mic: Option<bool>,
+    // percentage of the normal volume used while whispering
+    whisper_volume: Option<u8>,
+    // size of the whole UI, in percent
+    ui_scale: Option<u16>,
+    // draw the tray icon instead of looking it up in the icon theme
+    builtin_tray_icons: Option<bool>,
+    // the state as text next to the tray icon, off when unset
+    tray_label: Option<bool>,
+    // hex colors of the mic, overriding the theme's colors
+    muted_color: Option<String>,
+    transmitting_color: Option<String>,
+    disabled_color: Option<String>,
+    // show a mic icon on top of everything, including fullscreen windows, while transmitting
+    overlay: Option<bool>,
+    overlay_corner: Option<Corner>,
+    // in pixels and percent
+    overlay_margin: Option<u16>,
+    overlay_scale: Option<u16>,
+    overlay_opacity: Option<u8>,
+    overlay_show: Option<OverlayShow>,
+    overlay_transmitting_color: Option<String>,
+    overlay_muted_color: Option<String>,
+    // the name of a monitor, the compositor picks one when unset
+    overlay_output: Option<String>,
+    // ask GitHub about new releases, off when unset
+    check_for_updates: Option<bool>,
+    // keep daily talk time in a file, off when unset
+    save_stats: Option<bool>,
+    // when the mic was opened and closed, both off when unset
+    activity_history: Option<bool>,
+    activity_log: Option<bool>,
+    // remind about a mic left open, off when unset
+    hot_mic_warning_minutes: Option<u64>,
+    // shell commands run on state changes
+    on_unmute: Option<String>,
+    on_mute: Option<String>,
+    on_enable: Option<String>,
+    on_disable: Option<String>,
+    // programs that may use the control socket, any when unset
+    allowed_clients: Option<Vec<String>>,
+    // the control protocol over TCP, off when unset
+    remote_control: Option<bool>,
+    remote_address: Option<String>,
+    remote_port: Option<u16>,
+    remote_token: Option<String>,
+    // muting an input in OBS along with the mic, off when unset
+    obs_integration: Option<bool>,
+    obs_host: Option<String>,
+    obs_port: Option<u16>,
+    obs_password: Option<String>,
+    obs_input: Option<String>,
+    // publishing the state to an MQTT broker, off when unset
+    mqtt: Option<bool>,
+    mqtt_broker: Option<String>,
+    mqtt_username: Option<String>,
+    mqtt_password: Option<String>,
+    mqtt_topic_prefix: Option<String>,
+    mqtt_commands: Option<bool>,
+    mqtt_discovery: Option<bool>,
+    // only used in Flatpak, where the autostart entry can't be read back
+    autostart: Option<bool>,
+    active_profile: Option<String>,
+    // names of input devices left out of the microphone picker
+    #[serde(default)]
+    hidden_devices: Vec<String>,
+
+    // from before profiles existed, moved into the default profile when loading
+    #[serde(skip_serializing)]
     trigger_hotkey: Option<String>,
+    #[serde(skip_serializing)]
     toggle_active_hotkey: Option<String>,
+    #[serde(skip_serializing)]
+    microphone: Option<String>,
+    #[serde(skip_serializing)]
+    max_hold_minutes: Option<u64>,
+
+    // tables have to come last in TOML
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
 }
 
 impl Config {
     pub fn load() -> Result<Self, ConfyError> {
-        let config: Self = confy::load(APP_NAME, Some("config"))?;
+        match Self::path() {
+            Some(path) => Self::load_path(&path),
+            None => Self::loaded(confy::load(APP_NAME, Some(config_name().as_str()))),
+        }
+    }
+
+    fn load_path(path: &Path) -> Result<Self, ConfyError> {
+        Self::loaded(confy::load_path(path))
+    }
+
+    fn loaded(res: Result<Self, ConfyError>) -> Result<Self, ConfyError> {
+        // a missing file is created with the defaults, so errors mean the file is broken
+        READ_ONLY.store(res.is_err(), Ordering::Relaxed);
+
+        let mut config: Self = res?;
+        if config.profiles.is_empty() {
+            let profile = Profile {
+                trigger_hotkey: config.trigger_hotkey.take(),
+                toggle_active_hotkey: config.toggle_active_hotkey.take(),
+                microphone: config.microphone.take(),
+                max_hold_minutes: config.max_hold_minutes.take(),
+                ..Default::default()
+            };
+            config.profiles.insert(DEFAULT_PROFILE.into(), profile);
+        }
         Ok(config)
     }
 
-    pub fn hotkeys(&self) -> HotKeyConfig<HotKey> {
+    pub fn set_path(path: PathBuf) {
+        let _ = PATH_OVERRIDE.set(path);
+    }
+
+    // every read and write goes through here, so nothing ends up in the default location when
+    // another one was asked for
+    pub fn path() -> Option<PathBuf> {
+        PATH_OVERRIDE
+            .get()
+            .cloned()
+            .or_else(|| std::env::var_os(PATH_ENV).map(PathBuf::from))
+            .or_else(|| {
+                confy::get_configuration_file_path(APP_NAME, Some(config_name().as_str())).ok()
+            })
+    }
+
+    fn store(&self) {
+        let res = Self::path()
+            .ok_or(StoreError::NoPath)
+            .and_then(|path| Ok(write_atomic(&path, &toml::to_string_pretty(self)?)?));
+        if let Err(e) = res {
+            events::record(Level::Error, format!("Failed to save config: {e}"));
+            if let Ok(mut error) = SAVE_ERROR.lock() {
+                *error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Returns the error of the last save that failed since this was last called.
+    pub fn take_save_error() -> Option<String> {
+        SAVE_ERROR.lock().ok()?.take()
+    }
+
+    /// Replaces a broken config file with these settings.
+    pub fn overwrite(&self) {
+        READ_ONLY.store(false, Ordering::Relaxed);
+        self.store();
+    }
+
+    pub fn active_profile(&self) -> &str {
+        self.active_profile
+            .as_deref()
+            .filter(|name| self.profiles.contains_key(*name))
+            .or_else(|| self.profiles.keys().next().map(String::as_str))
+            .unwrap_or(DEFAULT_PROFILE)
+    }
+
+    pub fn profile_names(&self) -> Vec<String> {
+        self.profiles.keys().cloned().collect()
+    }
+
+    fn profile(&self) -> Profile {
+        self.profiles
+            .get(self.active_profile())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn profile_mut(&mut self) -> &mut Profile {
+        let name = self.active_profile().to_string();
+        self.profiles.entry(name).or_default()
+    }
+
+    pub fn hotkeys(&self) -> HotKeyConfig<Binding> {
+        let profile = self.profile();
         let default = HotKeyConfig::default();
-        let trigger = self
+        let trigger = profile
             .trigger_hotkey
             .as_deref()
-            .and_then(|t| HotKey::from_str(t).ok())
+            .and_then(|t| Binding::from_str(t).ok())
             .unwrap_or(default.trigger);
-        let toggle_active = self
+        let toggle_active = profile
             .toggle_active_hotkey
             .as_deref()
-            .and_then(|t| HotKey::from_str(t).ok())
+            .and_then(|t| Binding::from_str(t).ok())
             .unwrap_or(default.toggle_active);
+        let whisper = profile
+            .whisper_hotkey
+            .as_deref()
+            .and_then(|t| Binding::from_str(t).ok())
+            .unwrap_or(default.whisper);
+        let next_profile = profile
+            .next_profile_hotkey
+            .as_deref()
+            .and_then(|t| Binding::from_str(t).ok())
+            .unwrap_or(default.next_profile);
 
         HotKeyConfig {
             trigger,
             toggle_active,
+            whisper,
+            next_profile,
+        }
+    }
+
+    pub fn max_hold(&self) -> Option<Duration> {
+        self.profile()
+            .max_hold_minutes
+            .filter(|m| *m > 0)
+            .map(|m| Duration::from_secs(m.saturating_mul(60)))
+    }
+
+    /// Changes the max hold time without saving it, e.g. while a slider is being dragged.
+    pub fn set_max_hold_minutes(&mut self, minutes: u64) {
+        self.profile_mut().max_hold_minutes = (minutes > 0).then_some(minutes);
+    }
+
+    pub fn microphone(&self) -> Option<String> {
+        self.profile().microphone
+    }
+
+    pub fn active(&self) -> bool {
+        self.active.unwrap_or_default()
+    }
+
+    pub fn theme(&self) -> ThemePreference {
+        self.theme.clone().unwrap_or_default()
+    }
+
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    pub fn when_disabled(&self) -> RestingState {
+        self.when_disabled.unwrap_or_default()
+    }
+
+    pub fn on_lock(&self) -> LockBehavior {
+        self.on_lock.unwrap_or_default()
+    }
+
+    pub fn fail_policy(&self) -> FailPolicy {
+        self.fail_policy.unwrap_or_default()
+    }
+
+    pub fn close_behavior(&self) -> CloseBehavior {
+        self.close_behavior.unwrap_or_default()
+    }
+
+    pub fn background_reminder(&self) -> bool {
+        self.background_reminder.unwrap_or(true)
+    }
+
+    pub fn attention_on_change(&self) -> bool {
+        self.attention_on_change.unwrap_or(true)
+    }
+
+    pub fn notify_on_toggle(&self) -> bool {
+        self.notify_on_toggle.unwrap_or(false)
+    }
+
+    pub fn sound(&self, sound: Sound) -> bool {
+        match sound {
+            Sound::Unmute => self.sound_on_unmute,
+            Sound::Mute => self.sound_on_mute,
+        }
+        .unwrap_or(false)
+    }
+
+    pub fn announce(&self, announcement: Announcement) -> bool {
+        match announcement {
+            Announcement::Live => self.announce_live,
+            Announcement::Muted => self.announce_muted,
+            Announcement::Disabled => self.announce_disabled,
+        }
+        .unwrap_or(false)
+    }
+
+    pub fn osd_on_toggle(&self) -> bool {
+        self.osd_on_toggle.unwrap_or(false)
+    }
+
+    pub fn osd_on_press(&self) -> bool {
+        self.osd_on_press.unwrap_or(false)
+    }
+
+    pub fn headset_button(&self) -> bool {
+        self.headset_button.unwrap_or(false)
+    }
+
+    /// Unset for any headset.
+    pub fn headset(&self) -> Option<&str> {
+        self.headset.as_deref().filter(|name| !name.is_empty())
+    }
+
+    /// Unset for any keyboard.
+    pub fn trigger_keyboard(&self) -> Option<&str> {
+        self.trigger_keyboard.as_deref().filter(|id| !id.is_empty())
+    }
+
+    pub fn pause_media(&self) -> bool {
+        self.pause_media.unwrap_or(false)
+    }
+
+    pub fn pause_media_players(&self) -> Vec<String> {
+        self.pause_media_players.clone().unwrap_or_default()
+    }
+
+    pub fn pause_media_after(&self) -> Duration {
+        Duration::from_millis(
+            self.pause_media_after_ms
+                .unwrap_or(DEFAULT_PAUSE_MEDIA_AFTER_MS),
+        )
+    }
+
+    pub fn suppress_typing(&self) -> bool {
+        self.suppress_typing.unwrap_or(false)
+    }
+
+    pub fn typing_hold_off(&self) -> Duration {
+        Duration::from_millis(
+            self.typing_hold_off_ms
+                .unwrap_or(DEFAULT_TYPING_HOLD_OFF_MS),
+        )
+    }
+
+    pub fn watched_apps(&self) -> Vec<String> {
+        self.watched_apps.clone().unwrap_or_default()
+    }
+
+    pub fn disable_with_watched_apps(&self) -> bool {
+        self.disable_with_watched_apps.unwrap_or(false)
+    }
+
+    pub fn is_device_hidden(&self, name: &str) -> bool {
+        self.hidden_devices.iter().any(|n| n == name)
+    }
+
+    pub fn ui_scale(&self) -> u16 {
+        self.ui_scale
+            .unwrap_or(100)
+            .clamp(MIN_UI_SCALE, MAX_UI_SCALE)
+    }
+
+    pub fn whisper_volume(&self) -> u8 {
+        self.whisper_volume
+            .unwrap_or(DEFAULT_WHISPER_VOLUME)
+            .min(100)
+    }
+
+    pub fn auto_enable(&self) -> bool {
+        self.auto_enable.unwrap_or(true)
+    }
+
+    pub fn recreate_virtual_mic(&self) -> bool {
+        self.recreate_virtual_mic.unwrap_or_default()
+    }
+
+    pub fn start_minimized(&self) -> bool {
+        self.start_minimized.unwrap_or_default()
+    }
+
+    pub fn builtin_tray_icons(&self) -> bool {
+        self.builtin_tray_icons.unwrap_or_default()
+    }
+
+    pub fn tray_label(&self) -> bool {
+        self.tray_label.unwrap_or_default()
+    }
+
+    /// The color picked for `which`, if it's set and valid.
+    pub fn color(&self, which: IndicatorColor) -> Option<[u8; 3]> {
+        match which {
+            IndicatorColor::Muted => &self.muted_color,
+            IndicatorColor::Transmitting => &self.transmitting_color,
+            IndicatorColor::Disabled => &self.disabled_color,
+            IndicatorColor::OverlayTransmitting => &self.overlay_transmitting_color,
+            IndicatorColor::OverlayMuted => &self.overlay_muted_color,
+        }
+        .as_deref()
+        .and_then(parse_color)
+    }
+
+    /// Whether any of the colors were picked, rather than taken from the theme.
+    pub fn has_custom_colors(&self) -> bool {
+        IndicatorColor::ALL
+            .into_iter()
+            .any(|which| self.color(which).is_some())
+    }
+
+    pub fn hot_mic_warning(&self) -> Option<Duration> {
+        self.hot_mic_warning_minutes
+            .filter(|m| *m > 0)
+            .map(|m| Duration::from_secs(m.saturating_mul(60)))
+    }
+
+    /// Changes the hot mic warning without saving it, e.g. while a slider is being dragged.
+    pub fn set_hot_mic_warning_minutes(&mut self, minutes: u64) {
+        self.hot_mic_warning_minutes = (minutes > 0).then_some(minutes);
+    }
+
+    pub fn store_hot_mic_warning_minutes(&mut self, minutes: u64) {
+        self.update(|c| c.set_hot_mic_warning_minutes(minutes));
+    }
+
+    pub fn hook(&self, which: Hook) -> Option<&str> {
+        match which {
+            Hook::Unmute => &self.on_unmute,
+            Hook::Mute => &self.on_mute,
+            Hook::Enable => &self.on_enable,
+            Hook::Disable => &self.on_disable,
+        }
+        .as_deref()
+        .filter(|command| !command.trim().is_empty())
+    }
+
+    pub fn allowed_clients(&self) -> Vec<PathBuf> {
+        self.allowed_clients
+            .iter()
+            .flatten()
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    pub fn remote_control(&self) -> bool {
+        self.remote_control.unwrap_or(false)
+    }
+
+    pub fn remote_address(&self) -> String {
+        self.remote_address
+            .clone()
+            .unwrap_or_else(|| DEFAULT_REMOTE_ADDRESS.into())
+    }
+
+    pub fn remote_port(&self) -> u16 {
+        self.remote_port.unwrap_or(DEFAULT_REMOTE_PORT)
+    }
+
+    /// Unset when empty, remote control can't be used without one.
+    pub fn remote_token(&self) -> Option<&str> {
+        self.remote_token
+            .as_deref()
+            .filter(|token| !token.trim().is_empty())
+    }
+
+    pub fn obs_integration(&self) -> bool {
+        self.obs_integration.unwrap_or(false)
+    }
+
+    pub fn obs_host(&self) -> String {
+        self.obs_host
+            .clone()
+            .unwrap_or_else(|| DEFAULT_OBS_HOST.into())
+    }
+
+    pub fn obs_port(&self) -> u16 {
+        self.obs_port.unwrap_or(DEFAULT_OBS_PORT)
+    }
+
+    pub fn obs_password(&self) -> Option<&str> {
+        self.obs_password
+            .as_deref()
+            .filter(|password| !password.is_empty())
+    }
+
+    /// Unset when empty, there's nothing to mute without one.
+    pub fn obs_input(&self) -> Option<&str> {
+        self.obs_input
+            .as_deref()
+            .filter(|input| !input.trim().is_empty())
+    }
+
+    pub fn mqtt(&self) -> bool {
+        self.mqtt.unwrap_or(false)
+    }
+
+    pub fn mqtt_broker(&self) -> String {
+        self.mqtt_broker
+            .clone()
+            .unwrap_or_else(|| DEFAULT_MQTT_BROKER.into())
+    }
+
+    pub fn mqtt_username(&self) -> Option<&str> {
+        self.mqtt_username
+            .as_deref()
+            .filter(|username| !username.is_empty())
+    }
+
+    pub fn mqtt_password(&self) -> Option<&str> {
+        self.mqtt_password
+            .as_deref()
+            .filter(|password| !password.is_empty())
+    }
+
+    /// Without slashes at either end, so that topics can be put after it.
+    pub fn mqtt_topic_prefix(&self) -> String {
+        self.mqtt_topic_prefix
+            .as_deref()
+            .map(|prefix| prefix.trim_matches('/'))
+            .filter(|prefix| !prefix.is_empty())
+            .unwrap_or(DEFAULT_MQTT_PREFIX)
+            .to_string()
+    }
+
+    pub fn mqtt_commands(&self) -> bool {
+        self.mqtt_commands.unwrap_or(false)
+    }
+
+    pub fn mqtt_discovery(&self) -> bool {
+        self.mqtt_discovery.unwrap_or(true)
+    }
+
+    pub fn activity_history(&self) -> bool {
+        self.activity_history.unwrap_or(false)
+    }
+
+    /// Whether the history goes to disk too, which only happens while it's kept at all.
+    pub fn activity_log(&self) -> bool {
+        self.activity_history() && self.activity_log.unwrap_or(false)
+    }
+
+    pub fn check_for_updates(&self) -> bool {
+        self.check_for_updates.unwrap_or(false)
+    }
+
+    pub fn save_stats(&self) -> bool {
+        self.save_stats.unwrap_or(false)
+    }
+
+    pub fn overlay(&self) -> bool {
+        self.overlay.unwrap_or_default()
+    }
+
+    pub fn overlay_corner(&self) -> Corner {
+        self.overlay_corner.unwrap_or_default()
+    }
+
+    pub fn overlay_margin(&self) -> u16 {
+        self.overlay_margin
+            .unwrap_or(DEFAULT_OVERLAY_MARGIN)
+            .min(MAX_OVERLAY_MARGIN)
+    }
+
+    pub fn overlay_scale(&self) -> u16 {
+        self.overlay_scale
+            .unwrap_or(100)
+            .clamp(MIN_OVERLAY_SCALE, MAX_OVERLAY_SCALE)
+    }
+
+    pub fn overlay_opacity(&self) -> u8 {
+        self.overlay_opacity
+            .unwrap_or(100)
+            .clamp(MIN_OVERLAY_OPACITY, 100)
+    }
+
+    pub fn overlay_show(&self) -> OverlayShow {
+        self.overlay_show.unwrap_or_default()
+    }
+
+    pub fn overlay_output(&self) -> Option<&str> {
+        self.overlay_output.as_deref()
+    }
+
+    pub fn autostart(&self) -> bool {
+        self.autostart.unwrap_or_default()
+    }
+
+    pub fn store_hotkeys(&mut self, hotkeys: &HotKeyConfig<Binding>) {
+        self.update(|c| {
+            let profile = c.profile_mut();
+            profile.trigger_hotkey = Some(hotkeys.trigger.to_string());
+            profile.toggle_active_hotkey = Some(hotkeys.toggle_active.to_string());
+            profile.whisper_hotkey = Some(hotkeys.whisper.to_string());
+            profile.next_profile_hotkey = Some(hotkeys.next_profile.to_string());
+        });
+    }
+
+    pub fn store_microphone(&mut self, microphone: &str) {
+        self.update(|c| c.profile_mut().microphone = Some(microphone.to_string()));
+    }
+
+    pub fn store_max_hold_minutes(&mut self, minutes: u64) {
+        self.update(|c| c.set_max_hold_minutes(minutes));
+    }
+
+    pub fn store_active(&mut self, active: bool) {
+        self.update(|c| c.active = Some(active));
+    }
+
+    pub fn store_theme(&mut self, theme: ThemePreference) {
+        self.update(|c| c.theme = Some(theme));
+    }
+
+    pub fn store_when_disabled(&mut self, state: RestingState) {
+        self.update(|c| c.when_disabled = Some(state));
+    }
+
+    pub fn store_on_lock(&mut self, behavior: LockBehavior) {
+        self.update(|c| c.on_lock = Some(behavior));
+    }
+
+    pub fn store_fail_policy(&mut self, policy: FailPolicy) {
+        self.update(|c| c.fail_policy = Some(policy));
+    }
+
+    pub fn store_close_behavior(&mut self, behavior: CloseBehavior) {
+        self.update(|c| c.close_behavior = Some(behavior));
+    }
+
+    pub fn store_background_reminder(&mut self, remind: bool) {
+        self.update(|c| c.background_reminder = Some(remind));
+    }
+
+    pub fn store_attention_on_change(&mut self, attention: bool) {
+        self.update(|c| c.attention_on_change = Some(attention));
+    }
+
+    pub fn store_notify_on_toggle(&mut self, notify: bool) {
+        self.update(|c| c.notify_on_toggle = Some(notify));
+    }
+
+    pub fn store_sound(&mut self, sound: Sound, play: bool) {
+        self.update(|c| match sound {
+            Sound::Unmute => c.sound_on_unmute = Some(play),
+            Sound::Mute => c.sound_on_mute = Some(play),
+        });
+    }
+
+    pub fn store_announce(&mut self, announcement: Announcement, announce: bool) {
+        self.update(|c| match announcement {
+            Announcement::Live => c.announce_live = Some(announce),
+            Announcement::Muted => c.announce_muted = Some(announce),
+            Announcement::Disabled => c.announce_disabled = Some(announce),
+        });
+    }
+
+    pub fn store_osd_on_toggle(&mut self, show: bool) {
+        self.update(|c| c.osd_on_toggle = Some(show));
+    }
+
+    pub fn store_osd_on_press(&mut self, show: bool) {
+        self.update(|c| c.osd_on_press = Some(show));
+    }
+
+    pub fn store_headset_button(&mut self, enabled: bool) {
+        self.update(|c| c.headset_button = Some(enabled));
+    }
+
+    pub fn store_headset(&mut self, name: Option<String>) {
+        self.update(|c| c.headset = name);
+    }
+
+    pub fn store_trigger_keyboard(&mut self, id: Option<String>) {
+        self.update(|c| c.trigger_keyboard = id);
+    }
+
+    pub fn store_pause_media(&mut self, pause: bool) {
+        self.update(|c| c.pause_media = Some(pause));
+    }
+
+    pub fn store_suppress_typing(&mut self, suppress: bool) {
+        self.update(|c| c.suppress_typing = Some(suppress));
+    }
+
+    pub fn store_watched_apps(&mut self, apps: Vec<String>) {
+        self.update(|c| c.watched_apps = Some(apps));
+    }
+
+    pub fn store_disable_with_watched_apps(&mut self, disable: bool) {
+        self.update(|c| c.disable_with_watched_apps = Some(disable));
+    }
+
+    pub fn store_color(&mut self, which: IndicatorColor, color: Option<[u8; 3]>) {
+        let hex = color.map(format_color);
+        self.update(|c| {
+            *match which {
+                IndicatorColor::Muted => &mut c.muted_color,
+                IndicatorColor::Transmitting => &mut c.transmitting_color,
+                IndicatorColor::Disabled => &mut c.disabled_color,
+                IndicatorColor::OverlayTransmitting => &mut c.overlay_transmitting_color,
+                IndicatorColor::OverlayMuted => &mut c.overlay_muted_color,
+            } = hex;
+        });
+    }
+
+    pub fn store_device_hidden(&mut self, name: &str, hidden: bool) {
+        self.update(|c| {
+            c.hidden_devices.retain(|n| n != name);
+            if hidden {
+                c.hidden_devices.push(name.to_string());
+            }
+        });
+    }
+
+    pub fn store_whisper_volume(&mut self, percent: u8) {
+        self.update(|c| c.whisper_volume = Some(percent.min(100)));
+    }
+
+    pub fn store_ui_scale(&mut self, percent: u16) {
+        self.update(|c| c.ui_scale = Some(percent.clamp(MIN_UI_SCALE, MAX_UI_SCALE)));
+    }
+
+    pub fn store_auto_enable(&mut self, enabled: bool) {
+        self.update(|c| c.auto_enable = Some(enabled));
+    }
+
+    pub fn store_recreate_virtual_mic(&mut self, recreate: bool) {
+        self.update(|c| c.recreate_virtual_mic = Some(recreate));
+    }
+
+    pub fn store_builtin_tray_icons(&mut self, builtin: bool) {
+        self.update(|c| c.builtin_tray_icons = Some(builtin));
+    }
+
+    pub fn store_tray_label(&mut self, label: bool) {
+        self.update(|c| c.tray_label = Some(label));
+    }
+
+    pub fn store_activity_history(&mut self, keep: bool) {
+        self.update(|c| c.activity_history = Some(keep));
+    }
+
+    pub fn store_activity_log(&mut self, log: bool) {
+        self.update(|c| c.activity_log = Some(log));
+    }
+
+    pub fn store_check_for_updates(&mut self, check: bool) {
+        self.update(|c| c.check_for_updates = Some(check));
+    }
+
+    pub fn store_remote_control(&mut self, enabled: bool) {
+        self.update(|c| c.remote_control = Some(enabled));
+    }
+
+    pub fn store_obs_integration(&mut self, enabled: bool) {
+        self.update(|c| c.obs_integration = Some(enabled));
+    }
+
+    pub fn store_mqtt(&mut self, enabled: bool) {
+        self.update(|c| c.mqtt = Some(enabled));
+    }
+
+    pub fn store_save_stats(&mut self, save: bool) {
+        self.update(|c| c.save_stats = Some(save));
+    }
+
+    pub fn store_overlay(&mut self, overlay: bool) {
+        self.update(|c| c.overlay = Some(overlay));
+    }
+
+    pub fn store_overlay_corner(&mut self, corner: Corner) {
+        self.update(|c| c.overlay_corner = Some(corner));
+    }
+
+    pub fn set_overlay_margin(&mut self, margin: u16) {
+        self.overlay_margin = Some(margin.min(MAX_OVERLAY_MARGIN));
+    }
+
+    pub fn store_overlay_margin(&mut self, margin: u16) {
+        self.update(|c| c.set_overlay_margin(margin));
+    }
+
+    pub fn set_overlay_scale(&mut self, percent: u16) {
+        self.overlay_scale = Some(percent.clamp(MIN_OVERLAY_SCALE, MAX_OVERLAY_SCALE));
+    }
+
+    pub fn store_overlay_scale(&mut self, percent: u16) {
+        self.update(|c| c.set_overlay_scale(percent));
+    }
+
+    pub fn set_overlay_opacity(&mut self, percent: u8) {
+        self.overlay_opacity = Some(percent.clamp(MIN_OVERLAY_OPACITY, 100));
+    }
+
+    pub fn store_overlay_opacity(&mut self, percent: u8) {
+        self.update(|c| c.set_overlay_opacity(percent));
+    }
+
+    pub fn store_overlay_show(&mut self, show: OverlayShow) {
+        self.update(|c| c.overlay_show = Some(show));
+    }
+
+    pub fn store_overlay_output(&mut self, output: Option<String>) {
+        self.update(|c| c.overlay_output = output);
+    }
+
+    pub fn store_autostart(&mut self, autostart: bool) {
+        self.update(|c| c.autostart = Some(autostart));
+    }
+
+    pub fn store_active_profile(&mut self, name: &str) {
+        self.update(|c| c.active_profile = Some(name.to_string()));
+    }
+
+    /// Creates a new profile as a copy of the active one, and switches to it.
+    pub fn create_profile(&mut self, name: &str) -> Result<(), ProfileError> {
+        self.check_profile_name(name)?;
+        self.update(|c| {
+            let profile = c.profile();
+            c.profiles.insert(name.to_string(), profile);
+            c.active_profile = Some(name.to_string());
+        });
+        Ok(())
+    }
+
+    pub fn rename_profile(&mut self, old: &str, new: &str) -> Result<(), ProfileError> {
+        self.check_profile_name(new)?;
+        self.update(|c| {
+            let profile = c.profiles.remove(old).unwrap_or_default();
+            c.profiles.insert(new.to_string(), profile);
+            if c.active_profile.as_deref() == Some(old) {
+                c.active_profile = Some(new.to_string());
+            }
+        });
+        Ok(())
+    }
+
+    pub fn delete_profile(&mut self, name: &str) -> Result<(), ProfileError> {
+        if self.profiles.len() <= 1 {
+            return Err(ProfileError::LastProfile);
+        }
+        self.update(|c| {
+            c.profiles.remove(name);
+        });
+        Ok(())
+    }
+
+    fn check_profile_name(&self, name: &str) -> Result<(), ProfileError> {
+        if name.trim().is_empty() {
+            Err(ProfileError::EmptyName)
+        } else if self.profiles.contains_key(name) {
+            Err(ProfileError::Exists(name.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+
+    // several parts of the app keep their own copy, so changes are applied on top of what is
+    // currently on disk to avoid overwriting each other
+    fn update(&mut self, f: impl FnOnce(&mut Self)) {
+        let on_disk = Self::load().ok();
+        if let Some(config) = &on_disk {
+            *self = config.clone();
+        }
+        f(self);
+
+        // skip writes that wouldn't change anything
+        if !READ_ONLY.load(Ordering::Relaxed) && on_disk.as_ref() != Some(self) {
+            self.store();
+        }
+    }
+}
+
+/// Writes a config with every field set to its default and described in comments, returning
+/// where it was written.
+pub fn write_default() -> Result<PathBuf, StoreError> {
+    let path = Config::path().ok_or(StoreError::NoPath)?;
+    if path.exists() {
+        return Err(StoreError::Exists(path));
+    }
+
+    let hotkeys: HotKeyConfig<Binding> = HotKeyConfig::default();
+    let profile = Profile {
+        trigger_hotkey: Some(hotkeys.trigger.to_string()),
+        toggle_active_hotkey: Some(hotkeys.toggle_active.to_string()),
+        whisper_hotkey: Some(hotkeys.whisper.to_string()),
+        next_profile_hotkey: Some(hotkeys.next_profile.to_string()),
+        microphone: None,
+        max_hold_minutes: None,
+    };
+    let config = Config {
+        active: Some(false),
+        theme: Some(ThemePreference::default()),
+        when_disabled: Some(RestingState::default()),
+        start_minimized: Some(false),
+        on_lock: Some(LockBehavior::default()),
+        fail_policy: Some(FailPolicy::default()),
+        close_behavior: Some(CloseBehavior::default()),
+        background_reminder: Some(true),
+        attention_on_change: Some(true),
+        notify_on_toggle: Some(false),
+        sound_on_unmute: Some(false),
+        sound_on_mute: Some(false),
+        announce_live: Some(false),
+        announce_muted: Some(false),
+        announce_disabled: Some(false),
+        osd_on_toggle: Some(false),
+        osd_on_press: Some(false),
+        auto_enable: Some(true),
+        builtin_tray_icons: Some(false),
+        whisper_volume: Some(DEFAULT_WHISPER_VOLUME),
+        autostart: Some(false),
+        active_profile: Some(DEFAULT_PROFILE.into()),
+        profiles: BTreeMap::from([(DEFAULT_PROFILE.into(), profile.clone())]),
+        ..Default::default()
+    };
+
+    let mut contents = String::new();
+    for line in HOTKEY_FORMAT {
+        let _ = writeln!(contents, "# {line}");
+    }
+    contents.push('\n');
+    write_documented(&mut contents, &toml::Table::try_from(&config)?, CONFIG_DOCS);
+    let _ = write!(contents, "\n[profiles.{DEFAULT_PROFILE}]\n");
+    write_documented(
+        &mut contents,
+        &toml::Table::try_from(&profile)?,
+        PROFILE_DOCS,
+    );
+
+    write_atomic(&path, &contents)?;
+    Ok(path)
+}
+
+// writes each documented field of `table`, commenting out the example for unset fields
+fn write_documented(out: &mut String, table: &toml::Table, docs: &[(&str, &str, Option<&str>)]) {
+    // every serialized field needs a description, otherwise it would be left out
+    debug_assert!(
+        table
+            .iter()
+            .filter(|(_, value)| !value.is_table())
+            .all(|(key, _)| docs.iter().any(|(name, ..)| name == key)),
+        "undocumented config field"
+    );
+
+    for (key, description, example) in docs {
+        let _ = writeln!(out, "# {description}");
+        let _ = match (table.get(*key), example) {
+            (Some(value), _) => writeln!(out, "{key} = {value}"),
+            (None, Some(example)) => writeln!(out, "# {key} = {example}"),
+            (None, None) => Ok(()),
+        };
+    }
+}
+
+/// Parses "#rrggbb", the # being optional.
+pub fn parse_color(hex: &str) -> Option<[u8; 3]> {
+    let hex = hex.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+pub fn format_color([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+// writes to a temporary file next to the config and renames it over the old one, so that an
+// interrupted write never leaves a truncated config behind
+pub fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    // the config holds passwords and tokens, so only the user gets to read it. the mode only
+    // applies to new files, which is why a leftover from an earlier write goes first
+    let _ = std::fs::remove_file(&tmp_path);
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&tmp_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{os::unix::fs::PermissionsExt, pin::pin};
+
+    use iced::futures::StreamExt;
+
+    use super::*;
+
+    // a file of its own in the temporary directory, gone when dropped
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "global-ptt-test-{}-{name}.toml",
+                std::process::id()
+            ));
+            let _ = std::fs::remove_file(&path);
+            Self(path)
+        }
+
+        fn with(name: &str, contents: &str) -> Self {
+            let file = Self::new(name);
+            std::fs::write(&file.0, contents).expect("temporary file can be written");
+            file
         }
     }
 
-    pub fn store_hotkeys(&mut self, hotkeys: &HotKeyConfig<HotKey>) {
-        self.trigger_hotkey = Some(hotkeys.trigger.into_string());
-        self.toggle_active_hotkey = Some(hotkeys.toggle_active.into_string());
-        let _ = confy::store(APP_NAME, Some("config"), self);
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn malformed_files_are_errors_and_left_alone() {
+        for (name, contents) in [
+            ("unclosed-table", "active = true\n[profiles.Default\n"),
+            ("unquoted-string", "language = de\n"),
+            ("wrong-type", "active = \"yes\"\n"),
+            ("duplicate-key", "active = true\nactive = false\n"),
+            (
+                "wrong-profile-type",
+                "[profiles.Default]\nmax_hold_minutes = \"ten\"\n",
+            ),
+        ] {
+            let file = TempFile::with(name, contents);
+            assert!(Config::load_path(&file.0).is_err(), "{name} was accepted");
+            let after = std::fs::read_to_string(&file.0).expect("file is still there");
+            assert_eq!(after, contents, "{name} was overwritten");
+        }
+    }
+
+    #[test]
+    fn missing_files_get_the_defaults() {
+        let file = TempFile::new("missing");
+        let config = Config::load_path(&file.0).expect("defaults are used");
+        assert_eq!(config.hotkeys(), HotKeyConfig::default());
+    }
+
+    #[test]
+    fn files_replaced_by_a_rename_are_noticed() {
+        let file = TempFile::with("watched", "active = true\n");
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime can be built");
+        runtime.block_on(async {
+            let mut changes = pin!(changes_of(Some(file.0.clone())));
+            let path = file.0.clone();
+            // once the stream is waited on and watching
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                let tmp = path.with_extension("toml.tmp");
+                std::fs::write(&tmp, "active = false\n").expect("temporary file can be written");
+                std::fs::rename(&tmp, &path).expect("file can be replaced");
+            });
+            let change = tokio::time::timeout(Duration::from_secs(5), changes.next()).await;
+            assert_eq!(change, Ok(Some(())));
+        });
+    }
+
+    #[test]
+    fn unbound_actions_stay_unbound() {
+        let file = TempFile::with("unbound", "[profiles.Default]\nwhisper_hotkey = \"none\"\n");
+        let config = Config::load_path(&file.0).expect("valid config");
+        let hotkeys = config.hotkeys();
+        assert_eq!(hotkeys.whisper, Binding::Unbound);
+        assert_eq!(hotkeys.trigger, HotKeyConfig::default().trigger);
+    }
+
+    #[test]
+    fn settings_from_before_profiles_move_into_the_default_one() {
+        let file = TempFile::with(
+            "legacy",
+            "trigger_hotkey = \"shift+F13\"\nmicrophone = \"USB Mic\"\n",
+        );
+        let config = Config::load_path(&file.0).expect("valid config");
+        assert_eq!(config.hotkeys().trigger.to_string(), "shift+F13");
+        assert_eq!(config.profile().microphone.as_deref(), Some("USB Mic"));
+    }
+
+    #[test]
+    fn interrupted_writes_leave_the_old_file() {
+        let file = TempFile::with("interrupted", "active = true\n");
+        let mut tmp_path = file.0.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        // what a power loss halfway through leaves behind
+        std::fs::write(&tmp_path, "active = fa").expect("temporary file can be written");
+        let config = Config::load_path(&file.0).expect("the old file is still valid");
+        assert!(config.active());
+
+        // a write that fails before the rename
+        std::fs::remove_file(&tmp_path).expect("temporary file can be removed");
+        std::fs::create_dir(&tmp_path).expect("directory can be created");
+        assert!(write_atomic(&file.0, "active = false\n").is_err());
+        std::fs::remove_dir(&tmp_path).expect("directory can be removed");
+        let after = std::fs::read_to_string(&file.0).expect("file is still there");
+        assert_eq!(after, "active = true\n");
+
+        // and the next one goes through, readable only by the user
+        std::fs::write(&tmp_path, "active = fa").expect("temporary file can be written");
+        write_atomic(&file.0, "active = false\n").expect("file can be written");
+        let after = std::fs::read_to_string(&file.0).expect("file is still there");
+        assert_eq!(after, "active = false\n");
+        let mode = std::fs::metadata(&file.0)
+            .expect("file is there")
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(!tmp_path.exists());
+    }
+
+    #[test]
+    fn every_field_is_documented() {
+        // unset fields are left out of TOML, but not out of JSON
+        let fields = |value: serde_json::Value| -> Vec<String> {
+            let serde_json::Value::Object(fields) = value else {
+                panic!("not serialized as an object");
+            };
+            fields.into_iter().map(|(key, _)| key).collect()
+        };
+        for (fields, docs) in [
+            (
+                fields(serde_json::to_value(Config::default()).expect("serializable")),
+                CONFIG_DOCS,
+            ),
+            (
+                fields(serde_json::to_value(Profile::default()).expect("serializable")),
+                PROFILE_DOCS,
+            ),
+        ] {
+            for field in fields.iter().filter(|field| *field != "profiles") {
+                assert!(
+                    docs.iter().any(|(name, ..)| name == field),
+                    "{field} is undocumented"
+                );
+            }
+            for (name, ..) in docs {
+                assert!(
+                    fields.iter().any(|field| field == name),
+                    "{name} isn't a field"
+                );
+            }
+        }
     }
 }
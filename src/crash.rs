@@ -0,0 +1,225 @@
+use std::{
+    panic,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        mpsc,
+    },
+    thread::{self, ThreadId},
+    time::Duration,
+};
+
+use crate::{
+    config::FailPolicy,
+    pulse::{self, PulseAudioState},
+};
+
+// an audio server that doesn't answer mustn't hold up the panic
+const APPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+const NONE: u8 = 0;
+const CLOSED: u8 = 1;
+const OPEN: u8 = 2;
+
+// the hook can run on any thread, so the policy isn't taken from the app
+static POLICY: AtomicU8 = AtomicU8::new(NONE);
+
+// what the policy needs of the audio server, so that it can be tried without one
+trait AudioServer {
+    fn set_mute(&mut self, mute: bool) -> Result<(), pulse::Error>;
+}
+
+impl AudioServer for PulseAudioState {
+    fn set_mute(&mut self, mute: bool) -> Result<(), pulse::Error> {
+        PulseAudioState::set_mute(self, mute)
+    }
+}
+
+/// Applies the policy set with [`set_policy`] to the virtual mic when a panic ends the app,
+/// after the panic message was printed. The virtual mic outlives the app, since its module
+/// belongs to the audio server. Call this from the main thread, which runs the UI.
+pub fn install() {
+    install_with(PulseAudioState::init);
+}
+
+// with the audio server `connect` makes, so that the hook can be tried without one
+fn install_with<S: AudioServer + 'static>(connect: fn() -> Result<S, pulse::Error>) {
+    let main = thread::current().id();
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        previous(info);
+        if ends_process(main) {
+            apply(connect);
+        }
+    }));
+}
+
+/// What a panic leaves the virtual mic in, nothing when unset, e.g. in a dry run.
+pub fn set_policy(policy: Option<FailPolicy>) {
+    let policy = match policy {
+        None => NONE,
+        Some(FailPolicy::Closed) => CLOSED,
+        Some(FailPolicy::Open) => OPEN,
+    };
+    POLICY.store(policy, Ordering::Relaxed);
+}
+
+fn policy() -> Option<FailPolicy> {
+    match POLICY.load(Ordering::Relaxed) {
+        CLOSED => Some(FailPolicy::Closed),
+        OPEN => Some(FailPolicy::Open),
+        _ => None,
+    }
+}
+
+// a panic anywhere else only ends its own thread, e.g. one of the runtime's workers, and the app
+// carries on
+fn ends_process(main: ThreadId) -> bool {
+    cfg!(panic = "abort") || thread::current().id() == main
+}
+
+fn leaves_muted(policy: FailPolicy) -> bool {
+    match policy {
+        FailPolicy::Closed => true,
+        FailPolicy::Open => false,
+    }
+}
+
+fn enforce(policy: FailPolicy, server: &mut impl AudioServer) -> Result<(), pulse::Error> {
+    server.set_mute(leaves_muted(policy))
+}
+
+// through a connection of its own, since the app's may be what panicked, and on a thread of its
+// own, since it may be this one
+fn apply<S: AudioServer + 'static>(connect: fn() -> Result<S, pulse::Error>) {
+    let Some(policy) = policy() else {
+        return;
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let spawned = thread::Builder::new()
+        .name("fail policy".into())
+        .spawn(move || {
+            let res = connect().and_then(|mut server| enforce(policy, &mut server));
+            let _ = tx.send(res);
+        });
+    if spawned.is_err() {
+        eprintln!("Failed to apply the fail policy to the virtual microphone");
+        return;
+    }
+
+    let state = if leaves_muted(policy) {
+        "muted"
+    } else {
+        "open"
+    };
+    match rx.recv_timeout(APPLY_TIMEOUT) {
+        Ok(Ok(())) => eprintln!("Left the virtual microphone {state}"),
+        Ok(Err(e)) => eprintln!("Failed to leave the virtual microphone {state}: {e}"),
+        Err(_) => eprintln!("The audio server didn't answer, the virtual microphone stays as is"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Mutex, PoisonError};
+
+    use super::*;
+
+    // the policy is shared by the whole process
+    static POLICY_LOCK: Mutex<()> = Mutex::new(());
+
+    // remembers what it was told, refusing everything when `refuse` is set
+    #[derive(Default)]
+    struct MockServer {
+        refuse: bool,
+        mute_calls: Vec<bool>,
+    }
+
+    impl AudioServer for MockServer {
+        fn set_mute(&mut self, mute: bool) -> Result<(), pulse::Error> {
+            self.mute_calls.push(mute);
+            if self.refuse {
+                Err(pulse::Error::Refused)
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[test]
+    fn policies_leave_the_mic_as_they_say() {
+        for (policy, mute) in [(FailPolicy::Closed, true), (FailPolicy::Open, false)] {
+            let mut server = MockServer::default();
+            enforce(policy, &mut server).expect("mock server accepts");
+            assert_eq!(server.mute_calls, [mute]);
+        }
+    }
+
+    #[test]
+    fn refusals_are_reported() {
+        let mut server = MockServer {
+            refuse: true,
+            ..Default::default()
+        };
+        assert!(enforce(FailPolicy::Closed, &mut server).is_err());
+    }
+
+    #[test]
+    fn the_policy_is_kept_for_the_hook() {
+        let _lock = POLICY_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        for set in [None, Some(FailPolicy::Closed), Some(FailPolicy::Open)] {
+            set_policy(set);
+            assert_eq!(policy(), set);
+        }
+        set_policy(None);
+    }
+
+    #[test]
+    fn only_panics_on_the_main_thread_end_the_app() {
+        let main = thread::current().id();
+        assert!(ends_process(main));
+        let other = thread::spawn(move || ends_process(main))
+            .join()
+            .expect("thread finishes");
+        assert_eq!(other, cfg!(panic = "abort"));
+    }
+
+    // what the hook below did, in order
+    static HOOK_CALLS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    fn record(call: String) {
+        HOOK_CALLS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(call);
+    }
+
+    struct RecordingServer;
+
+    impl AudioServer for RecordingServer {
+        fn set_mute(&mut self, mute: bool) -> Result<(), pulse::Error> {
+            record(format!("set_mute({mute})"));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn the_hook_applies_the_policy_after_the_previous_one() {
+        let _lock = POLICY_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        set_policy(Some(FailPolicy::Closed));
+        let original = panic::take_hook();
+        panic::set_hook(Box::new(|_| record("previous".into())));
+        install_with(|| Ok(RecordingServer));
+
+        // this thread is the one the hook was installed from, like the UI's
+        let _ = panic::catch_unwind(|| panic!("testing the fail policy"));
+
+        panic::set_hook(original);
+        set_policy(None);
+        let calls = HOOK_CALLS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        assert_eq!(calls, ["previous", "set_mute(true)"]);
+    }
+}
@@ -0,0 +1,329 @@
+// the code generated for the interfaces passes on arguments that are named to be unused
+#![allow(clippy::used_underscore_binding)]
+
+use std::{collections::HashMap, sync::Arc};
+
+use iced::{
+    futures::{
+        FutureExt, SinkExt, Stream, StreamExt,
+        channel::mpsc::{Sender, channel},
+        lock::Mutex,
+    },
+    stream,
+};
+use zbus::{
+    Connection,
+    fdo::{self, RequestNameFlags, RequestNameReply},
+    interface,
+    object_server::SignalEmitter,
+    zvariant::{OwnedValue, Value},
+};
+
+use crate::{
+    app::Msg,
+    instance,
+    ipc::{Request, Snapshot},
+};
+
+const PATH: &str = "/";
+// where ksni puts the tray item
+const TRAY_PATH: &str = "/StatusNotifierItem";
+const APPLICATION_INTERFACE: &str = "org.freedesktop.Application";
+
+// where org.freedesktop.Application is expected, derived from the app id
+fn application_path() -> String {
+    format!("/{}", instance::app_id().replace('.', "/"))
+}
+
+/// Whether this instance got the well-known name, which decides the one that keeps running.
+pub enum Claim {
+    Owner(Connection),
+    // another instance has it, and can be reached through this connection
+    Taken(Connection),
+}
+
+/// The state and controls on the session bus, for desktop widgets and scripts.
+#[derive(Debug)]
+pub struct Service {
+    msg_sender: Arc<Mutex<Sender<Msg>>>,
+    state: Snapshot,
+}
+
+impl Service {
+    pub fn new() -> (Self, impl Stream<Item = Msg>) {
+        let (msg_sender, mut msg_receiver) = channel(10);
+        let stream = stream::channel(10, async move |mut tx| {
+            while let Some(msg) = msg_receiver.next().await {
+                let _ = tx.send(msg).now_or_never();
+            }
+        });
+
+        (
+            Self {
+                msg_sender: Arc::new(Mutex::new(msg_sender)),
+                state: Snapshot {
+                    active: false,
+                    muted: true,
+                    microphone: None,
+                },
+            },
+            stream,
+        )
+    }
+
+    /// Puts the service on the bus and tries to take the app's well-known name. The name is
+    /// never queued for, whoever holds it keeps it.
+    pub async fn claim(self) -> zbus::Result<Claim> {
+        let application = Application {
+            msg_sender: self.msg_sender.clone(),
+        };
+        let connection = zbus::connection::Builder::session()?
+            .serve_at(PATH, self)?
+            .serve_at(application_path(), application)?
+            .build()
+            .await?;
+        let reply = connection
+            .request_name_with_flags(instance::app_id(), RequestNameFlags::DoNotQueue.into())
+            .await?;
+        Ok(match reply {
+            RequestNameReply::PrimaryOwner | RequestNameReply::AlreadyOwner => {
+                Claim::Owner(connection)
+            }
+            RequestNameReply::InQueue | RequestNameReply::Exists => Claim::Taken(connection),
+        })
+    }
+
+    async fn send(&self, msg: Msg) {
+        let _ = self.msg_sender.lock().await.send(msg).await;
+    }
+}
+
+#[interface(name = "com.github.Adamskye.GlobalPushToTalk")]
+impl Service {
+    #[zbus(property)]
+    fn active(&self) -> bool {
+        self.state.active
+    }
+
+    #[zbus(property)]
+    fn muted(&self) -> bool {
+        self.state.muted
+    }
+
+    /// Description of the selected microphone, empty when there is none.
+    #[zbus(property)]
+    fn microphone(&self) -> String {
+        self.state.microphone.clone().unwrap_or_default()
+    }
+
+    async fn set_active(&self, active: bool) {
+        self.send(Msg::Request(Request::SetActive(active))).await;
+    }
+
+    async fn toggle_mute(&self) {
+        self.send(Msg::Request(Request::ToggleMute)).await;
+    }
+
+    /// Takes the name of a `PulseAudio` source, or part of its name or description.
+    async fn select_microphone(&self, name: String) {
+        self.send(Msg::Request(Request::SelectMicrophone(name)))
+            .await;
+    }
+
+    async fn show_window(&self) {
+        self.send(Msg::Request(Request::Show)).await;
+    }
+
+    #[zbus(signal)]
+    async fn state_changed(
+        emitter: &SignalEmitter<'_>,
+        active: bool,
+        muted: bool,
+        microphone: &str,
+    ) -> zbus::Result<()>;
+}
+
+/// Lets desktops launch and raise the app through D-Bus activation, see the Desktop Entry
+/// specification. Command line flags are passed on as actions.
+struct Application {
+    msg_sender: Arc<Mutex<Sender<Msg>>>,
+}
+
+impl Application {
+    async fn send(&self, msg: Msg) {
+        let _ = self.msg_sender.lock().await.send(msg).await;
+    }
+}
+
+#[interface(name = "org.freedesktop.Application")]
+impl Application {
+    async fn activate(&self, _platform_data: HashMap<String, OwnedValue>) {
+        self.send(Msg::Request(Request::Show)).await;
+    }
+
+    // there are no files to open, so this only raises the window
+    async fn open(&self, _uris: Vec<String>, _platform_data: HashMap<String, OwnedValue>) {
+        self.send(Msg::Request(Request::Show)).await;
+    }
+
+    async fn activate_action(
+        &self,
+        name: &str,
+        parameter: Vec<OwnedValue>,
+        _platform_data: HashMap<String, OwnedValue>,
+    ) -> fdo::Result<()> {
+        let parameter = parameter.into_iter().next();
+        let request = match name {
+            "show" => Request::Show,
+            "toggle-active" => Request::ToggleActive,
+            "toggle-mute" => Request::ToggleMute,
+            "set-active" => Request::SetActive(
+                parameter
+                    .and_then(|p| bool::try_from(p).ok())
+                    .ok_or_else(|| fdo::Error::InvalidArgs("set-active takes a boolean".into()))?,
+            ),
+            "select-microphone" => Request::SelectMicrophone(
+                parameter
+                    .and_then(|p| String::try_from(p).ok())
+                    .ok_or_else(|| {
+                        fdo::Error::InvalidArgs("select-microphone takes a string".into())
+                    })?,
+            ),
+            _ => return Err(fdo::Error::NotSupported(format!("unknown action {name}"))),
+        };
+        self.send(Msg::Request(request)).await;
+        Ok(())
+    }
+}
+
+/// The text Ayatana hosts draw next to the tray icon. ksni has no way of adding it to the item
+/// itself, so it is served next to it.
+#[derive(Debug, Default)]
+struct TrayLabel {
+    label: String,
+    // the longest label there can be, which hosts keep room for
+    guide: String,
+}
+
+#[interface(name = "org.kde.StatusNotifierItem")]
+impl TrayLabel {
+    #[zbus(property)]
+    fn x_ayatana_label(&self) -> String {
+        self.label.clone()
+    }
+
+    #[zbus(property)]
+    fn x_ayatana_label_guide(&self) -> String {
+        self.guide.clone()
+    }
+
+    #[zbus(signal)]
+    async fn x_ayatana_new_label(
+        emitter: &SignalEmitter<'_>,
+        label: &str,
+        guide: &str,
+    ) -> zbus::Result<()>;
+}
+
+/// Shows `label` next to the tray icon on hosts that draw one, with `guide` being the longest
+/// it can get. Without a label, a shown one is taken away again.
+pub async fn publish_tray_label(
+    connection: Connection,
+    label: Option<(String, String)>,
+) -> zbus::Result<()> {
+    let server = connection.object_server();
+    let Some((label, guide)) = label else {
+        if let Ok(iface) = server.interface::<_, TrayLabel>(TRAY_PATH).await {
+            TrayLabel::x_ayatana_new_label(iface.signal_emitter(), "", "").await?;
+            server.remove::<TrayLabel, _>(TRAY_PATH).await?;
+        }
+        return Ok(());
+    };
+
+    server.at(TRAY_PATH, TrayLabel::default()).await?;
+    let iface = server.interface::<_, TrayLabel>(TRAY_PATH).await?;
+    let mut tray_label = iface.get_mut().await;
+    if tray_label.label == label && tray_label.guide == guide {
+        return Ok(());
+    }
+    tray_label.label = label;
+    tray_label.guide = guide;
+    let emitter = iface.signal_emitter();
+    tray_label.x_ayatana_label_changed(emitter).await?;
+    tray_label.x_ayatana_label_guide_changed(emitter).await?;
+    TrayLabel::x_ayatana_new_label(emitter, &tray_label.label, &tray_label.guide).await
+}
+
+/// Hands the requests to the instance that owns the name, raising its window first unless
+/// `hidden`.
+pub async fn forward(
+    connection: &Connection,
+    hidden: bool,
+    requests: &[Request],
+) -> zbus::Result<()> {
+    let platform_data: HashMap<&str, Value<'_>> = HashMap::new();
+    let path = application_path();
+    if !hidden {
+        connection
+            .call_method(
+                Some(instance::app_id()),
+                path.as_str(),
+                Some(APPLICATION_INTERFACE),
+                "Activate",
+                &(&platform_data,),
+            )
+            .await?;
+    }
+
+    for request in requests {
+        let (name, parameter) = match request {
+            Request::SetActive(active) => ("set-active", vec![Value::from(*active)]),
+            Request::SelectMicrophone(query) => {
+                ("select-microphone", vec![Value::from(query.as_str())])
+            }
+            Request::ToggleActive => ("toggle-active", Vec::new()),
+            Request::ToggleMute => ("toggle-mute", Vec::new()),
+            Request::Show => ("show", Vec::new()),
+            // only what the startup flags can ask for is needed
+            _ => continue,
+        };
+        connection
+            .call_method(
+                Some(instance::app_id()),
+                path.as_str(),
+                Some(APPLICATION_INTERFACE),
+                "ActivateAction",
+                &(name, parameter, &platform_data),
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+/// Updates the properties, announcing the ones that changed.
+pub async fn publish(connection: Connection, state: Snapshot) -> zbus::Result<()> {
+    let iface = connection
+        .object_server()
+        .interface::<_, Service>(PATH)
+        .await?;
+    let mut service = iface.get_mut().await;
+    let old = std::mem::replace(&mut service.state, state);
+    let emitter = iface.signal_emitter();
+
+    if old.active != service.state.active {
+        service.active_changed(emitter).await?;
+    }
+    if old.muted != service.state.muted {
+        service.muted_changed(emitter).await?;
+    }
+    if old.microphone != service.state.microphone {
+        service.microphone_changed(emitter).await?;
+    }
+    Service::state_changed(
+        emitter,
+        service.state.active,
+        service.state.muted,
+        service.state.microphone.as_deref().unwrap_or_default(),
+    )
+    .await
+}
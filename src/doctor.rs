@@ -0,0 +1,243 @@
+use std::fmt::Display;
+
+use global_hotkey::wayland::using_wayland;
+use zbus::{Connection, fdo::DBusProxy, names::BusName};
+
+use crate::{
+    config::Config,
+    flatpak, hotkey, instance,
+    ipc::{self, Request},
+    pulse::PulseAudioState,
+};
+
+const WATCHER: &str = "org.kde.StatusNotifierWatcher";
+const PORTAL: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const GLOBAL_SHORTCUTS: &str = "org.freedesktop.portal.GlobalShortcuts";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Pass,
+    // the app works, but not fully
+    Warn,
+    // the app can't do its job
+    Fail,
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Pass => "[ OK ]",
+            Self::Warn => "[WARN]",
+            Self::Fail => "[FAIL]",
+        })
+    }
+}
+
+struct Check {
+    name: &'static str,
+    outcome: Outcome,
+    detail: String,
+    // what to do about it, for anything that didn't pass
+    hint: Option<&'static str>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, hint: &'static str) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Warn,
+            detail: detail.into(),
+            hint: Some(hint),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: &'static str) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Fail,
+            detail: detail.into(),
+            hint: Some(hint),
+        }
+    }
+}
+
+/// Checks what the app needs from the system and prints the results, without starting the app.
+/// Returns the exit code, 1 when anything the app can't work without is missing.
+pub fn run() -> i32 {
+    let mut checks = vec![config(), sandbox()];
+
+    let (bus, on_bus) = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime.block_on(bus_checks()),
+        Err(e) => (
+            vec![Check::fail(
+                "Session bus",
+                format!("could not start the async runtime: {e}"),
+                "This is a bug, please report it",
+            )],
+            false,
+        ),
+    };
+    checks.extend(bus);
+
+    // without a session bus, the lock file and socket decide which instance runs
+    let on_socket = !on_bus && ipc::request(Request::Status).is_ok();
+    checks.push(Check::pass(
+        "Running instance",
+        match (on_bus, on_socket) {
+            (true, _) => "owns the name on the session bus",
+            (false, true) => "answering on the control socket",
+            (false, false) => "none",
+        },
+    ));
+    checks.extend(audio(on_bus || on_socket));
+
+    for check in &checks {
+        println!("{} {}: {}", check.outcome, check.name, check.detail);
+        if let Some(hint) = check.hint {
+            println!("       {hint}");
+        }
+    }
+    i32::from(checks.iter().any(|check| check.outcome == Outcome::Fail))
+}
+
+fn config() -> Check {
+    const NAME: &str = "Config file";
+    let path =
+        Config::path().map_or_else(|| "unknown location".into(), |p| p.display().to_string());
+    match Config::load() {
+        Ok(_) => Check::pass(NAME, path),
+        Err(e) => Check::fail(
+            NAME,
+            format!("{path} can't be read: {e}"),
+            "Fix or remove the file, settings won't be saved until then",
+        ),
+    }
+}
+
+fn sandbox() -> Check {
+    const NAME: &str = "Sandbox";
+    match flatpak::id() {
+        Some(id) => Check::pass(
+            NAME,
+            format!(
+                "Flatpak ({id}), control socket at {}",
+                ipc::socket_path().display()
+            ),
+        ),
+        None => Check::pass(NAME, "none"),
+    }
+}
+
+// also tells whether an instance owns the app's name
+async fn bus_checks() -> (Vec<Check>, bool) {
+    let connection = match Connection::session().await {
+        Ok(connection) => connection,
+        Err(e) => {
+            let check = Check::fail(
+                "Session bus",
+                e.to_string(),
+                "Run the app inside a desktop session, or start one with dbus-run-session",
+            );
+            return (vec![check], false);
+        }
+    };
+    let mut checks = vec![Check::pass("Session bus", "reachable")];
+    let Ok(dbus) = DBusProxy::new(&connection).await else {
+        return (checks, false);
+    };
+    let has_owner = async |name: &'static str| {
+        let Ok(name) = BusName::try_from(name) else {
+            return false;
+        };
+        dbus.name_has_owner(name).await.unwrap_or(false)
+    };
+
+    checks.push(if has_owner(WATCHER).await {
+        Check::pass("Tray", "a StatusNotifierWatcher is running")
+    } else {
+        Check::warn(
+            "Tray",
+            "no StatusNotifierWatcher, the tray icon won't show",
+            "On GNOME, install the AppIndicator extension. Other desktops need a panel with a \
+             system tray",
+        )
+    });
+
+    checks.push(shortcuts_portal(&connection).await);
+    (checks, has_owner(instance::app_id()).await)
+}
+
+async fn shortcuts_portal(connection: &Connection) -> Check {
+    const NAME: &str = "Hotkeys";
+    if !using_wayland() {
+        return Check::pass(NAME, "X11, no portal needed");
+    }
+
+    let version = async {
+        zbus::Proxy::new(connection, PORTAL, PORTAL_PATH, GLOBAL_SHORTCUTS)
+            .await?
+            .get_property::<u32>("version")
+            .await
+    };
+    match version.await {
+        Ok(version) => {
+            let backend =
+                hotkey::portal_implementation().unwrap_or_else(|| "implementation unknown".into());
+            Check::pass(
+                NAME,
+                format!("GlobalShortcuts portal version {version} ({backend})"),
+            )
+        }
+        Err(e) => Check::fail(
+            NAME,
+            format!("the GlobalShortcuts portal isn't available: {e}"),
+            "Install the portal backend for your desktop, e.g. xdg-desktop-portal-kde or \
+             xdg-desktop-portal-hyprland",
+        ),
+    }
+}
+
+fn audio(running: bool) -> Vec<Check> {
+    let pa_state = match PulseAudioState::init() {
+        Ok(pa_state) => pa_state,
+        Err(e) => {
+            return vec![Check::fail(
+                "Audio server",
+                e.to_string(),
+                "Start PulseAudio, or PipeWire with pipewire-pulse",
+            )];
+        }
+    };
+    let server = pa_state.server_info().map_or_else(
+        || "reachable".into(),
+        |info| format!("{} {}", info.name, info.version),
+    );
+    let mut checks = vec![Check::pass("Audio server", server)];
+
+    // the running instance has one of its own
+    let mic_exists = pa_state.virtual_mic_exists();
+    checks.push(match (mic_exists, running) {
+        (true, false) => Check::warn(
+            "Virtual microphone",
+            "left behind without a running instance",
+            "It's removed the next time the app starts, or unload its module-remap-source with \
+             pactl",
+        ),
+        (true, true) => Check::pass("Virtual microphone", "in use by the running instance"),
+        (false, _) => Check::pass("Virtual microphone", "none"),
+    });
+    checks
+}
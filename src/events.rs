@@ -0,0 +1,70 @@
+use std::{collections::VecDeque, fmt::Display, sync::Mutex};
+
+use chrono::{DateTime, Local};
+
+// older events are dropped once there are this many
+const CAPACITY: usize = 200;
+
+static EVENTS: Mutex<VecDeque<Event>> = Mutex::new(VecDeque::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Info => "INFO",
+            Self::Warning => "WARN",
+            Self::Error => "ERROR",
+        })
+    }
+}
+
+/// Something that happened inside the app, kept for the Diagnostics page.
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub text: String,
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {:5} {}",
+            self.time.format("%H:%M:%S"),
+            self.level,
+            self.text
+        )
+    }
+}
+
+/// Records an event and prints it to stderr. Can be called from any thread.
+pub fn record(level: Level, text: impl Into<String>) {
+    let event = Event {
+        time: Local::now(),
+        level,
+        text: text.into(),
+    };
+    eprintln!("{event}");
+
+    if let Ok(mut events) = EVENTS.lock() {
+        if events.len() == CAPACITY {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+}
+
+/// Every event still kept, oldest first.
+pub fn recent() -> Vec<Event> {
+    EVENTS
+        .lock()
+        .map(|events| events.iter().cloned().collect())
+        .unwrap_or_default()
+}
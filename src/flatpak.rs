@@ -0,0 +1,60 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+// every Flatpak sandbox has it, and nothing else does
+const INFO_FILE: &str = "/.flatpak-info";
+
+static SANDBOXED: OnceLock<bool> = OnceLock::new();
+
+pub fn is_sandboxed() -> bool {
+    *SANDBOXED.get_or_init(|| Path::new(INFO_FILE).exists())
+}
+
+/// The app id Flatpak runs this as, only set inside the sandbox.
+pub fn id() -> Option<String> {
+    sandbox_id(is_sandboxed(), std::env::var("FLATPAK_ID").ok())
+}
+
+// FLATPAK_ID may as well be set by hand outside of one
+fn sandbox_id(sandboxed: bool, flatpak_id: Option<String>) -> Option<String> {
+    flatpak_id.filter(|id| sandboxed && !id.is_empty())
+}
+
+/// The part of `runtime_dir` that Flatpak shares between the sandbox of app `id` and the host,
+/// so that scripts outside can still reach the control socket. `runtime_dir` itself outside of
+/// a sandbox.
+pub fn shared_runtime_dir(runtime_dir: PathBuf, id: Option<&str>) -> PathBuf {
+    match id {
+        Some(id) => runtime_dir.join("app").join(id),
+        None => runtime_dir,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_id_only_counts_inside_a_sandbox() {
+        let id = || Some("com.github.Adamskye.GlobalPushToTalk".to_string());
+        assert_eq!(sandbox_id(true, id()), id());
+        assert_eq!(sandbox_id(false, id()), None);
+        assert_eq!(sandbox_id(true, Some(String::new())), None);
+        assert_eq!(sandbox_id(true, None), None);
+    }
+
+    #[test]
+    fn sandboxes_share_their_own_runtime_dir() {
+        let runtime_dir = PathBuf::from("/run/user/1000");
+        assert_eq!(
+            shared_runtime_dir(
+                runtime_dir.clone(),
+                Some("com.github.Adamskye.GlobalPushToTalk")
+            ),
+            Path::new("/run/user/1000/app/com.github.Adamskye.GlobalPushToTalk")
+        );
+        assert_eq!(shared_runtime_dir(runtime_dir.clone(), None), runtime_dir);
+    }
+}
@@ -0,0 +1,225 @@
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    io,
+    path::{Path, PathBuf},
+    pin::pin,
+    time::Duration,
+};
+
+use evdev::{Device, EventType, InputEvent, KeyCode, LedCode};
+use iced::{
+    futures::{
+        SinkExt, Stream,
+        channel::mpsc::Sender,
+        future::{Either, select},
+    },
+    stream,
+};
+use tokio::{sync::watch, task::JoinSet};
+
+use crate::{
+    activity::Cause,
+    app::Msg,
+    events::{self, Level},
+    i18n::tr,
+    ipc::Snapshot,
+};
+
+const INPUT_DIR: &str = "/dev/input";
+// how often plugged in headsets are looked for
+const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A headset with a mute button, which the kernel turns from the HID telephony page's Phone
+/// Mute into `KEY_MICMUTE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Headset {
+    pub path: PathBuf,
+    pub name: String,
+    // whether it has a mute LED that can be set from here
+    pub led: bool,
+}
+
+/// The input devices found, for the settings page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scan {
+    pub headsets: Vec<Headset>,
+    // devices that couldn't be opened, usually for lack of the input group
+    pub denied: usize,
+}
+
+/// Every headset with a mute button that can be opened. Other input devices aren't kept open.
+pub fn scan() -> Scan {
+    let mut scan = Scan::default();
+    let Ok(entries) = std::fs::read_dir(INPUT_DIR) else {
+        return scan;
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("event"))
+        })
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        match Device::open(&path) {
+            Ok(device) => {
+                if let Some(headset) = headset(&path, &device) {
+                    scan.headsets.push(headset);
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => scan.denied += 1,
+            Err(_) => {}
+        }
+    }
+    scan
+}
+
+fn headset(path: &Path, device: &Device) -> Option<Headset> {
+    let has_button = device
+        .supported_keys()
+        .is_some_and(|keys| keys.contains(KeyCode::KEY_MICMUTE));
+    if !has_button {
+        return None;
+    }
+    Some(Headset {
+        path: path.to_path_buf(),
+        name: device.name().unwrap_or("Unknown headset").to_string(),
+        led: device
+            .supported_leds()
+            .is_some_and(|leds| leds.contains(LedCode::LED_MUTE)),
+    })
+}
+
+/// Toggles the mute state whenever the mute button of a headset is pressed, of the one named
+/// `only` or else of any, and keeps their mute LEDs in line with `state`. Headsets plugged in
+/// later are picked up, and the scans come back for the settings page.
+pub fn watch(only: Option<String>, state: watch::Receiver<Snapshot>) -> impl Stream<Item = Msg> {
+    stream::channel(10, async move |mut tx| {
+        // dropping the set stops listening to every headset
+        let mut listeners = JoinSet::new();
+        let mut listening = HashSet::new();
+        let mut last_scan = None;
+        loop {
+            // unplugged headsets end their listeners
+            while let Some(res) = listeners.try_join_next() {
+                if let Ok(path) = res {
+                    listening.remove(&path);
+                }
+            }
+
+            let scan = tokio::task::spawn_blocking(scan).await.unwrap_or_default();
+            if last_scan.as_ref() != Some(&scan) {
+                if scan.denied > 0 && scan.headsets.is_empty() {
+                    events::record(
+                        Level::Warning,
+                        format!(
+                            "{} input devices can't be read, so headset buttons may be missed",
+                            scan.denied
+                        ),
+                    );
+                }
+                let _ = tx.send(Msg::HeadsetScan(scan.clone())).await;
+            }
+
+            let wanted = scan
+                .headsets
+                .iter()
+                .filter(|headset| only.as_ref().is_none_or(|name| *name == headset.name));
+            for headset in wanted {
+                if listening.contains(&headset.path) {
+                    continue;
+                }
+                events::record(
+                    Level::Info,
+                    format!("Listening to the mute button of {}", headset.name),
+                );
+                listeners.spawn(listen(headset.clone(), state.clone(), tx.clone()));
+                listening.insert(headset.path.clone());
+            }
+            last_scan = Some(scan);
+
+            tokio::time::sleep(RESCAN_INTERVAL).await;
+        }
+    })
+}
+
+// until the headset is unplugged, returning its path
+async fn listen(headset: Headset, state: watch::Receiver<Snapshot>, tx: Sender<Msg>) -> PathBuf {
+    if let Err(e) = follow(&headset, state, tx).await {
+        events::record(
+            Level::Info,
+            format!("Stopped listening to {}: {e}", headset.name),
+        );
+    }
+    headset.path
+}
+
+async fn follow(
+    headset: &Headset,
+    mut state: watch::Receiver<Snapshot>,
+    mut tx: Sender<Msg>,
+) -> io::Result<()> {
+    let mut events = Device::open(&headset.path)?.into_event_stream()?;
+    let mut muted = state.borrow_and_update().muted;
+    if headset.led {
+        set_led(&mut events, muted)?;
+    }
+    loop {
+        // both borrow what's used below, so they're dropped first
+        let next = {
+            let event = pin!(events.next_event());
+            let change = pin!(state.changed());
+            match select(event, change).await {
+                Either::Left((event, _)) => Either::Left(event),
+                Either::Right((changed, _)) => Either::Right(changed),
+            }
+        };
+        match next {
+            Either::Left(event) => {
+                let event = event?;
+                let pressed = event.event_type() == EventType::KEY
+                    && event.code() == KeyCode::KEY_MICMUTE.code()
+                    && event.value() == 1;
+                if pressed {
+                    let _ = tx.send(Msg::ToggleMute(Cause::Headset)).await;
+                }
+            }
+            // the app is gone
+            Either::Right(Err(_)) => return Ok(()),
+            Either::Right(Ok(())) => {
+                let now_muted = state.borrow_and_update().muted;
+                if headset.led && now_muted != muted {
+                    set_led(&mut events, now_muted)?;
+                }
+                muted = now_muted;
+            }
+        }
+    }
+}
+
+fn set_led(events: &mut evdev::EventStream, muted: bool) -> io::Result<()> {
+    let led = InputEvent::new(EventType::LED.0, LedCode::LED_MUTE.0, i32::from(muted));
+    events.device_mut().send_events(&[led])
+}
+
+/// A headset to listen to, for the settings page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Choice {
+    Any,
+    // by the name the device reports
+    Named(String),
+}
+
+impl Display for Choice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Any => f.write_str(&tr!("headset-any")),
+            Self::Named(name) => f.write_str(name),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::{
+    io,
+    process::{ExitStatus, Stdio},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio::{process::Command, sync::Mutex};
+
+use crate::config::Hook;
+
+// a hook that runs longer than this is killed
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("failed to start: {0}")]
+    Spawn(#[from] io::Error),
+    #[error("killed after {} seconds", TIMEOUT.as_secs())]
+    TimedOut,
+    #[error("{status}: {stderr}")]
+    Failed { status: ExitStatus, stderr: String },
+}
+
+#[derive(Debug, Default)]
+struct Slot {
+    running: Mutex<()>,
+    // a run is waiting for the current one to finish
+    queued: AtomicBool,
+}
+
+/// Runs the commands the user configured for state changes. Each hook runs one at a time, and
+/// at most one more run waits behind it, so rapid toggling can't pile up processes.
+#[derive(Debug, Clone, Default)]
+pub struct Hooks {
+    slots: Arc<[Slot; Hook::ALL.len()]>,
+}
+
+impl Hooks {
+    /// Runs `command` with `sh -c`, unless a run of the same hook is already waiting.
+    pub async fn run(self, hook: Hook, command: String) -> Result<(), Error> {
+        let slot = &self.slots[hook as usize];
+        if slot.queued.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+        let _running = slot.running.lock().await;
+        slot.queued.store(false, Ordering::Release);
+
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            // dropping the output future on timeout takes the process with it
+            .kill_on_drop(true)
+            .spawn()?;
+        let output = tokio::time::timeout(TIMEOUT, child.wait_with_output())
+            .await
+            .map_err(|_| Error::TimedOut)??;
+        if output.status.success() {
+            return Ok(());
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(Error::Failed {
+            status: output.status,
+            stderr: stderr.trim().lines().last().unwrap_or_default().to_string(),
+        })
+    }
+}
@@ -1,63 +1,448 @@
-use std::sync::Arc;
+use std::{fmt::Display, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
 
 use global_hotkey::{
     GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState,
-    hotkey::{Code, HotKey, Modifiers},
+    hotkey::{Code, HotKey, HotKeyParseError, Modifiers},
     wayland::{WlHotKeysChangedEvent, WlNewHotKeyAction, using_wayland},
 };
 use iced::{
     futures::{FutureExt, SinkExt, Stream, channel::mpsc::Sender},
     stream,
 };
-use tokio::sync::{Mutex, mpsc};
+use tokio::{
+    sync::{Mutex, mpsc},
+    time::timeout,
+};
 
-use crate::{APP_ID, app::Msg, config::Config};
+use crate::{
+    app::{HotKeyAction, Msg, Severity},
+    config::Config,
+    events::{self, Level},
+    i18n::tr,
+    input, instance,
+};
 
 const WL_TRIGGER_ID: u32 = 0;
 const WL_TOGGLE_ACTIVE_ID: u32 = 1;
+const WL_WHISPER_ID: u32 = 2;
+const WL_NEXT_PROFILE_ID: u32 = 3;
+
+// how an action without a binding is written in the config file
+const UNBOUND: &str = "none";
+
+// how long to wait for further hotkey changes before applying them
+const CHANGE_DEBOUNCE: Duration = Duration::from_millis(250);
 
 // used to store any data corresponding to each type of hotkey
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct HotKeyConfig<T> {
     pub trigger: T,
     pub toggle_active: T,
+    pub whisper: T,
+    pub next_profile: T,
 }
 
-impl Default for HotKeyConfig<HotKey> {
+impl Default for HotKeyConfig<Binding> {
     fn default() -> Self {
         Self {
-            trigger: HotKey::new(None, Code::Insert),
-            toggle_active: HotKey::new(Some(Modifiers::CONTROL | Modifiers::SUPER), Code::KeyP),
+            trigger: Binding::HotKey(HotKey::new(None, Code::Insert)),
+            toggle_active: Binding::HotKey(HotKey::new(
+                Some(Modifiers::CONTROL | Modifiers::SUPER),
+                Code::KeyP,
+            )),
+            whisper: Binding::HotKey(HotKey::new(Some(Modifiers::ALT), Code::Insert)),
+            next_profile: Binding::HotKey(HotKey::new(
+                Some(Modifiers::CONTROL | Modifiers::SUPER),
+                Code::KeyN,
+            )),
         }
     }
 }
 
+impl HotKeyConfig<Binding> {
+    /// The action bound to the grabbed hotkey with this ID. Presses and releases, with or
+    /// without `NumLock` and `CapsLock`, all come with the same one.
+    pub fn action(&self, id: u32) -> Option<HotKeyAction> {
+        [
+            (HotKeyAction::Trigger, &self.trigger),
+            (HotKeyAction::ToggleActive, &self.toggle_active),
+            (HotKeyAction::WhisperTrigger, &self.whisper),
+            (HotKeyAction::NextProfile, &self.next_profile),
+        ]
+        .into_iter()
+        .find(|(_, binding)| binding.hotkeys().iter().any(|hk| hk.id() == id))
+        .map(|(action, _)| action)
+    }
+}
+
 impl Default for HotKeyConfig<String> {
     fn default() -> Self {
         Self {
             trigger: String::default(),
             toggle_active: String::default(),
+            whisper: String::default(),
+            next_profile: String::default(),
+        }
+    }
+}
+
+/// How hotkeys ended up being detected, for diagnostics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HotKeyBackend {
+    /// The `GlobalShortcuts` portal, with the D-Bus name of its implementation if it is known.
+    Portal(Option<String>),
+    X11,
+}
+
+impl Display for HotKeyBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&match self {
+            Self::Portal(Some(implementation)) => tr!(
+                "diagnostics-hotkeys-portal-impl",
+                implementation = implementation.as_str()
+            ),
+            Self::Portal(None) => tr!("diagnostics-hotkeys-portal"),
+            Self::X11 => tr!("diagnostics-hotkeys-x11"),
+        })
+    }
+}
+
+// the portal doesn't tell which implementation answered, so pick it the way xdg-desktop-portal
+// does without a portals.conf: the first one offering GlobalShortcuts for the current desktop
+pub fn portal_implementation() -> Option<String> {
+    const INTERFACE: &str = "org.freedesktop.impl.portal.GlobalShortcuts";
+
+    let desktops: Vec<String> = std::env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(':')
+        .map(str::to_lowercase)
+        .collect();
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+
+    let mut candidates = Vec::new();
+    for dir in data_dirs.split(':') {
+        let dir = PathBuf::from(dir)
+            .join("xdg-desktop-portal")
+            .join("portals");
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(contents) = std::fs::read_to_string(entry.path()) else {
+                continue;
+            };
+            let field = |name: &str| {
+                contents
+                    .lines()
+                    .find_map(|line| line.strip_prefix(name)?.trim().strip_prefix('='))
+                    .map(str::trim)
+            };
+            let offers_interface = field("Interfaces")
+                .is_some_and(|interfaces| interfaces.split(';').any(|i| i == INTERFACE));
+            if !offers_interface {
+                continue;
+            }
+            let used_in_desktop = field("UseIn").is_some_and(|use_in| {
+                use_in
+                    .split(';')
+                    .any(|desktop| desktops.contains(&desktop.to_lowercase()))
+            });
+            if let Some(name) = field("DBusName") {
+                candidates.push((used_in_desktop, name.to_string()));
+            }
+        }
+    }
+
+    // implementations meant for the current desktop win, otherwise any will do
+    candidates.sort_by_key(|(used_in_desktop, _)| !used_in_desktop);
+    candidates.into_iter().next().map(|(_, name)| name)
+}
+
+/// What an action is bound to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Binding {
+    /// Any number of modifiers plus a single key, e.g. `control+KeyP`.
+    HotKey(HotKey),
+    /// Several non-modifier keys held down together, e.g. `KeyV+KeyB`. These are read from the
+    /// keyboards by [`crate::input`], since grabbing them would grab each key on its own.
+    Chord(Vec<Code>),
+    /// Nothing, the action can only be used from elsewhere. Written as `none`.
+    Unbound,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BindingError {
+    #[error("key chords are not supported by the Global Shortcuts portal")]
+    ChordUnsupported,
+    #[error("nothing is bound")]
+    Unbound,
+}
+
+impl Binding {
+    /// The hotkeys that need to be registered for this binding to be detected.
+    pub fn hotkeys(&self) -> Vec<HotKey> {
+        match self {
+            Self::HotKey(hk) => vec![*hk],
+            Self::Chord(_) | Self::Unbound => Vec::new(),
+        }
+    }
+
+    /// Converts this binding into a single hotkey, as required by the portal.
+    pub fn as_hotkey(&self) -> Result<HotKey, BindingError> {
+        match self {
+            Self::HotKey(hk) => Ok(*hk),
+            Self::Chord(_) => Err(BindingError::ChordUnsupported),
+            Self::Unbound => Err(BindingError::Unbound),
+        }
+    }
+
+    /// A human-friendly description, e.g. "Ctrl + Super + P". Empty when unbound.
+    pub fn description(&self) -> String {
+        let mut parts = Vec::new();
+        match self {
+            Self::HotKey(hk) => {
+                for (m, name) in [
+                    (Modifiers::CONTROL, "Ctrl"),
+                    (Modifiers::SHIFT, "Shift"),
+                    (Modifiers::ALT, "Alt"),
+                    (Modifiers::SUPER, "Super"),
+                ] {
+                    if hk.mods.contains(m) {
+                        parts.push(name.to_string());
+                    }
+                }
+                parts.push(key_name(hk.key));
+            }
+            Self::Chord(codes) => parts.extend(codes.iter().copied().map(key_name)),
+            Self::Unbound => {}
+        }
+        parts.join(" + ")
+    }
+
+    /// The binding in the format of a dbusmenu `shortcut`, e.g. `[["Control", "Super", "p"]]`.
+    pub fn shortcut(&self) -> Vec<Vec<String>> {
+        let keys = match self {
+            Self::HotKey(hk) => {
+                let mut keys: Vec<String> = [
+                    (Modifiers::CONTROL, "Control"),
+                    (Modifiers::ALT, "Alt"),
+                    (Modifiers::SHIFT, "Shift"),
+                    (Modifiers::SUPER, "Super"),
+                ]
+                .into_iter()
+                .filter(|(m, _)| hk.mods.contains(*m))
+                .map(|(_, name)| name.to_string())
+                .collect();
+                keys.push(keysym_name(hk.key));
+                keys
+            }
+            Self::Chord(codes) => codes.iter().map(|c| keysym_name(*c)).collect(),
+            Self::Unbound => return Vec::new(),
+        };
+        vec![keys]
+    }
+}
+
+// X keysym names, which is what menu hosts parse shortcut keys as
+fn keysym_name(code: Code) -> String {
+    let name = match code {
+        Code::ArrowUp => "Up",
+        Code::ArrowDown => "Down",
+        Code::ArrowLeft => "Left",
+        Code::ArrowRight => "Right",
+        Code::PageUp => "Page_Up",
+        Code::PageDown => "Page_Down",
+        Code::Backspace => "BackSpace",
+        Code::Enter => "Return",
+        Code::Space => "space",
+        Code::CapsLock => "Caps_Lock",
+        Code::NumLock => "Num_Lock",
+        Code::ScrollLock => "Scroll_Lock",
+        Code::PrintScreen => "Print",
+        Code::Backquote => "grave",
+        Code::Minus => "minus",
+        Code::Equal => "equal",
+        Code::BracketLeft => "bracketleft",
+        Code::BracketRight => "bracketright",
+        Code::Backslash => "backslash",
+        Code::Semicolon => "semicolon",
+        Code::Quote => "apostrophe",
+        Code::Comma => "comma",
+        Code::Period => "period",
+        Code::Slash => "slash",
+        Code::NumpadAdd => "KP_Add",
+        Code::NumpadSubtract => "KP_Subtract",
+        Code::NumpadMultiply => "KP_Multiply",
+        Code::NumpadDivide => "KP_Divide",
+        Code::NumpadDecimal => "KP_Decimal",
+        Code::NumpadEnter => "KP_Enter",
+        Code::NumpadEqual => "KP_Equal",
+        _ => "",
+    };
+    if !name.is_empty() {
+        return name.into();
+    }
+
+    // the rest map over directly, e.g. "KeyP" to "p", "Digit1" to "1", "Numpad1" to "KP_1"
+    let name = code.to_string();
+    if let Some(letter) = name.strip_prefix("Key") {
+        letter.to_lowercase()
+    } else if let Some(digit) = name.strip_prefix("Digit") {
+        digit.into()
+    } else if let Some(digit) = name.strip_prefix("Numpad") {
+        format!("KP_{digit}")
+    } else {
+        name
+    }
+}
+
+fn key_name(code: Code) -> String {
+    let symbol = match code {
+        Code::Backquote => "`",
+        Code::Minus => "-",
+        Code::Equal => "=",
+        Code::BracketLeft => "[",
+        Code::BracketRight => "]",
+        Code::Backslash => "\\",
+        Code::Semicolon => ";",
+        Code::Quote => "'",
+        Code::Comma => ",",
+        Code::Period => ".",
+        Code::Slash => "/",
+        Code::NumpadAdd => "Numpad +",
+        Code::NumpadSubtract => "Numpad -",
+        Code::NumpadMultiply => "Numpad *",
+        Code::NumpadDivide => "Numpad /",
+        Code::NumpadDecimal => "Numpad .",
+        Code::NumpadEqual => "Numpad =",
+        _ => "",
+    };
+    if !symbol.is_empty() {
+        return symbol.into();
+    }
+
+    // everything else is named in CamelCase, e.g. "KeyP", "Numpad0", "PageUp"
+    let name = code.to_string();
+    let name = ["Key", "Digit", "Arrow"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .unwrap_or(&name);
+
+    // split into words, e.g. "Numpad 0", "Page Up"
+    let mut words = String::new();
+    let mut prev: Option<char> = None;
+    for c in name.chars() {
+        if prev.is_some_and(|p| p.is_lowercase() && (c.is_uppercase() || c.is_ascii_digit())) {
+            words.push(' ');
+        }
+        words.push(c);
+        prev = Some(c);
+    }
+    words
+}
+
+impl Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HotKey(hk) => write!(f, "{hk}"),
+            Self::Chord(codes) => {
+                let codes: Vec<String> = codes.iter().map(ToString::to_string).collect();
+                write!(f, "{}", codes.join("+"))
+            }
+            Self::Unbound => f.write_str(UNBOUND),
+        }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = HotKeyParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case(UNBOUND) {
+            return Ok(Self::Unbound);
         }
+        let err = match HotKey::from_str(s) {
+            Ok(hk) => return Ok(Self::HotKey(hk)),
+            Err(e) => e,
+        };
+
+        // not a regular hotkey, so try parsing it as a chord of plain keys instead
+        let mut codes = Vec::new();
+        for token in s.split('+') {
+            let hk = HotKey::from_str(token.trim())?;
+            if !hk.mods.is_empty() {
+                return Err(err);
+            }
+            if !codes.contains(&hk.key) {
+                codes.push(hk.key);
+            }
+        }
+
+        if codes.len() < 2 {
+            return Err(err);
+        }
+        Ok(Self::Chord(codes))
     }
 }
 
 async fn hotkeys_wl(gh: GlobalHotKeyManager, tx: Sender<Msg>) -> anyhow::Result<()> {
+    // configured bindings are only suggested to the portal, which has the final say
+    let preferred = Config::load().unwrap_or_default().hotkeys();
+    let default = HotKeyConfig::<Binding>::default();
+    let suggest = |binding: &Binding, default: &Binding| {
+        // the portal may still bind it, but nothing is suggested
+        if *binding == Binding::Unbound {
+            return None;
+        }
+        binding
+            .as_hotkey()
+            .or_else(|e| {
+                events::record(
+                    Level::Warning,
+                    format!("Cannot use \"{binding}\" as a global shortcut: {e}"),
+                );
+                default.as_hotkey()
+            })
+            .ok()
+    };
+
     let trigger_hk = WlNewHotKeyAction::new(
         WL_TRIGGER_ID,
-        "Push-to-talk trigger/unmute microphone",
-        Some(HotKey::new(None, Code::Insert)),
+        &tr!("portal-trigger"),
+        suggest(&preferred.trigger, &default.trigger),
     );
 
     let toggle_active_hk = WlNewHotKeyAction::new(
         WL_TOGGLE_ACTIVE_ID,
-        "Enable/disable push-to-talk",
-        Some(HotKey::new(
-            Some(Modifiers::CONTROL | Modifiers::SUPER),
-            Code::KeyP,
-        )),
+        &tr!("portal-toggle-active"),
+        suggest(&preferred.toggle_active, &default.toggle_active),
+    );
+
+    let whisper_hk = WlNewHotKeyAction::new(
+        WL_WHISPER_ID,
+        &tr!("portal-whisper"),
+        suggest(&preferred.whisper, &default.whisper),
     );
 
-    gh.wl_register_all(APP_ID, &[trigger_hk, toggle_active_hk])?;
+    let next_profile_hk = WlNewHotKeyAction::new(
+        WL_NEXT_PROFILE_ID,
+        &tr!("portal-next-profile"),
+        suggest(&preferred.next_profile, &default.next_profile),
+    );
+
+    // unlike ScreenCast, the GlobalShortcuts portal has no restore token, so a new session is
+    // created on every launch. the portal remembers bindings per application ID instead, which
+    // is why APP_ID must never change. other instances can't share bindings with it, so they
+    // get an app id of their own.
+    gh.wl_register_all(
+        instance::app_id(),
+        &[trigger_hk, toggle_active_hk, whisper_hk, next_profile_hk],
+    )?;
+    let backend = HotKeyBackend::Portal(portal_implementation());
+    let _ = tx.clone().send(Msg::HotKeyBackendReady(backend)).await;
 
     // react to user changing the hotkeys
     let mut msg_tx = tx.clone();
@@ -70,6 +455,8 @@ async fn hotkeys_wl(gh: GlobalHotKeyManager, tx: Sender<Msg>) -> anyhow::Result<
                 match hk.id() {
                     WL_TRIGGER_ID => d.trigger = hk_desc,
                     WL_TOGGLE_ACTIVE_ID => d.toggle_active = hk_desc,
+                    WL_WHISPER_ID => d.whisper = hk_desc,
+                    WL_NEXT_PROFILE_ID => d.next_profile = hk_desc,
                     _ => (),
                 }
             }
@@ -91,12 +478,17 @@ async fn hotkeys_wl(gh: GlobalHotKeyManager, tx: Sender<Msg>) -> anyhow::Result<
 
     // handle hotkey events
     let hk_event_rx = GlobalHotKeyEvent::receiver();
-    let hotkey_ids = HotKeyConfig {
-        trigger: WL_TRIGGER_ID,
-        toggle_active: WL_TOGGLE_ACTIVE_ID,
-    };
     while let Ok(Ok(event)) = tokio::task::spawn_blocking(|| hk_event_rx.recv()).await {
-        handle_hotkey_press(tx.clone(), event, &hotkey_ids);
+        let state = event.state();
+        match event.id() {
+            WL_TRIGGER_ID => handle_hotkey(tx.clone(), HotKeyAction::Trigger, state),
+            WL_TOGGLE_ACTIVE_ID => {
+                handle_hotkey(tx.clone(), HotKeyAction::ToggleActive, state);
+            }
+            WL_WHISPER_ID => handle_hotkey(tx.clone(), HotKeyAction::WhisperTrigger, state),
+            WL_NEXT_PROFILE_ID => handle_hotkey(tx.clone(), HotKeyAction::NextProfile, state),
+            _ => (),
+        }
     }
 
     Ok(())
@@ -106,6 +498,7 @@ async fn hotkeys_non_wl(gh: GlobalHotKeyManager, mut tx: Sender<Msg>) -> anyhow:
     // channel for UI to send hotkey updates through
     let (change_hotkey_tx, mut change_hotkey_rx) = mpsc::channel(10);
     let _ = tx.send(Msg::InitChangeHotKeyTX(change_hotkey_tx)).await;
+    let _ = tx.send(Msg::HotKeyBackendReady(HotKeyBackend::X11)).await;
 
     // load our hotkeys
     let config_outer = Arc::new(Mutex::new(Config::load().unwrap_or_default()));
@@ -117,37 +510,62 @@ async fn hotkeys_non_wl(gh: GlobalHotKeyManager, mut tx: Sender<Msg>) -> anyhow:
         loop {
             // set up hotkeys
             {
-                let mut config = config.lock().await;
+                let config = config.lock().await;
                 let hks = config.hotkeys();
+                let passive = input::passive(&hks, config.trigger_keyboard());
 
-                // register the hotkeys
-                let _ = gh.register(hks.trigger);
-                let _ = gh.register(hks.toggle_active);
-
-                // update description in UI
-                let _ = msg_tx
-                    .send(Msg::UpdateHotKeyDescriptions(HotKeyConfig {
-                        trigger: hks.trigger.into_string(),
-                        toggle_active: hks.toggle_active.into_string(),
-                    }))
-                    .await;
+                // register the hotkeys. global_hotkey already grabs every NumLock/CapsLock
+                // variant and reports them all under the hotkey's own ID
+                for (action, binding) in [
+                    (HotKeyAction::Trigger, &hks.trigger),
+                    (HotKeyAction::ToggleActive, &hks.toggle_active),
+                    (HotKeyAction::WhisperTrigger, &hks.whisper),
+                    (HotKeyAction::NextProfile, &hks.next_profile),
+                ] {
+                    // these are read from the keyboards instead
+                    if *binding == Binding::Unbound || passive.iter().any(|p| p.action == action) {
+                        continue;
+                    }
+                    if let Err(e) = gh.register_all(&binding.hotkeys()) {
+                        let text = tr!(
+                            "hotkey-register-failed",
+                            hotkey = binding.description(),
+                            error = e.to_string()
+                        );
+                        let _ = msg_tx.send(Msg::PushToast(Severity::Error, text)).await;
+                    } else {
+                        events::record(
+                            Level::Info,
+                            format!("Registered {}", binding.description()),
+                        );
+                    }
+                }
 
-                // save config
-                config.store_hotkeys(&hks);
+                // update hotkeys in UI
+                let _ = msg_tx.send(Msg::UpdateHotKeys(hks)).await;
             }
 
             // update hotkeys whenever one is changed
-            if let Some(change) = change_hotkey_rx.recv().await {
-                // unregister old hotkeys
-                let mut config = config.lock().await;
-                let hks = config.hotkeys();
-                let _ = gh.unregister(hks.trigger);
-                let _ = gh.unregister(hks.toggle_active);
-
-                config.store_hotkeys(&change);
-            } else {
+            let Some(mut change) = change_hotkey_rx.recv().await else {
                 return;
+            };
+
+            // only the last of several changes in quick succession needs registering and saving
+            while let Ok(Some(next)) = timeout(CHANGE_DEBOUNCE, change_hotkey_rx.recv()).await {
+                change = next;
             }
+
+            // unregister old hotkeys
+            let mut config = config.lock().await;
+            let hks = config.hotkeys();
+            let _ = gh.unregister_all(&hks.trigger.hotkeys());
+            let _ = gh.unregister_all(&hks.toggle_active.hotkeys());
+            let _ = gh.unregister_all(&hks.whisper.hotkeys());
+            let _ = gh.unregister_all(&hks.next_profile.hotkeys());
+
+            // this also picks up the trigger's keyboard, which the app stores before sending
+            // the hotkeys along to register them again
+            config.store_hotkeys(&change);
         }
     });
 
@@ -156,29 +574,16 @@ async fn hotkeys_non_wl(gh: GlobalHotKeyManager, mut tx: Sender<Msg>) -> anyhow:
     while let Ok(Ok(event)) = tokio::task::spawn_blocking(|| hk_event_rx.recv()).await {
         let config = config_outer.lock().await;
         let hks = config.hotkeys();
-        let ids = HotKeyConfig {
-            trigger: hks.trigger.id(),
-            toggle_active: hks.toggle_active.id(),
-        };
-        handle_hotkey_press(tx.clone(), event, &ids);
+        if let Some(action) = hks.action(event.id()) {
+            handle_hotkey(tx.clone(), action, event.state());
+        }
     }
     Ok(())
 }
 
-fn handle_hotkey_press(
-    mut tx: Sender<Msg>,
-    event: GlobalHotKeyEvent,
-    hotkey_ids: &HotKeyConfig<u32>,
-) {
-    let id = event.id();
+fn handle_hotkey(mut tx: Sender<Msg>, action: HotKeyAction, state: HotKeyState) {
     let _ = tx
-        .send(if id == hotkey_ids.trigger {
-            Msg::SetMuted(event.state() == HotKeyState::Released)
-        } else if id == hotkey_ids.toggle_active && event.state() == HotKeyState::Pressed {
-            Msg::ToggleActive
-        } else {
-            return;
-        })
+        .send(Msg::HotKeyEvent(action, state == HotKeyState::Pressed))
         .now_or_never();
 }
 
@@ -200,3 +605,102 @@ pub fn hotkeys() -> impl Stream<Item = Msg> {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(s: &str) -> Binding {
+        s.parse().expect("valid binding")
+    }
+
+    fn id(s: &str) -> u32 {
+        binding(s).as_hotkey().expect("not a chord").id()
+    }
+
+    #[test]
+    fn numpad_keys_map_to_their_own_action() {
+        // what recording makes of Insert and of Numpad0 with NumLock off
+        let hotkeys = HotKeyConfig {
+            trigger: binding("Insert"),
+            whisper: binding("Numpad0"),
+            ..HotKeyConfig::default()
+        };
+        assert_ne!(id("Insert"), id("Numpad0"));
+        assert_eq!(hotkeys.action(id("Insert")), Some(HotKeyAction::Trigger));
+        assert_eq!(
+            hotkeys.action(id("Numpad0")),
+            Some(HotKeyAction::WhisperTrigger)
+        );
+    }
+
+    #[test]
+    fn recorded_modifiers_map_back_to_the_action() {
+        let hotkeys = HotKeyConfig {
+            toggle_active: binding("CTRL+SHIFT+Numpad1"),
+            ..HotKeyConfig::default()
+        };
+        assert_eq!(
+            hotkeys.action(id("control+shift+Numpad1")),
+            Some(HotKeyAction::ToggleActive)
+        );
+        // without the modifiers it's another hotkey
+        assert_eq!(hotkeys.action(id("Numpad1")), None);
+    }
+
+    #[test]
+    fn chords_are_not_grabbed() {
+        let hotkeys = HotKeyConfig {
+            trigger: binding("KeyV+KeyB"),
+            ..HotKeyConfig::default()
+        };
+        assert!(hotkeys.trigger.hotkeys().is_empty());
+        assert_eq!(hotkeys.action(id("KeyV")), None);
+        assert_eq!(hotkeys.action(id("KeyB")), None);
+    }
+
+    #[test]
+    fn unbound_actions_are_written_as_none() {
+        assert_eq!(binding("none"), Binding::Unbound);
+        assert_eq!(binding(&Binding::Unbound.to_string()), Binding::Unbound);
+        assert!(Binding::Unbound.hotkeys().is_empty());
+        assert!(Binding::Unbound.description().is_empty());
+        assert!(Binding::Unbound.shortcut().is_empty());
+    }
+
+    #[test]
+    fn descriptions() {
+        for (binding_str, description) in [
+            ("control+super+KeyP", "Ctrl + Super + P"),
+            ("Insert", "Insert"),
+            ("shift+F13", "Shift + F13"),
+            ("alt+Insert", "Alt + Insert"),
+            ("Numpad0", "Numpad 0"),
+            ("NumpadAdd", "Numpad +"),
+            ("PageUp", "Page Up"),
+            ("ArrowLeft", "Left"),
+            ("Digit5", "5"),
+            ("Backquote", "`"),
+            ("KeyV+KeyB", "V + B"),
+        ] {
+            assert_eq!(binding(binding_str).description(), description);
+        }
+    }
+
+    #[test]
+    fn tray_shortcuts() {
+        for (binding_str, shortcut) in [
+            ("control+super+KeyP", &["Control", "Super", "p"][..]),
+            ("shift+alt+F13", &["Alt", "Shift", "F13"]),
+            ("Insert", &["Insert"]),
+            ("Numpad1", &["KP_1"]),
+            ("NumpadEnter", &["KP_Enter"]),
+            ("PageDown", &["Page_Down"]),
+            ("Digit7", &["7"]),
+            ("Quote", &["apostrophe"]),
+            ("KeyV+KeyB", &["v", "b"]),
+        ] {
+            assert_eq!(binding(binding_str).shortcut(), [shortcut]);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use std::sync::OnceLock;
+
+use fluent::{FluentArgs, FluentResource, concurrent::FluentBundle};
+use unic_langid::LanguageIdentifier;
+
+// every translation, embedded in the binary. english comes first as it is the fallback for
+// messages missing from the others
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../i18n/en.ftl")),
+    ("de", include_str!("../i18n/de.ftl")),
+];
+
+static BUNDLE: OnceLock<FluentBundle<FluentResource>> = OnceLock::new();
+
+/// Translates a message, e.g. `tr!("profile-switched", name = name)`.
+macro_rules! tr {
+    ($id:literal) => {
+        $crate::i18n::translate($id, None)
+    };
+    ($id:literal, $($arg:ident = $value:expr),+ $(,)?) => {{
+        let mut args = fluent::FluentArgs::new();
+        $(args.set(stringify!($arg), $value);)+
+        $crate::i18n::translate($id, Some(&args))
+    }};
+}
+pub(crate) use tr;
+
+/// Picks the language of the UI, either `language` or the one from the environment. Only the
+/// first call has an effect, and it must come before anything is translated.
+pub fn init(language: Option<&str>) {
+    let _ = BUNDLE.set(load(language));
+}
+
+pub fn translate(id: &str, args: Option<&FluentArgs>) -> String {
+    let bundle = BUNDLE.get_or_init(|| load(None));
+    let Some(pattern) = bundle.get_message(id).and_then(|msg| msg.value()) else {
+        eprintln!("Missing translation for \"{id}\"");
+        return id.to_string();
+    };
+
+    let mut errors = Vec::new();
+    let text = bundle.format_pattern(pattern, args, &mut errors);
+    for e in errors {
+        eprintln!("Failed to translate \"{id}\": {e}");
+    }
+    text.into_owned()
+}
+
+fn load(language: Option<&str>) -> FluentBundle<FluentResource> {
+    let requested = language.map(str::to_string).or_else(system_language);
+    let (lang, source) = requested
+        .as_deref()
+        .and_then(|requested| LOCALES.iter().find(|(lang, _)| *lang == requested))
+        .unwrap_or(&LOCALES[0]);
+
+    let langid: LanguageIdentifier = lang.parse().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // the direction marks around arguments show up as boxes in some fonts
+    bundle.set_use_isolating(false);
+
+    for source in [LOCALES[0].1, source] {
+        match FluentResource::try_new(source.to_string()) {
+            Ok(resource) => bundle.add_resource_overriding(resource),
+            Err((_, errors)) => eprintln!("Failed to parse translation for {lang}: {errors:?}"),
+        }
+    }
+    bundle
+}
+
+// the language part of the first locale variable that is set, e.g. "de" for "de_DE.UTF-8"
+fn system_language() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(std::env::var_os)
+        .find(|value| !value.is_empty())
+        .and_then(|value| {
+            let value = value.to_str()?;
+            let lang = value.split(['_', '-', '.', '@']).next()?;
+            (lang != "C" && lang != "POSIX").then(|| lang.to_lowercase())
+        })
+}
@@ -0,0 +1,587 @@
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use evdev::{Device, EventType, KeyCode};
+use global_hotkey::hotkey::{Code, Modifiers};
+use iced::{
+    futures::{SinkExt, Stream, channel::mpsc::Sender},
+    stream,
+};
+use tokio::task::JoinSet;
+
+use crate::{
+    app::{HotKeyAction, Msg},
+    events::{self, Level},
+    hotkey::{Binding, HotKeyConfig},
+    i18n::tr,
+};
+
+const INPUT_DIR: &str = "/dev/input";
+// links named after the device's bus, vendor and model, which udev keeps the same across
+// reboots and USB ports
+const BY_ID_DIR: &str = "/dev/input/by-id";
+// how often plugged in keyboards are looked for
+const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+// key events are 1 when pressed, 0 when released and 2 while held down
+const RELEASED: i32 = 0;
+const PRESSED: i32 = 1;
+
+/// Runs `listen` on every keyboard, and on ones plugged in later, for as long as the future is
+/// polled. `purpose` finishes "listening to ... for", for the event log.
+pub async fn each_keyboard<F>(purpose: &str, listen: impl Fn(PathBuf) -> F)
+where
+    F: Future<Output = io::Result<()>> + Send + 'static,
+{
+    // dropping the set stops listening to every keyboard
+    let mut listeners: JoinSet<(PathBuf, io::Result<()>)> = JoinSet::new();
+    let mut listening = HashSet::new();
+    let mut warned = false;
+    loop {
+        // unplugged keyboards end their listeners
+        while let Some(res) = listeners.try_join_next() {
+            if let Ok((path, res)) = res {
+                if let Err(e) = res {
+                    events::record(
+                        Level::Info,
+                        format!("Stopped listening to {} for {purpose}: {e}", path.display()),
+                    );
+                }
+                listening.remove(&path);
+            }
+        }
+
+        let (keyboards, denied) = tokio::task::spawn_blocking(keyboards)
+            .await
+            .unwrap_or_default();
+        if keyboards.is_empty() && denied > 0 && !warned {
+            events::record(
+                Level::Warning,
+                format!("{denied} input devices can't be read, so nothing listens for {purpose}"),
+            );
+            warned = true;
+        }
+        for (path, _) in keyboards {
+            if listening.insert(path.clone()) {
+                let listener = listen(path.clone());
+                listeners.spawn(async move { (path, listener.await) });
+            }
+        }
+
+        tokio::time::sleep(RESCAN_INTERVAL).await;
+    }
+}
+
+/// A keyboard the trigger can be kept to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keyboard {
+    pub id: String,
+    pub name: String,
+}
+
+/// The keyboards found, for the settings page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scan {
+    pub keyboards: Vec<Keyboard>,
+    // devices that couldn't be opened, usually for lack of the input group
+    pub denied: usize,
+}
+
+/// Every keyboard that can be opened, sorted by name.
+pub fn scan() -> Scan {
+    let (keyboards, denied) = keyboards();
+    let mut keyboards: Vec<_> = keyboards
+        .into_iter()
+        .map(|(path, device)| Keyboard {
+            id: id(&path, &device),
+            name: device.name().unwrap_or("Unknown keyboard").to_string(),
+        })
+        .collect();
+    keyboards.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.id.cmp(&b.id)));
+    Scan { keyboards, denied }
+}
+
+impl Scan {
+    /// The keyboards the [`passive`] bindings are read from.
+    pub fn read_for(&self, passive: &[Passive]) -> Vec<&Keyboard> {
+        self.keyboards
+            .iter()
+            .filter(|keyboard| {
+                passive
+                    .iter()
+                    .any(|p| p.keyboard.as_ref().is_none_or(|id| *id == keyboard.id))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Choice {
+    Any,
+    Keyboard(Keyboard),
+}
+
+impl Display for Choice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Any => f.write_str(&tr!("keyboard-any")),
+            Self::Keyboard(keyboard) => f.write_str(&keyboard.name),
+        }
+    }
+}
+
+// the name of its link in /dev/input/by-id, or else the name it reports, which is all there is
+// for e.g. Bluetooth keyboards
+fn id(path: &Path, device: &Device) -> String {
+    let by_id = std::fs::read_dir(BY_ID_DIR).ok().and_then(|entries| {
+        entries
+            .filter_map(Result::ok)
+            .find(|entry| std::fs::canonicalize(entry.path()).is_ok_and(|target| target == path))
+            .and_then(|entry| entry.file_name().into_string().ok())
+    });
+    by_id
+        .or_else(|| device.name().map(ToString::to_string))
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+// the devices with letter keys, and how many couldn't be opened
+fn keyboards() -> (Vec<(PathBuf, Device)>, usize) {
+    let mut found = (Vec::new(), 0);
+    let Ok(entries) = std::fs::read_dir(INPUT_DIR) else {
+        return found;
+    };
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        let is_event = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("event"));
+        if !is_event {
+            continue;
+        }
+        match Device::open(&path) {
+            Ok(device) if is_keyboard(&device) => found.0.push((path, device)),
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => found.1 += 1,
+            Ok(_) | Err(_) => {}
+        }
+    }
+    found
+}
+
+fn is_keyboard(device: &Device) -> bool {
+    device
+        .supported_keys()
+        .is_some_and(|keys| keys.contains(KeyCode::KEY_A) && keys.contains(KeyCode::KEY_SPACE))
+}
+
+/// A binding that is detected by reading the keyboards directly instead of grabbing it through
+/// X11, see [`passive`].
+#[derive(Debug, Clone)]
+pub struct Passive {
+    pub action: HotKeyAction,
+    pub binding: Binding,
+    // only heard from the keyboard with this id
+    pub keyboard: Option<String>,
+}
+
+/// The bindings that are read from the keyboards. Grabbing a chord would grab each of its keys
+/// on their own, and a grab can't tell keyboards apart, so these are listened to without taking
+/// them away from other applications.
+pub fn passive(hotkeys: &HotKeyConfig<Binding>, trigger_keyboard: Option<&str>) -> Vec<Passive> {
+    [
+        (HotKeyAction::Trigger, &hotkeys.trigger),
+        (HotKeyAction::ToggleActive, &hotkeys.toggle_active),
+        (HotKeyAction::WhisperTrigger, &hotkeys.whisper),
+        (HotKeyAction::NextProfile, &hotkeys.next_profile),
+    ]
+    .into_iter()
+    .filter_map(|(action, binding)| {
+        let keyboard = trigger_keyboard
+            .filter(|_| action == HotKeyAction::Trigger)
+            .map(ToString::to_string);
+        let read = match binding {
+            Binding::Chord(_) => true,
+            Binding::HotKey(_) => keyboard.is_some(),
+            Binding::Unbound => false,
+        };
+        read.then(|| Passive {
+            action,
+            binding: binding.clone(),
+            keyboard,
+        })
+    })
+    .collect()
+}
+
+/// Sends `Msg::HotKeyEvent` for the [`passive`] bindings in `hotkeys`, with the trigger kept to
+/// the keyboard with the given id.
+pub fn bindings(
+    (hotkeys, trigger_keyboard): &(HotKeyConfig<Binding>, Option<String>),
+) -> impl Stream<Item = Msg> + use<> {
+    let passive = passive(hotkeys, trigger_keyboard.as_deref());
+    stream::channel(10, async move |tx| {
+        each_keyboard("hotkeys", |path| follow(path, passive.clone(), tx.clone())).await;
+    })
+}
+
+async fn follow(path: PathBuf, passive: Vec<Passive>, mut tx: Sender<Msg>) -> io::Result<()> {
+    let device = Device::open(&path)?;
+    // the others are left to their own keyboards
+    let id = id(&path, &device);
+    let bindings: Vec<_> = passive
+        .into_iter()
+        .filter(|p| p.keyboard.as_ref().is_none_or(|keyboard| *keyboard == id))
+        .map(|p| (p.action, p.binding))
+        .collect();
+    // looked at again with the next scan, in case another device takes over its path
+    if bindings.is_empty() {
+        return Ok(());
+    }
+
+    let mut events = device.into_event_stream()?;
+    let mut tracker = Tracker::default();
+    loop {
+        let event = events.next_event().await?;
+        if event.event_type() != EventType::KEY {
+            continue;
+        }
+        let pressed = match event.value() {
+            PRESSED => true,
+            RELEASED => false,
+            _ => continue,
+        };
+        for (action, pressed) in tracker.key(&bindings, KeyCode(event.code()), pressed) {
+            if tx.send(Msg::HotKeyEvent(action, pressed)).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Turns the keys of one keyboard going up and down into presses and releases of bindings.
+#[derive(Debug, Default)]
+pub struct Tracker {
+    held: HashSet<KeyCode>,
+    // indices of the bindings that are down
+    active: HashSet<usize>,
+}
+
+impl Tracker {
+    pub fn key(
+        &mut self,
+        bindings: &[(HotKeyAction, Binding)],
+        key: KeyCode,
+        pressed: bool,
+    ) -> Vec<(HotKeyAction, bool)> {
+        // modifiers count as they were before this key
+        let mods = self.modifiers();
+        if pressed {
+            self.held.insert(key);
+        } else {
+            self.held.remove(&key);
+        }
+
+        let mut changes = Vec::new();
+        for (i, (action, binding)) in bindings.iter().enumerate() {
+            let involved = match binding {
+                Binding::HotKey(hk) => key_code(hk.key) == Some(key),
+                Binding::Chord(codes) => codes.iter().any(|c| key_code(*c) == Some(key)),
+                Binding::Unbound => false,
+            };
+            if !involved {
+                continue;
+            }
+
+            // a binding is down once all of its keys are, and up as soon as any is released
+            let down = pressed
+                && match binding {
+                    Binding::HotKey(hk) => hk.mods == mods,
+                    Binding::Chord(codes) => codes
+                        .iter()
+                        .all(|c| key_code(*c).is_some_and(|k| self.held.contains(&k))),
+                    Binding::Unbound => false,
+                };
+            if down && self.active.insert(i) {
+                changes.push((action.clone(), true));
+            } else if !pressed && self.active.remove(&i) {
+                changes.push((action.clone(), false));
+            }
+        }
+        changes
+    }
+
+    fn modifiers(&self) -> Modifiers {
+        [
+            (KeyCode::KEY_LEFTCTRL, Modifiers::CONTROL),
+            (KeyCode::KEY_RIGHTCTRL, Modifiers::CONTROL),
+            (KeyCode::KEY_LEFTSHIFT, Modifiers::SHIFT),
+            (KeyCode::KEY_RIGHTSHIFT, Modifiers::SHIFT),
+            (KeyCode::KEY_LEFTALT, Modifiers::ALT),
+            (KeyCode::KEY_RIGHTALT, Modifiers::ALT),
+            (KeyCode::KEY_LEFTMETA, Modifiers::SUPER),
+            (KeyCode::KEY_RIGHTMETA, Modifiers::SUPER),
+        ]
+        .into_iter()
+        .filter(|(key, _)| self.held.contains(key))
+        .fold(Modifiers::empty(), |mods, (_, m)| mods | m)
+    }
+}
+
+/// The evdev key for a key code, for the keys bindings can be made of.
+// a table, one line per key
+#[allow(clippy::too_many_lines)]
+pub fn key_code(code: Code) -> Option<KeyCode> {
+    let key = match code {
+        Code::KeyA => KeyCode::KEY_A,
+        Code::KeyB => KeyCode::KEY_B,
+        Code::KeyC => KeyCode::KEY_C,
+        Code::KeyD => KeyCode::KEY_D,
+        Code::KeyE => KeyCode::KEY_E,
+        Code::KeyF => KeyCode::KEY_F,
+        Code::KeyG => KeyCode::KEY_G,
+        Code::KeyH => KeyCode::KEY_H,
+        Code::KeyI => KeyCode::KEY_I,
+        Code::KeyJ => KeyCode::KEY_J,
+        Code::KeyK => KeyCode::KEY_K,
+        Code::KeyL => KeyCode::KEY_L,
+        Code::KeyM => KeyCode::KEY_M,
+        Code::KeyN => KeyCode::KEY_N,
+        Code::KeyO => KeyCode::KEY_O,
+        Code::KeyP => KeyCode::KEY_P,
+        Code::KeyQ => KeyCode::KEY_Q,
+        Code::KeyR => KeyCode::KEY_R,
+        Code::KeyS => KeyCode::KEY_S,
+        Code::KeyT => KeyCode::KEY_T,
+        Code::KeyU => KeyCode::KEY_U,
+        Code::KeyV => KeyCode::KEY_V,
+        Code::KeyW => KeyCode::KEY_W,
+        Code::KeyX => KeyCode::KEY_X,
+        Code::KeyY => KeyCode::KEY_Y,
+        Code::KeyZ => KeyCode::KEY_Z,
+        Code::Digit0 => KeyCode::KEY_0,
+        Code::Digit1 => KeyCode::KEY_1,
+        Code::Digit2 => KeyCode::KEY_2,
+        Code::Digit3 => KeyCode::KEY_3,
+        Code::Digit4 => KeyCode::KEY_4,
+        Code::Digit5 => KeyCode::KEY_5,
+        Code::Digit6 => KeyCode::KEY_6,
+        Code::Digit7 => KeyCode::KEY_7,
+        Code::Digit8 => KeyCode::KEY_8,
+        Code::Digit9 => KeyCode::KEY_9,
+        Code::Backquote => KeyCode::KEY_GRAVE,
+        Code::Minus => KeyCode::KEY_MINUS,
+        Code::Equal => KeyCode::KEY_EQUAL,
+        Code::BracketLeft => KeyCode::KEY_LEFTBRACE,
+        Code::BracketRight => KeyCode::KEY_RIGHTBRACE,
+        Code::Backslash => KeyCode::KEY_BACKSLASH,
+        Code::IntlBackslash => KeyCode::KEY_102ND,
+        Code::Semicolon => KeyCode::KEY_SEMICOLON,
+        Code::Quote => KeyCode::KEY_APOSTROPHE,
+        Code::Comma => KeyCode::KEY_COMMA,
+        Code::Period => KeyCode::KEY_DOT,
+        Code::Slash => KeyCode::KEY_SLASH,
+        Code::Backspace => KeyCode::KEY_BACKSPACE,
+        Code::Tab => KeyCode::KEY_TAB,
+        Code::Enter => KeyCode::KEY_ENTER,
+        Code::Space => KeyCode::KEY_SPACE,
+        Code::Escape => KeyCode::KEY_ESC,
+        Code::CapsLock => KeyCode::KEY_CAPSLOCK,
+        Code::NumLock => KeyCode::KEY_NUMLOCK,
+        Code::ScrollLock => KeyCode::KEY_SCROLLLOCK,
+        Code::PrintScreen => KeyCode::KEY_SYSRQ,
+        Code::Pause => KeyCode::KEY_PAUSE,
+        Code::ContextMenu => KeyCode::KEY_COMPOSE,
+        Code::Insert => KeyCode::KEY_INSERT,
+        Code::Delete => KeyCode::KEY_DELETE,
+        Code::Home => KeyCode::KEY_HOME,
+        Code::End => KeyCode::KEY_END,
+        Code::PageUp => KeyCode::KEY_PAGEUP,
+        Code::PageDown => KeyCode::KEY_PAGEDOWN,
+        Code::ArrowUp => KeyCode::KEY_UP,
+        Code::ArrowDown => KeyCode::KEY_DOWN,
+        Code::ArrowLeft => KeyCode::KEY_LEFT,
+        Code::ArrowRight => KeyCode::KEY_RIGHT,
+        Code::Numpad0 => KeyCode::KEY_KP0,
+        Code::Numpad1 => KeyCode::KEY_KP1,
+        Code::Numpad2 => KeyCode::KEY_KP2,
+        Code::Numpad3 => KeyCode::KEY_KP3,
+        Code::Numpad4 => KeyCode::KEY_KP4,
+        Code::Numpad5 => KeyCode::KEY_KP5,
+        Code::Numpad6 => KeyCode::KEY_KP6,
+        Code::Numpad7 => KeyCode::KEY_KP7,
+        Code::Numpad8 => KeyCode::KEY_KP8,
+        Code::Numpad9 => KeyCode::KEY_KP9,
+        Code::NumpadAdd => KeyCode::KEY_KPPLUS,
+        Code::NumpadSubtract => KeyCode::KEY_KPMINUS,
+        Code::NumpadMultiply => KeyCode::KEY_KPASTERISK,
+        Code::NumpadDivide => KeyCode::KEY_KPSLASH,
+        Code::NumpadDecimal => KeyCode::KEY_KPDOT,
+        Code::NumpadEnter => KeyCode::KEY_KPENTER,
+        Code::NumpadEqual => KeyCode::KEY_KPEQUAL,
+        Code::F1 => KeyCode::KEY_F1,
+        Code::F2 => KeyCode::KEY_F2,
+        Code::F3 => KeyCode::KEY_F3,
+        Code::F4 => KeyCode::KEY_F4,
+        Code::F5 => KeyCode::KEY_F5,
+        Code::F6 => KeyCode::KEY_F6,
+        Code::F7 => KeyCode::KEY_F7,
+        Code::F8 => KeyCode::KEY_F8,
+        Code::F9 => KeyCode::KEY_F9,
+        Code::F10 => KeyCode::KEY_F10,
+        Code::F11 => KeyCode::KEY_F11,
+        Code::F12 => KeyCode::KEY_F12,
+        Code::F13 => KeyCode::KEY_F13,
+        Code::F14 => KeyCode::KEY_F14,
+        Code::F15 => KeyCode::KEY_F15,
+        Code::F16 => KeyCode::KEY_F16,
+        Code::F17 => KeyCode::KEY_F17,
+        Code::F18 => KeyCode::KEY_F18,
+        Code::F19 => KeyCode::KEY_F19,
+        Code::F20 => KeyCode::KEY_F20,
+        Code::F21 => KeyCode::KEY_F21,
+        Code::F22 => KeyCode::KEY_F22,
+        Code::F23 => KeyCode::KEY_F23,
+        Code::F24 => KeyCode::KEY_F24,
+        Code::AudioVolumeDown => KeyCode::KEY_VOLUMEDOWN,
+        Code::AudioVolumeUp => KeyCode::KEY_VOLUMEUP,
+        Code::AudioVolumeMute => KeyCode::KEY_MUTE,
+        Code::MediaPlayPause => KeyCode::KEY_PLAYPAUSE,
+        Code::MediaStop => KeyCode::KEY_STOPCD,
+        Code::MediaTrackNext => KeyCode::KEY_NEXTSONG,
+        Code::MediaTrackPrevious => KeyCode::KEY_PREVIOUSSONG,
+        _ => return None,
+    };
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use global_hotkey::hotkey::HotKey;
+
+    use super::*;
+
+    fn chord() -> Vec<(HotKeyAction, Binding)> {
+        vec![(
+            HotKeyAction::Trigger,
+            Binding::Chord(vec![Code::KeyV, Code::KeyB]),
+        )]
+    }
+
+    #[test]
+    fn only_the_trigger_keyboard_is_read_for_a_kept_trigger() {
+        let keyboard = |id: &str| Keyboard {
+            id: id.into(),
+            name: id.into(),
+        };
+        let scan = Scan {
+            keyboards: vec![keyboard("pad"), keyboard("main")],
+            denied: 0,
+        };
+        let kept = Passive {
+            action: HotKeyAction::Trigger,
+            binding: Binding::HotKey(HotKey::new(None, Code::F13)),
+            keyboard: Some("pad".into()),
+        };
+        assert_eq!(
+            scan.read_for(std::slice::from_ref(&kept)),
+            [&scan.keyboards[0]]
+        );
+
+        let chord = Passive {
+            action: HotKeyAction::ToggleActive,
+            binding: Binding::Chord(vec![Code::KeyV, Code::KeyB]),
+            keyboard: None,
+        };
+        assert_eq!(scan.read_for(&[kept, chord]).len(), 2);
+    }
+
+    #[test]
+    fn chord_is_down_once_all_keys_are() {
+        let bindings = chord();
+        let mut tracker = Tracker::default();
+        assert!(tracker.key(&bindings, KeyCode::KEY_V, true).is_empty());
+        assert_eq!(
+            tracker.key(&bindings, KeyCode::KEY_B, true),
+            [(HotKeyAction::Trigger, true)]
+        );
+        // any of its keys lets go of it
+        assert_eq!(
+            tracker.key(&bindings, KeyCode::KEY_V, false),
+            [(HotKeyAction::Trigger, false)]
+        );
+        assert!(tracker.key(&bindings, KeyCode::KEY_B, false).is_empty());
+    }
+
+    #[test]
+    fn other_keys_leave_chords_alone() {
+        let bindings = chord();
+        let mut tracker = Tracker::default();
+        assert!(tracker.key(&bindings, KeyCode::KEY_V, true).is_empty());
+        assert!(tracker.key(&bindings, KeyCode::KEY_N, true).is_empty());
+        assert!(tracker.key(&bindings, KeyCode::KEY_N, false).is_empty());
+        assert!(tracker.key(&bindings, KeyCode::KEY_V, false).is_empty());
+    }
+
+    #[test]
+    fn hotkeys_need_their_exact_modifiers() {
+        let bindings = vec![(
+            HotKeyAction::ToggleActive,
+            Binding::HotKey(HotKey::new(Some(Modifiers::CONTROL), Code::KeyP)),
+        )];
+        let mut tracker = Tracker::default();
+        assert!(tracker.key(&bindings, KeyCode::KEY_P, true).is_empty());
+        assert!(tracker.key(&bindings, KeyCode::KEY_P, false).is_empty());
+
+        assert!(
+            tracker
+                .key(&bindings, KeyCode::KEY_RIGHTCTRL, true)
+                .is_empty()
+        );
+        assert_eq!(
+            tracker.key(&bindings, KeyCode::KEY_P, true),
+            [(HotKeyAction::ToggleActive, true)]
+        );
+        assert_eq!(
+            tracker.key(&bindings, KeyCode::KEY_P, false),
+            [(HotKeyAction::ToggleActive, false)]
+        );
+
+        assert!(
+            tracker
+                .key(&bindings, KeyCode::KEY_LEFTSHIFT, true)
+                .is_empty()
+        );
+        assert!(tracker.key(&bindings, KeyCode::KEY_P, true).is_empty());
+    }
+
+    #[test]
+    fn only_chords_and_a_kept_trigger_are_passive() {
+        let mut hotkeys = HotKeyConfig::<Binding>::default();
+        assert!(passive(&hotkeys, None).is_empty());
+
+        let passive_trigger = passive(&hotkeys, Some("usb-Macro_Pad-event-kbd"));
+        assert_eq!(passive_trigger.len(), 1);
+        assert_eq!(passive_trigger[0].action, HotKeyAction::Trigger);
+        assert_eq!(
+            passive_trigger[0].keyboard.as_deref(),
+            Some("usb-Macro_Pad-event-kbd")
+        );
+
+        hotkeys.whisper = Binding::Chord(vec![Code::KeyV, Code::KeyB]);
+        let passive_chord = passive(&hotkeys, None);
+        assert_eq!(passive_chord.len(), 1);
+        assert_eq!(passive_chord[0].action, HotKeyAction::WhisperTrigger);
+        assert_eq!(passive_chord[0].keyboard, None);
+    }
+
+    #[test]
+    fn chord_keys_map_to_evdev() {
+        assert_eq!(key_code(Code::KeyV), Some(KeyCode::KEY_V));
+        assert_eq!(key_code(Code::Numpad0), Some(KeyCode::KEY_KP0));
+        assert_eq!(key_code(Code::IntlBackslash), Some(KeyCode::KEY_102ND));
+    }
+}
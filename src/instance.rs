@@ -0,0 +1,52 @@
+use std::sync::OnceLock;
+
+use crate::APP_ID;
+
+// set from --instance-id, unset for the default instance
+static ID: OnceLock<String> = OnceLock::new();
+static APP_ID_WITH_INSTANCE: OnceLock<String> = OnceLock::new();
+
+/// Checks an instance id given on the command line. It ends up in D-Bus names and object paths,
+/// which only allow a few characters.
+pub fn parse_id(id: &str) -> Result<String, String> {
+    let mut chars = id.chars();
+    let starts_with_letter = chars.next().is_some_and(|c| c.is_ascii_alphabetic());
+    if starts_with_letter && chars.all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        Ok(id.to_string())
+    } else {
+        Err("must start with a letter and contain only letters, digits and underscores".into())
+    }
+}
+
+/// Runs this process as another instance, which has its own config, virtual microphone, control
+/// socket and names on the session bus. Must be called before any of those are used.
+pub fn set_id(id: String) {
+    let _ = ID.set(id);
+}
+
+pub fn id() -> Option<&'static str> {
+    ID.get().map(String::as_str)
+}
+
+/// The app id, with the instance id after it for other instances, e.g.
+/// `com.github.Adamskye.GlobalPushToTalk.work`. The portal remembers hotkeys by it, so each
+/// instance gets its own.
+pub fn app_id() -> &'static str {
+    APP_ID_WITH_INSTANCE.get_or_init(|| suffixed(APP_ID, "."))
+}
+
+/// `base` for the default instance, otherwise with `separator` and the instance id after it.
+pub fn suffixed(base: &str, separator: &str) -> String {
+    match id() {
+        Some(id) => format!("{base}{separator}{id}"),
+        None => base.to_string(),
+    }
+}
+
+/// A file name with the instance id before its extension, e.g. `stats-work.toml`.
+pub fn file_name(name: &str) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, extension)) => format!("{}.{extension}", suffixed(stem, "-")),
+        None => suffixed(name, "-"),
+    }
+}
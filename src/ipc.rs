@@ -0,0 +1,1294 @@
+use std::{
+    fmt::Write as _,
+    fs::{DirBuilder, File, OpenOptions, Permissions},
+    io::{self, BufRead, BufReader, Write},
+    net::TcpStream,
+    os::unix::{
+        fs::{DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt},
+        net::UnixStream,
+    },
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use iced::{
+    Task,
+    futures::{SinkExt, Stream},
+    stream,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader as AsyncBufReader},
+    net::{UnixListener, UnixStream as AsyncUnixStream},
+};
+
+use nix::{
+    errno::Errno,
+    fcntl::{Flock, FlockArg},
+    unistd::Uid,
+};
+
+use crate::{
+    APP_ID,
+    app::Msg,
+    events::{self, Level},
+    flatpak, instance,
+};
+
+const DEFAULT_TAP_MS: u64 = 200;
+// how long to wait for an instance that holds the lock to start listening
+const STARTUP_WAIT: Duration = Duration::from_secs(3);
+const STARTUP_POLL: Duration = Duration::from_millis(100);
+// requests are short, anything longer or slower is dropped
+const MAX_REQUEST: u64 = 4096;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const ACCEPT_RETRY: Duration = Duration::from_millis(100);
+
+/// Version of the JSON protocol, raised whenever a change would break existing clients.
+pub const PROTOCOL: u32 = 1;
+
+/// Commands for the running instance, as given on the command line.
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Show the window
+    #[command(alias = "open")]
+    Show,
+    /// Turn push-to-talk on or off
+    Toggle,
+    /// Turn push-to-talk on
+    Enable,
+    /// Turn push-to-talk off
+    Disable,
+    /// Mute the microphone
+    Mute,
+    /// Unmute the microphone
+    Unmute,
+    /// Mute or unmute the microphone
+    ToggleMute,
+    /// Print the state, exiting with 0 while transmitting, 1 while muted and 2 while disabled
+    Status {
+        /// Keep running and print a line whenever the state changes, e.g. for status bars
+        #[arg(long)]
+        follow: bool,
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Show or change the microphone of the running instance
+    Mic {
+        #[command(subcommand)]
+        action: MicCommand,
+    },
+    /// Print the available microphones, also when the app isn't running
+    ListDevices {
+        /// Print them as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Unmute, as if the trigger hotkey was pressed
+    TriggerPress,
+    /// Mute again after trigger-press
+    TriggerRelease,
+    /// Press the trigger, wait, then release it
+    TriggerTap {
+        /// How long to hold the trigger, in milliseconds
+        #[arg(long, default_value_t = DEFAULT_TAP_MS)]
+        ms: u64,
+    },
+    /// Check the system for what the app needs, without starting it. Exits with 1 when
+    /// something it can't work without is missing
+    Doctor,
+    /// Change a hotkey of the running instance, then print all of them. Not available on
+    /// Wayland, where the desktop's settings decide them
+    Bind {
+        #[arg(value_enum)]
+        action: HotKeyTarget,
+        /// E.g. "Insert", "CTRL+SUPER+KeyP", or several keys to be held together like
+        /// "KeyA+KeyS"
+        #[arg(required_unless_present = "clear")]
+        binding: Option<String>,
+        /// Leave the action unbound, so that it can only be used from the window, tray or
+        /// commands
+        #[arg(long, conflicts_with = "binding")]
+        clear: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum MicCommand {
+    /// Use another microphone, exiting with 4 when nothing matches and 5 when several do
+    Set {
+        /// A source name, or part of its description
+        query: String,
+    },
+    /// Print the microphone in use
+    Get,
+    /// Print the available microphones, like list-devices
+    List {
+        /// Print them as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MatchError {
+    #[error("no microphone matches \"{0}\"")]
+    NoMatch(String),
+    // descriptions of everything that matched
+    #[error("\"{0}\" matches several microphones: {names}", names = .1.join(", "))]
+    Ambiguous(String, Vec<String>),
+}
+
+/// The hotkeys `bind` can change.
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HotKeyTarget {
+    Trigger,
+    ToggleActive,
+    Whisper,
+    NextProfile,
+}
+
+impl HotKeyTarget {
+    pub const ALL: [Self; 4] = [
+        Self::Trigger,
+        Self::ToggleActive,
+        Self::Whisper,
+        Self::NextProfile,
+    ];
+
+    /// The name as on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Trigger => "trigger",
+            Self::ToggleActive => "toggle-active",
+            Self::Whisper => "whisper",
+            Self::NextProfile => "next-profile",
+        }
+    }
+}
+
+/// How `status` prints the state.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// A sentence
+    #[default]
+    Plain,
+    /// The state as JSON, like in answers on the socket
+    Json,
+    /// JSON for a Waybar custom module, with the state as its class
+    Waybar,
+}
+
+impl Command {
+    fn request(&self) -> Request {
+        match self {
+            Self::Show => Request::Show,
+            Self::Toggle => Request::ToggleActive,
+            Self::Enable => Request::SetActive(true),
+            Self::Disable => Request::SetActive(false),
+            Self::Mute => Request::SetMuted(true),
+            Self::Unmute => Request::SetMuted(false),
+            Self::ToggleMute => Request::ToggleMute,
+            // doctor is checked by the client itself, over the socket it only asks for the state
+            Self::Status { follow: false, .. }
+            | Self::Mic {
+                action: MicCommand::Get,
+            }
+            | Self::Doctor => Request::Status,
+            Self::Status { follow: true, .. } => Request::Subscribe,
+            Self::Mic {
+                action: MicCommand::Set { query },
+            } => Request::SelectMicrophone(query.clone()),
+            Self::ListDevices { .. }
+            | Self::Mic {
+                action: MicCommand::List { .. },
+            } => Request::ListDevices,
+            Self::TriggerPress => Request::TriggerPress,
+            Self::TriggerRelease => Request::TriggerRelease,
+            Self::TriggerTap { ms } => Request::TriggerTap(*ms),
+            Self::Bind {
+                action, binding, ..
+            } => Request::Bind {
+                action: *action,
+                binding: binding.clone(),
+            },
+        }
+    }
+}
+
+// commands from before the JSON protocol, written as plain text exactly as on the command line
+// (e.g. "trigger-tap --ms 500") without a binary name in front
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct Legacy {
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// One line of JSON, e.g. `{"protocol":1,"cmd":"set_active","value":true}`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct Envelope {
+    protocol: u32,
+    // only required for remote control
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    #[serde(flatten)]
+    request: Request,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "cmd", content = "value", rename_all = "snake_case")]
+pub enum Request {
+    Show,
+    SetActive(bool),
+    ToggleActive,
+    SetMuted(bool),
+    ToggleMute,
+    // a source name, or part of its name or description
+    SelectMicrophone(String),
+    Status,
+    // keeps the connection open and sends the state again on every change
+    Subscribe,
+    ListDevices,
+    TriggerPress,
+    TriggerRelease,
+    // milliseconds
+    TriggerTap(u64),
+    // without a binding, the action gets its default one back
+    Bind {
+        action: HotKeyTarget,
+        binding: Option<String>,
+    },
+}
+
+/// Everything a client may want to know, sent with every answer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub active: bool,
+    pub muted: bool,
+    // description of the selected microphone
+    pub microphone: Option<String>,
+}
+
+impl Snapshot {
+    pub fn description(&self) -> &'static str {
+        match (self.active, self.muted) {
+            (true, false) => "Push-to-talk is enabled, the microphone is transmitting",
+            (true, true) => "Push-to-talk is enabled, the microphone is muted",
+            (false, _) => "Push-to-talk is disabled",
+        }
+    }
+
+    /// For `status`, so that scripts can branch on it.
+    pub fn exit_code(&self) -> i32 {
+        match (self.active, self.muted) {
+            (true, false) => 0,
+            (true, true) => 1,
+            (false, _) => 2,
+        }
+    }
+
+    /// What the plain text commands are answered with.
+    pub fn name(&self) -> &'static str {
+        match (self.active, self.muted) {
+            (true, false) => "transmitting",
+            (true, true) => "muted",
+            (false, _) => "disabled",
+        }
+    }
+
+    /// One line for `status`.
+    pub fn format(&self, format: Format) -> String {
+        match format {
+            Format::Plain => self.description().to_string(),
+            Format::Json => serde_json::to_string(self).unwrap_or_default(),
+            Format::Waybar => {
+                let text = match (self.active, self.muted) {
+                    (true, false) => "LIVE",
+                    (true, true) => "MUTED",
+                    (false, _) => "OFF",
+                };
+                let tooltip = match &self.microphone {
+                    Some(mic) => format!("{}\n{mic}", self.description()),
+                    None => self.description().to_string(),
+                };
+                serde_json::json!({
+                    "text": text,
+                    "alt": self.name(),
+                    "class": self.name(),
+                    "tooltip": tooltip,
+                })
+                .to_string()
+            }
+        }
+    }
+
+    // a line as it goes over the socket
+    fn line(&self, legacy: bool) -> String {
+        if legacy {
+            return format!("{}\n", self.name());
+        }
+        Response::ok(self.clone()).line()
+    }
+}
+
+/// An input source, as listed by `list-devices`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    pub name: String,
+    pub description: String,
+    // the system's default source
+    pub is_default: bool,
+    // used for the virtual mic, only ever set by a running instance
+    pub selected: bool,
+}
+
+/// Finds the device with `query` as its exact name, or else the only one whose name or
+/// description contains it, ignoring case.
+pub fn find_microphone<'a>(devices: &'a [Device], query: &str) -> Result<&'a Device, MatchError> {
+    if let Some(dev) = devices.iter().find(|dev| dev.name == query) {
+        return Ok(dev);
+    }
+
+    let lowercase = query.to_lowercase();
+    let matches: Vec<_> = devices
+        .iter()
+        .filter(|dev| {
+            dev.name.to_lowercase().contains(&lowercase)
+                || dev.description.to_lowercase().contains(&lowercase)
+        })
+        .collect();
+    match matches.as_slice() {
+        [] => Err(MatchError::NoMatch(query.to_string())),
+        [dev] => Ok(dev),
+        _ => Err(MatchError::Ambiguous(
+            query.to_string(),
+            matches.iter().map(|dev| dev.description.clone()).collect(),
+        )),
+    }
+}
+
+/// A hotkey and what it's bound to, as printed by `bind`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BoundHotKey {
+    pub action: HotKeyTarget,
+    // as it would be given to bind
+    pub binding: String,
+    // for people, e.g. "Ctrl + Super + P"
+    pub description: String,
+}
+
+/// One line of JSON answering a request.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub protocol: u32,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<Snapshot>,
+    // only for list_devices
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub devices: Option<Vec<Device>>,
+    // only for bind, every hotkey once the change is made
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hotkeys: Option<Vec<BoundHotKey>>,
+}
+
+impl Response {
+    fn line(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default() + "\n"
+    }
+
+    fn ok(state: Snapshot) -> Self {
+        Self {
+            protocol: PROTOCOL,
+            ok: true,
+            error: None,
+            state: Some(state),
+            devices: None,
+            hotkeys: None,
+        }
+    }
+
+    fn error(error: &str) -> Self {
+        Self {
+            protocol: PROTOCOL,
+            ok: false,
+            error: Some(error.into()),
+            state: None,
+            devices: None,
+            hotkeys: None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid response: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("{0}")]
+    Refused(String),
+}
+
+/// A client of the control socket or of remote control.
+#[derive(Debug)]
+pub enum Connection {
+    Unix(UnixStream),
+    Tcp(TcpStream),
+}
+
+impl Connection {
+    fn write_all(&self, buf: &[u8]) -> io::Result<()> {
+        // both can be written through a shared reference
+        match self {
+            Self::Unix(stream) => {
+                let mut stream = stream;
+                stream.write_all(buf)
+            }
+            Self::Tcp(stream) => {
+                let mut stream = stream;
+                stream.write_all(buf)
+            }
+        }
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        match self {
+            Self::Unix(stream) => stream.set_nonblocking(nonblocking),
+            Self::Tcp(stream) => stream.set_nonblocking(nonblocking),
+        }
+    }
+}
+
+/// Where the answer to a request goes. Only the first answer is sent.
+#[derive(Debug, Clone)]
+pub struct Reply {
+    stream: Arc<Mutex<Option<Connection>>>,
+    legacy: bool,
+}
+
+impl Reply {
+    /// `legacy` answers in plain text, for commands from before the JSON protocol.
+    pub fn new(connection: Connection, legacy: bool) -> Self {
+        Self {
+            stream: Arc::new(Mutex::new(Some(connection))),
+            legacy,
+        }
+    }
+
+    pub fn send(&self, state: &Snapshot) {
+        let Some(stream) = self.take() else {
+            return;
+        };
+        // plain text answers end with the connection, as they always have
+        let answer = if self.legacy {
+            state.name().to_string()
+        } else {
+            state.line(false)
+        };
+        let _ = stream.write_all(answer.as_bytes());
+    }
+
+    /// Answers with the devices as well, or only their names for plain text commands.
+    pub fn send_devices(&self, state: &Snapshot, devices: Vec<Device>) {
+        let Some(stream) = self.take() else {
+            return;
+        };
+        let answer = if self.legacy {
+            devices.iter().fold(String::new(), |mut answer, dev| {
+                let _ = writeln!(answer, "{}", dev.name);
+                answer
+            })
+        } else {
+            let response = Response {
+                devices: Some(devices),
+                ..Response::ok(state.clone())
+            };
+            response.line()
+        };
+        let _ = stream.write_all(answer.as_bytes());
+    }
+
+    /// Answers with the hotkeys as well, one per line for plain text commands.
+    pub fn send_hotkeys(&self, state: &Snapshot, hotkeys: Vec<BoundHotKey>) {
+        let Some(stream) = self.take() else {
+            return;
+        };
+        let answer = if self.legacy {
+            hotkeys.iter().fold(String::new(), |mut answer, hk| {
+                let _ = writeln!(answer, "{} {}", hk.action.name(), hk.binding);
+                answer
+            })
+        } else {
+            let response = Response {
+                hotkeys: Some(hotkeys),
+                ..Response::ok(state.clone())
+            };
+            response.line()
+        };
+        let _ = stream.write_all(answer.as_bytes());
+    }
+
+    /// Answers that the request couldn't be carried out.
+    pub fn send_error(&self, error: &str) {
+        let Some(stream) = self.take() else {
+            return;
+        };
+        let answer = if self.legacy {
+            format!("error: {error}")
+        } else {
+            refusal(error)
+        };
+        let _ = stream.write_all(answer.as_bytes());
+    }
+
+    /// Keeps the connection around for every change to come.
+    pub fn subscribe(&self) -> Option<Subscriber> {
+        let stream = self.take()?;
+        // a subscriber that stops reading must not hold up the app
+        stream.set_nonblocking(true).ok()?;
+        Some(Subscriber {
+            remote: matches!(stream, Connection::Tcp(_)),
+            stream: Arc::new(stream),
+            legacy: self.legacy,
+        })
+    }
+
+    fn take(&self) -> Option<Connection> {
+        self.stream.lock().ok().and_then(|mut stream| stream.take())
+    }
+}
+
+/// A connection that is sent the state whenever it changes.
+#[derive(Debug, Clone)]
+pub struct Subscriber {
+    stream: Arc<Connection>,
+    legacy: bool,
+    // connected over the network
+    remote: bool,
+}
+
+impl Subscriber {
+    pub fn is_remote(&self) -> bool {
+        self.remote
+    }
+
+    /// Returns false once the other end is gone, or isn't keeping up.
+    pub fn send(&self, state: &Snapshot) -> bool {
+        self.stream
+            .write_all(state.line(self.legacy).as_bytes())
+            .is_ok()
+    }
+}
+
+// the runtime dir is private to the user and emptied on logout. /tmp is only a fallback, with a
+// directory of our own in it that nobody else can look into
+fn runtime_dir() -> PathBuf {
+    select_runtime_dir(
+        std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from),
+        flatpak::id().as_deref(),
+    )
+}
+
+fn select_runtime_dir(xdg_runtime_dir: Option<PathBuf>, flatpak_id: Option<&str>) -> PathBuf {
+    match xdg_runtime_dir {
+        Some(dir) => flatpak::shared_runtime_dir(dir, flatpak_id),
+        None => PathBuf::from(format!("/tmp/{APP_ID}.{}", Uid::current())),
+    }
+}
+
+fn runtime_path(extension: &str) -> PathBuf {
+    runtime_dir().join(format!("{}.{extension}", instance::app_id()))
+}
+
+// creates the fallback directory, refusing one that belongs to somebody else or that others can
+// get into
+fn prepare_runtime_dir() -> io::Result<()> {
+    let dir = runtime_dir();
+    match DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => return Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e),
+    }
+
+    let metadata = std::fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir()
+        || metadata.uid() != Uid::current().as_raw()
+        || metadata.mode() & 0o077 != 0
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is not private to this user", dir.display()),
+        ));
+    }
+    Ok(())
+}
+
+// the process on the other end of a connection
+struct Peer {
+    uid: u32,
+    // unknown when the process is gone already, or belongs to another user
+    exe: Option<PathBuf>,
+}
+
+impl Peer {
+    fn of(stream: &AsyncUnixStream) -> Result<Self, String> {
+        let cred = stream
+            .peer_cred()
+            .map_err(|e| format!("no peer credentials: {e}"))?;
+        Ok(Self {
+            uid: cred.uid(),
+            exe: cred
+                .pid()
+                .and_then(|pid| std::fs::read_link(format!("/proc/{pid}/exe")).ok()),
+        })
+    }
+}
+
+// only this user may connect, and with an allowlist only the programs on it
+fn check_peer(peer: &Peer, allowed: &[PathBuf]) -> Result<(), String> {
+    if peer.uid != Uid::current().as_raw() {
+        return Err(format!("uid {} is another user", peer.uid));
+    }
+    if allowed.is_empty() {
+        return Ok(());
+    }
+
+    let exe = peer.exe.as_ref().ok_or("the program is unknown")?;
+    if allowed.contains(exe) {
+        Ok(())
+    } else {
+        Err(format!("{} is not in allowed_clients", exe.display()))
+    }
+}
+
+pub fn socket_path() -> PathBuf {
+    runtime_path("sock")
+}
+
+/// Takes the lock that tells whether an instance is running, held until the process exits.
+/// Returns false when another instance holds it, even if that one is still starting up.
+pub fn claim_instance() -> bool {
+    if let Err(e) = prepare_runtime_dir() {
+        events::record(
+            Level::Warning,
+            format!("Failed to prepare the lock file: {e}"),
+        );
+        return true;
+    }
+    match lock(&runtime_path("lock")) {
+        Ok(Some(lock)) => {
+            // the lock goes away with the file descriptor when the process ends, even on a crash
+            std::mem::forget(lock);
+            true
+        }
+        Ok(None) => false,
+        Err(e) => {
+            // without a lock there's no telling, so carry on like before there was one
+            events::record(Level::Warning, format!("Failed to lock the lock file: {e}"));
+            true
+        }
+    }
+}
+
+// nothing when somebody else holds it
+fn lock(path: &Path) -> io::Result<Option<Flock<File>>> {
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .mode(0o600)
+        .open(path)?;
+    match Flock::lock(file, FlockArg::LockExclusiveNonblock) {
+        Ok(lock) => Ok(Some(lock)),
+        Err((_, Errno::EWOULDBLOCK)) => Ok(None),
+        Err((_, e)) => Err(e.into()),
+    }
+}
+
+/// Like [`request`], but gives an instance that is still starting up some time to listen.
+pub fn request_waiting(request: &Request) -> Result<Snapshot, Error> {
+    let deadline = Instant::now() + STARTUP_WAIT;
+    loop {
+        match self::request(request.clone()) {
+            Err(Error::Io(_)) if Instant::now() < deadline => std::thread::sleep(STARTUP_POLL),
+            res => return res,
+        }
+    }
+}
+
+/// Sends a command to the running instance and waits for the state it ends up in.
+pub fn send(cmd: &Command) -> Result<Snapshot, Error> {
+    request(cmd.request())
+}
+
+pub fn request(request: Request) -> Result<Snapshot, Error> {
+    let response = exchange(request)?;
+    match (response.ok, response.state) {
+        (true, Some(state)) => Ok(state),
+        _ => Err(Error::Refused(response.error.unwrap_or_default())),
+    }
+}
+
+/// Asks the running instance for the input sources.
+pub fn list_devices() -> Result<Vec<Device>, Error> {
+    let response = exchange(Request::ListDevices)?;
+    match (response.ok, response.devices) {
+        (true, Some(devices)) => Ok(devices),
+        _ => Err(Error::Refused(response.error.unwrap_or_default())),
+    }
+}
+
+/// Has the running instance bind `action`, or reset it without a binding. Returns every hotkey
+/// after the change.
+pub fn bind(action: HotKeyTarget, binding: Option<String>) -> Result<Vec<BoundHotKey>, Error> {
+    let response = exchange(Request::Bind { action, binding })?;
+    match (response.ok, response.hotkeys) {
+        (true, Some(hotkeys)) => Ok(hotkeys),
+        _ => Err(Error::Refused(response.error.unwrap_or_default())),
+    }
+}
+
+fn exchange(request: Request) -> Result<Response, Error> {
+    let mut stream = UnixStream::connect(socket_path())?;
+    let envelope = Envelope {
+        protocol: PROTOCOL,
+        token: None,
+        request,
+    };
+    stream.write_all((serde_json::to_string(&envelope)? + "\n").as_bytes())?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// Subscribes to the running instance, calling `on_state` with the state and then again with
+/// every change, until the instance goes away.
+pub fn follow(mut on_state: impl FnMut(&Snapshot)) -> Result<(), Error> {
+    let mut stream = UnixStream::connect(socket_path())?;
+    let envelope = Envelope {
+        protocol: PROTOCOL,
+        token: None,
+        request: Request::Subscribe,
+    };
+    stream.write_all((serde_json::to_string(&envelope)? + "\n").as_bytes())?;
+
+    for line in BufReader::new(stream).lines() {
+        let response: Response = serde_json::from_str(&line?)?;
+        match (response.ok, response.state) {
+            (true, Some(state)) => on_state(&state),
+            _ => return Err(Error::Refused(response.error.unwrap_or_default())),
+        }
+    }
+    Ok(())
+}
+
+// a JSON request, or a plain text command from before there was one
+fn parse(line: &str) -> Result<(Request, bool), String> {
+    if !line.trim_start().starts_with('{') {
+        let legacy = Legacy::try_parse_from(line.split_whitespace()).map_err(|e| e.to_string())?;
+        return Ok((legacy.command.request(), true));
+    }
+
+    Ok((parse_json(line)?.request, false))
+}
+
+fn parse_json(line: &str) -> Result<Envelope, String> {
+    let envelope: Envelope = serde_json::from_str(line).map_err(|e| e.to_string())?;
+    if envelope.protocol > PROTOCOL {
+        return Err(format!(
+            "unsupported protocol {}, at most {PROTOCOL} is understood",
+            envelope.protocol
+        ));
+    }
+    Ok(envelope)
+}
+
+/// Parses a JSON request that has to carry `token`. There are no plain text commands here.
+pub fn parse_with_token(line: &str, token: &str) -> Result<Request, String> {
+    let envelope = parse_json(line)?;
+    // compared in full, so that the time taken doesn't tell how much of it was right
+    let given = envelope.token.unwrap_or_default();
+    let matches = given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0;
+    if !matches {
+        return Err("missing or wrong token".into());
+    }
+    Ok(envelope.request)
+}
+
+/// A line answering that a request was refused.
+pub fn refusal(error: &str) -> String {
+    Response::error(error).line()
+}
+
+/// Listens for requests from other processes of this user, one per connection. When
+/// `allowed_clients` isn't empty, only those programs and this one may connect. Only call this
+/// while holding the lock from [`claim_instance`], anything left at the socket's path is from a
+/// crashed instance.
+pub fn listen(mut allowed_clients: Vec<PathBuf>) -> Task<Msg> {
+    if !allowed_clients.is_empty()
+        && let Ok(exe) = std::env::current_exe()
+    {
+        allowed_clients.push(exe);
+    }
+    let allowed_clients = Arc::new(allowed_clients);
+
+    Task::future(async move {
+        prepare_runtime_dir()?;
+        bind_socket(&socket_path())
+    })
+    .then(move |res| {
+        let listener = match res {
+            Ok(listener) => listener,
+            Err(e) => {
+                events::record(Level::Error, format!("Failed to listen for commands: {e}"));
+                // startup is over all the same
+                return Task::done(Msg::ControlSocketReady);
+            }
+        };
+
+        let connections = Task::stream(serve(listener, allowed_clients.clone()));
+        Task::done(Msg::ControlSocketReady).chain(connections)
+    })
+}
+
+// every connection gets a task of its own, so that a slow client holds up nobody else
+fn serve(listener: UnixListener, allowed_clients: Arc<Vec<PathBuf>>) -> impl Stream<Item = Msg> {
+    stream::channel(16, async move |tx| {
+        loop {
+            let Ok((incoming, _)) = listener.accept().await else {
+                // e.g. out of file descriptors, which takes a moment to change
+                tokio::time::sleep(ACCEPT_RETRY).await;
+                continue;
+            };
+            let allowed_clients = allowed_clients.clone();
+            let mut tx = tx.clone();
+            tokio::spawn(async move {
+                if let Some(msg) = accept(incoming, &allowed_clients).await {
+                    let _ = tx.send(msg).await;
+                }
+            });
+        }
+    })
+}
+
+/// Reads the first line of a request, giving up on clients that send too much or take too
+/// long. Whatever arrived by then is returned along with the connection.
+pub async fn read_request<S: AsyncRead + Unpin>(incoming: S) -> (String, S) {
+    let mut reader = AsyncBufReader::new(incoming);
+    let mut line = String::new();
+    let mut limited = (&mut reader).take(MAX_REQUEST);
+    let _ = tokio::time::timeout(READ_TIMEOUT, limited.read_line(&mut line)).await;
+    (line, reader.into_inner())
+}
+
+// anything left at the path is from a crashed instance, the lock tells that none is running
+fn bind_socket(socket_path: &Path) -> io::Result<UnixListener> {
+    let _ = std::fs::remove_file(socket_path);
+    let listener = UnixListener::bind(socket_path)?;
+    // only this user gets to control the mic
+    std::fs::set_permissions(socket_path, Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+// reads the request of a new connection, for the app to answer
+async fn accept(incoming: AsyncUnixStream, allowed_clients: &[PathBuf]) -> Option<Msg> {
+    if let Err(e) = Peer::of(&incoming).and_then(|peer| check_peer(&peer, allowed_clients)) {
+        events::record(
+            Level::Warning,
+            format!("Rejected a connection to the control socket: {e}"),
+        );
+        return None;
+    }
+
+    // plain text commands end with the connection instead of a newline
+    let (line, mut incoming) = read_request(incoming).await;
+
+    let (request, legacy) = match parse(&line) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let _ = incoming.write_all(refusal(&e).as_bytes()).await;
+            return None;
+        }
+    };
+
+    // the answer is written from the app, which doesn't run async code
+    let incoming = incoming.into_std().ok()?;
+    let _ = incoming.set_nonblocking(false);
+    Some(Msg::Command(
+        request,
+        Reply::new(Connection::Unix(incoming), legacy),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::Shutdown, os::unix::net::UnixListener as StdUnixListener, pin::pin};
+
+    use iced::futures::StreamExt;
+
+    use super::*;
+
+    const SNAPSHOT: Snapshot = Snapshot {
+        active: true,
+        muted: true,
+        microphone: None,
+    };
+
+    // a directory of its own for sockets and locks, gone when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("global-ptt-test-{}-{name}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir(&path).expect("temporary directory can be created");
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime can be started")
+    }
+
+    // connects to a listening socket and sends `line`, returning what the listener made of it
+    // and the client's end, for reading the answer
+    fn send_line(dir: &TempDir, allowed: &[PathBuf], line: &str) -> (Option<Msg>, UnixStream) {
+        let path = dir.0.join("control.sock");
+        runtime().block_on(async {
+            let listener = UnixListener::bind(&path).expect("socket can be bound");
+            let mut client = UnixStream::connect(&path).expect("socket accepts connections");
+            client
+                .write_all(line.as_bytes())
+                .expect("request can be sent");
+            // plain text commands end with the connection
+            client
+                .shutdown(Shutdown::Write)
+                .expect("connection can be half closed");
+            let (incoming, _) = listener.accept().await.expect("connection arrives");
+            let msg = accept(incoming, allowed).await;
+            std::fs::remove_file(&path).expect("socket can be removed");
+            (msg, client)
+        })
+    }
+
+    fn read_answer(client: UnixStream) -> String {
+        let mut answer = String::new();
+        BufReader::new(client)
+            .read_line(&mut answer)
+            .expect("answer can be read");
+        answer
+    }
+
+    #[test]
+    fn trigger_commands_arrive_through_the_socket() {
+        let dir = TempDir::new("trigger");
+        for (line, expected, legacy) in [
+            ("trigger-press", Request::TriggerPress, true),
+            ("trigger-release", Request::TriggerRelease, true),
+            ("trigger-tap", Request::TriggerTap(DEFAULT_TAP_MS), true),
+            ("trigger-tap --ms 500", Request::TriggerTap(500), true),
+            (
+                "{\"protocol\":1,\"cmd\":\"trigger_press\"}\n",
+                Request::TriggerPress,
+                false,
+            ),
+            (
+                "{\"protocol\":1,\"cmd\":\"trigger_tap\",\"value\":500}\n",
+                Request::TriggerTap(500),
+                false,
+            ),
+        ] {
+            let (msg, client) = send_line(&dir, &[], line);
+            let Some(Msg::Command(request, reply)) = msg else {
+                panic!("{line} wasn't passed on");
+            };
+            assert_eq!(request, expected);
+            reply.send(&SNAPSHOT);
+            let answer = read_answer(client);
+            if legacy {
+                assert_eq!(answer, "muted");
+            } else {
+                assert_eq!(answer, Response::ok(SNAPSHOT).line());
+            }
+        }
+    }
+
+    #[test]
+    fn every_request_is_answered_with_the_state() {
+        let dir = TempDir::new("requests");
+        for (cmd, request) in [
+            ("\"cmd\":\"show\"", Request::Show),
+            (
+                "\"cmd\":\"set_active\",\"value\":true",
+                Request::SetActive(true),
+            ),
+            ("\"cmd\":\"toggle_active\"", Request::ToggleActive),
+            (
+                "\"cmd\":\"set_muted\",\"value\":false",
+                Request::SetMuted(false),
+            ),
+            ("\"cmd\":\"toggle_mute\"", Request::ToggleMute),
+            (
+                "\"cmd\":\"select_microphone\",\"value\":\"usb\"",
+                Request::SelectMicrophone("usb".into()),
+            ),
+            ("\"cmd\":\"status\"", Request::Status),
+            ("\"cmd\":\"subscribe\"", Request::Subscribe),
+            ("\"cmd\":\"trigger_release\"", Request::TriggerRelease),
+        ] {
+            let line = format!("{{\"protocol\":{PROTOCOL},{cmd}}}\n");
+            let (msg, client) = send_line(&dir, &[], &line);
+            let Some(Msg::Command(parsed, reply)) = msg else {
+                panic!("{line} wasn't passed on");
+            };
+            assert_eq!(parsed, request);
+            reply.send(&SNAPSHOT);
+            let response: Response =
+                serde_json::from_str(&read_answer(client)).expect("answer is JSON");
+            assert!(response.ok);
+            assert_eq!(response.state, Some(SNAPSHOT));
+        }
+    }
+
+    #[test]
+    fn devices_and_hotkeys_come_with_the_state() {
+        let dir = TempDir::new("lists");
+        let devices = vec![Device {
+            name: "usb".into(),
+            description: "USB Mic".into(),
+            is_default: true,
+            selected: false,
+        }];
+        let (Some(Msg::Command(Request::ListDevices, reply)), client) =
+            send_line(&dir, &[], "{\"protocol\":1,\"cmd\":\"list_devices\"}\n")
+        else {
+            panic!("list_devices wasn't passed on");
+        };
+        reply.send_devices(&SNAPSHOT, devices.clone());
+        let response: Response =
+            serde_json::from_str(&read_answer(client)).expect("answer is JSON");
+        assert_eq!(response.state, Some(SNAPSHOT));
+        assert_eq!(response.devices, Some(devices));
+
+        let hotkeys = vec![BoundHotKey {
+            action: HotKeyTarget::Whisper,
+            binding: "KeyW".into(),
+            description: "W".into(),
+        }];
+        let line = concat!(
+            "{\"protocol\":1,\"cmd\":\"bind\",",
+            "\"value\":{\"action\":\"whisper\",\"binding\":\"KeyW\"}}\n"
+        );
+        let (Some(Msg::Command(request, reply)), client) = send_line(&dir, &[], line) else {
+            panic!("bind wasn't passed on");
+        };
+        assert_eq!(
+            request,
+            Request::Bind {
+                action: HotKeyTarget::Whisper,
+                binding: Some("KeyW".into()),
+            }
+        );
+        reply.send_hotkeys(&SNAPSHOT, hotkeys.clone());
+        let response: Response =
+            serde_json::from_str(&read_answer(client)).expect("answer is JSON");
+        assert_eq!(response.state, Some(SNAPSHOT));
+        assert_eq!(response.hotkeys, Some(hotkeys));
+    }
+
+    #[test]
+    fn bad_requests_are_refused_by_the_listener() {
+        let dir = TempDir::new("refused");
+        for line in [
+            "{\"protocol\":1,\"cmd\":\"explode\"}\n",
+            "{\"protocol\":99,\"cmd\":\"status\"}\n",
+            "{\"cmd\":\"status\"}\n",
+            "explode",
+        ] {
+            let (msg, client) = send_line(&dir, &[], line);
+            assert!(msg.is_none(), "{line} was passed on");
+            let response: Response =
+                serde_json::from_str(&read_answer(client)).expect("answer is JSON");
+            assert!(!response.ok);
+            assert!(response.error.is_some());
+        }
+    }
+
+    #[test]
+    fn remote_requests_need_the_exact_token() {
+        let token = "s3cret";
+        for line in [
+            r#"{"protocol":1,"cmd":"status"}"#,
+            r#"{"protocol":1,"cmd":"status","token":"s3creT"}"#,
+            r#"{"protocol":1,"cmd":"status","token":"s3cret!"}"#,
+            r#"{"protocol":1,"cmd":"status","token":""}"#,
+        ] {
+            assert_eq!(
+                parse_with_token(line, token),
+                Err("missing or wrong token".into()),
+                "{line} got in"
+            );
+        }
+        assert_eq!(
+            parse_with_token(r#"{"protocol":1,"cmd":"status","token":"s3cret"}"#, token),
+            Ok(Request::Status)
+        );
+    }
+
+    #[test]
+    fn a_silent_client_holds_up_nobody_else() {
+        let dir = TempDir::new("silent");
+        let path = dir.0.join("control.sock");
+        runtime().block_on(async {
+            let listener = UnixListener::bind(&path).expect("socket can be bound");
+            let mut connections = pin!(serve(listener, Arc::default()));
+            // connects and never sends anything
+            let _silent = UnixStream::connect(&path).expect("socket accepts connections");
+            let mut client = UnixStream::connect(&path).expect("socket accepts connections");
+            client
+                .write_all(b"{\"protocol\":1,\"cmd\":\"status\"}\n")
+                .expect("request can be sent");
+
+            let msg = tokio::time::timeout(READ_TIMEOUT / 5, connections.next())
+                .await
+                .expect("answered before the silent client gives up");
+            assert!(matches!(msg, Some(Msg::Command(Request::Status, _))));
+        });
+    }
+
+    #[test]
+    fn the_legacy_open_still_shows_the_window() {
+        let dir = TempDir::new("legacy");
+        let (Some(Msg::Command(request, reply)), client) = send_line(&dir, &[], "open") else {
+            panic!("open wasn't passed on");
+        };
+        assert_eq!(request, Request::Show);
+        reply.send_error("no window");
+        assert_eq!(read_answer(client), "error: no window");
+    }
+
+    #[test]
+    fn sockets_of_crashed_instances_are_replaced() {
+        let dir = TempDir::new("stale");
+        let path = dir.0.join("control.sock");
+        // the file stays behind when its listener goes away without cleaning up
+        drop(StdUnixListener::bind(&path).expect("socket can be bound"));
+        assert!(path.exists());
+        assert!(UnixStream::connect(&path).is_err());
+
+        runtime().block_on(async {
+            let _listener = bind_socket(&path).expect("stale socket is replaced");
+            UnixStream::connect(&path).expect("new socket accepts connections");
+        });
+        let mode = std::fs::metadata(&path).expect("socket is there").mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn only_one_instance_gets_the_lock() {
+        let dir = TempDir::new("lock");
+        let path = dir.0.join("instance.lock");
+        let first = lock(&path).expect("lock file can be opened");
+        assert!(first.is_some());
+        // another process starting at the same time opens it again, like this
+        assert!(lock(&path).expect("lock file can be opened").is_none());
+        // and gets it once the first one is gone, leaving its lock file behind
+        drop(first);
+        assert!(path.exists());
+        assert!(lock(&path).expect("lock file can be opened").is_some());
+    }
+
+    #[test]
+    fn only_this_user_and_allowed_programs_get_in() {
+        let uid = Uid::current().as_raw();
+        let script = PathBuf::from("/usr/bin/ptt-script");
+        let other = PathBuf::from("/usr/bin/other");
+        let peer = |uid, exe: Option<&PathBuf>| Peer {
+            uid,
+            exe: exe.cloned(),
+        };
+        let allowed = std::slice::from_ref(&script);
+        for (peer, allowed, ok) in [
+            (peer(uid, Some(&other)), &[][..], true),
+            (peer(uid, None), &[][..], true),
+            (peer(uid + 1, Some(&script)), &[][..], false),
+            (peer(uid, Some(&script)), allowed, true),
+            (peer(uid, Some(&other)), allowed, false),
+            (peer(uid, None), allowed, false),
+            (peer(uid + 1, Some(&script)), allowed, false),
+        ] {
+            assert_eq!(
+                check_peer(&peer, allowed).is_ok(),
+                ok,
+                "uid {} running {:?}",
+                peer.uid,
+                peer.exe
+            );
+        }
+    }
+
+    #[test]
+    fn connections_are_checked_against_the_allowlist() {
+        let dir = TempDir::new("allowlist");
+        let this = std::env::current_exe().expect("test binary is known");
+        let (msg, client) = send_line(&dir, &[this], "status");
+        assert!(matches!(msg, Some(Msg::Command(Request::Status, _))));
+        drop(client);
+
+        // closed without an answer
+        let (msg, client) = send_line(&dir, &[PathBuf::from("/usr/bin/other")], "status");
+        assert!(msg.is_none());
+        let mut answer = String::new();
+        let _ = BufReader::new(client).read_line(&mut answer);
+        assert_eq!(answer, "");
+    }
+
+    #[test]
+    fn the_socket_goes_where_the_host_can_reach_it() {
+        let runtime_dir = || Some(PathBuf::from("/run/user/1000"));
+        let id = "com.github.Adamskye.GlobalPushToTalk";
+        for (xdg_runtime_dir, flatpak_id, expected) in [
+            (runtime_dir(), None, PathBuf::from("/run/user/1000")),
+            (
+                runtime_dir(),
+                Some(id),
+                PathBuf::from("/run/user/1000/app/com.github.Adamskye.GlobalPushToTalk"),
+            ),
+            (
+                None,
+                None,
+                PathBuf::from(format!("/tmp/{APP_ID}.{}", Uid::current())),
+            ),
+            (
+                None,
+                Some(id),
+                PathBuf::from(format!("/tmp/{APP_ID}.{}", Uid::current())),
+            ),
+        ] {
+            assert_eq!(select_runtime_dir(xdg_runtime_dir, flatpak_id), expected);
+        }
+    }
+}
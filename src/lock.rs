@@ -0,0 +1,68 @@
+use std::pin::pin;
+
+use iced::{
+    futures::{SinkExt, Stream, StreamExt, channel::mpsc::Sender, stream::select},
+    stream,
+};
+use nix::unistd::Uid;
+use zbus::{Connection, Proxy, zvariant::OwnedObjectPath};
+
+use crate::{
+    app::Msg,
+    events::{self, Level},
+};
+
+const LOGIND: &str = "org.freedesktop.login1";
+const MANAGER_PATH: &str = "/org/freedesktop/login1";
+const MANAGER_INTERFACE: &str = "org.freedesktop.login1.Manager";
+const USER_INTERFACE: &str = "org.freedesktop.login1.User";
+const SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+
+/// Follows whether the login session is locked. The Lock signal asks the screen locker to lock,
+/// and its `LockedHint` says that it did, so whichever comes first counts.
+pub fn watch() -> impl Stream<Item = Msg> {
+    stream::channel(4, async move |mut tx| {
+        if let Err(e) = follow(&mut tx).await {
+            events::record(
+                Level::Warning,
+                format!("Screen locks can't be followed through logind: {e}"),
+            );
+        }
+    })
+}
+
+async fn follow(tx: &mut Sender<Msg>) -> zbus::Result<()> {
+    let bus = Connection::system().await?;
+    let path = session_path(&bus).await?;
+    let session = Proxy::new(&bus, LOGIND, path, SESSION_INTERFACE).await?;
+
+    let hint = session
+        .receive_property_changed::<bool>("LockedHint")
+        .await
+        .filter_map(async |change| change.get().await.ok());
+    let lock = session.receive_signal("Lock").await?.map(|_| true);
+    let unlock = session.receive_signal("Unlock").await?.map(|_| false);
+    let mut changes = pin!(select(hint, select(lock, unlock)));
+
+    // started from behind the lock screen
+    if session.get_property::<bool>("LockedHint").await? {
+        let _ = tx.send(Msg::SessionLocked(true)).await;
+    }
+    while let Some(locked) = changes.next().await {
+        let _ = tx.send(Msg::SessionLocked(locked)).await;
+    }
+    Ok(())
+}
+
+// the session this runs in, or when started outside of one, e.g. as a systemd user service,
+// the one showing the user's desktop
+async fn session_path(bus: &Connection) -> zbus::Result<OwnedObjectPath> {
+    let manager = Proxy::new(bus, LOGIND, MANAGER_PATH, MANAGER_INTERFACE).await?;
+    if let Ok(path) = manager.call("GetSession", &("auto",)).await {
+        return Ok(path);
+    }
+    let user: OwnedObjectPath = manager.call("GetUser", &(Uid::current().as_raw(),)).await?;
+    let user = Proxy::new(bus, LOGIND, user, USER_INTERFACE).await?;
+    let (_id, path): (String, OwnedObjectPath) = user.get_property("Display").await?;
+    Ok(path)
+}
@@ -1,26 +1,327 @@
 #![deny(clippy::unwrap_used)]
 #![warn(clippy::pedantic)]
 
+mod activity;
+mod announce;
 mod app;
+mod apps;
+mod autostart;
 mod config;
+mod crash;
+mod dbus;
+mod doctor;
+mod events;
+mod flatpak;
+mod headset;
+mod hooks;
 mod hotkey;
+mod i18n;
+mod input;
+mod instance;
+mod ipc;
+mod lock;
+mod mpris;
+mod mqtt;
+mod obs;
+mod osd;
+mod overlay;
 mod pulse;
+mod remote;
+mod sounds;
+mod stats;
+mod suspend;
+mod systemd;
 mod tray;
+mod typing;
+mod updates;
+
+use std::{path::PathBuf, process::exit};
+
+use clap::Parser;
 
 use iced_fonts::LUCIDE_FONT_BYTES;
 
-use crate::app::App;
+use crate::{
+    app::{App, Startup},
+    config::Config,
+    hotkey::Binding,
+    ipc::{Command, Format, HotKeyTarget, MatchError, MicCommand, Request},
+};
 
 const APP_ID: &str = "com.github.Adamskye.GlobalPushToTalk";
 
 const PADDING: f32 = 12.0;
 const SPACING: f32 = 8.0;
 
+/// Push-to-talk for any microphone and application. Without a command, starts the app, or shows
+/// the window of the one already running.
+#[derive(Parser)]
+#[command(version)]
+#[allow(clippy::struct_excessive_bools)]
+struct Cli {
+    /// Start without showing the window
+    #[arg(long)]
+    hidden: bool,
+    /// Turn push-to-talk on
+    #[arg(long)]
+    active: bool,
+    /// Use this microphone, given by its source name or part of its name or description
+    #[arg(long, value_name = "NAME")]
+    mic: Option<String>,
+    /// Print the pactl commands for every change to the audio setup instead of making it
+    #[arg(long)]
+    dry_run: bool,
+    /// Run another instance next to the default one, with its own config and virtual
+    /// microphone. Commands given with it go to that instance
+    #[arg(long, value_name = "NAME", value_parser = instance::parse_id)]
+    instance_id: Option<String>,
+    /// Use this config file instead of the default one
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Write a config file with every setting at its default value and exit
+    #[arg(long)]
+    write_default_config: bool,
+    /// Print a systemd user unit that starts the app with the graphical session and exit
+    #[arg(long)]
+    print_systemd_unit: bool,
+    /// A command for the running instance
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
 fn main() -> iced::Result {
-    iced::daemon(App::new, App::update, App::view)
+    let cli = Cli::parse();
+    // these also reach an instance that's already running
+    let startup = Startup {
+        hidden: cli.hidden,
+        active: cli.active,
+        mic: cli.mic,
+        dry_run: cli.dry_run,
+    };
+    if let Some(id) = cli.instance_id {
+        instance::set_id(id);
+    }
+    if let Some(path) = cli.config {
+        Config::set_path(path);
+    }
+
+    if cli.write_default_config {
+        match config::write_default() {
+            Ok(path) => {
+                println!("Wrote default config to {}", path.display());
+                exit(0);
+            }
+            Err(e) => {
+                eprintln!("Failed to write default config: {e}");
+                exit(1);
+            }
+        }
+    }
+
+    if cli.print_systemd_unit {
+        print!("{}", systemd::unit());
+        exit(0);
+    }
+
+    if let Some(cmd) = cli.command {
+        exit(run_command(&cmd));
+    }
+
+    // the tray and the window are both translated, so the language has to be known up front
+    i18n::init(Config::load().unwrap_or_default().language());
+    crash::install();
+
+    iced::daemon(move || App::new(&startup), App::update, App::view)
         .subscription(App::subscription)
         .theme(App::theme)
-        .title("Global Push-to-Talk")
+        .scale_factor(App::scale_factor)
+        .title(App::title)
         .font(LUCIDE_FONT_BYTES)
         .run()
 }
+
+// sends `cmd` to the running instance and prints the state it ends up in, returning the exit
+// code. only status tells the states apart by exit code, 3 means nothing answered
+fn run_command(cmd: &Command) -> i32 {
+    match cmd {
+        Command::ListDevices { json }
+        | Command::Mic {
+            action: MicCommand::List { json },
+        } => return list_devices(*json),
+        Command::Mic {
+            action: MicCommand::Set { query },
+        } => return set_mic(query),
+        Command::Mic {
+            action: MicCommand::Get,
+        } => return get_mic(),
+        Command::Doctor => return doctor::run(),
+        Command::Bind {
+            action, binding, ..
+        } => return bind(*action, binding.clone()),
+        _ => {}
+    }
+    if let Command::Status {
+        follow: true,
+        format,
+    } = *cmd
+    {
+        return match ipc::follow(|state| println!("{}", state.format(format))) {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("Failed to reach running instance: {e}");
+                3
+            }
+        };
+    }
+
+    let state = match ipc::send(cmd) {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("Failed to reach running instance: {e}");
+            return 3;
+        }
+    };
+
+    if let Command::Status { format, .. } = *cmd {
+        println!("{}", state.format(format));
+        if let (Format::Plain, Some(mic)) = (format, &state.microphone) {
+            println!("Microphone: {mic}");
+        }
+        state.exit_code()
+    } else {
+        println!("{}", state.description());
+        if let Some(mic) = &state.microphone {
+            println!("Microphone: {mic}");
+        }
+        0
+    }
+}
+
+// asks the running instance, or else the audio server directly
+fn list_devices(json: bool) -> i32 {
+    let devices = match ipc::list_devices() {
+        Ok(devices) => devices,
+        Err(ipc::Error::Io(_)) => match pulse::PulseAudioState::init() {
+            Ok(pa_state) => pa_state
+                .get_input_devices()
+                .into_iter()
+                .map(|dev| ipc::Device {
+                    name: dev.name,
+                    description: dev.description,
+                    is_default: dev.is_default,
+                    selected: false,
+                })
+                .collect(),
+            Err(e) => {
+                eprintln!("Failed to connect to the audio server: {e}");
+                return 1;
+            }
+        },
+        Err(e) => {
+            eprintln!("Failed to list devices: {e}");
+            return 1;
+        }
+    };
+
+    if json {
+        println!("{}", serde_json::to_string(&devices).unwrap_or_default());
+        return 0;
+    }
+    for dev in devices {
+        let flags: Vec<_> = [(dev.is_default, "default"), (dev.selected, "selected")]
+            .into_iter()
+            .filter_map(|(set, flag)| set.then_some(flag))
+            .collect();
+        println!("{}", dev.name);
+        if flags.is_empty() {
+            println!("    {}", dev.description);
+        } else {
+            println!("    {} ({})", dev.description, flags.join(", "));
+        }
+    }
+    0
+}
+
+// resolves `query` here, so that ambiguity can be reported, then has the instance switch
+fn set_mic(query: &str) -> i32 {
+    let devices = match ipc::list_devices() {
+        Ok(devices) => devices,
+        Err(e) => {
+            eprintln!("Failed to reach running instance: {e}");
+            return 3;
+        }
+    };
+    let dev = match ipc::find_microphone(&devices, query) {
+        Ok(dev) => dev,
+        Err(e) => {
+            eprintln!("{e}");
+            return match e {
+                MatchError::NoMatch(_) => 4,
+                MatchError::Ambiguous(..) => 5,
+            };
+        }
+    };
+
+    match ipc::request(Request::SelectMicrophone(dev.name.clone())) {
+        Ok(_) => {
+            println!("Using {}", dev.description);
+            0
+        }
+        Err(e) => {
+            eprintln!("Failed to reach running instance: {e}");
+            3
+        }
+    }
+}
+
+// checked here first, so that a typo doesn't need a running instance to be reported
+fn bind(action: HotKeyTarget, binding: Option<String>) -> i32 {
+    if let Some(binding) = &binding
+        && let Err(e) = binding.parse::<Binding>()
+    {
+        eprintln!("Invalid hotkey \"{binding}\": {e}");
+        return 1;
+    }
+
+    match ipc::bind(action, binding) {
+        Ok(hotkeys) => {
+            for hk in hotkeys {
+                // unbound actions have no description
+                let shown = if hk.description.is_empty() {
+                    &hk.binding
+                } else {
+                    &hk.description
+                };
+                println!("{}: {shown}", hk.action.name());
+            }
+            0
+        }
+        Err(e @ ipc::Error::Refused(_)) => {
+            eprintln!("{e}");
+            1
+        }
+        Err(e) => {
+            eprintln!("Failed to reach running instance: {e}");
+            3
+        }
+    }
+}
+
+fn get_mic() -> i32 {
+    match ipc::request(Request::Status) {
+        Ok(state) => {
+            println!(
+                "{}",
+                state
+                    .microphone
+                    .as_deref()
+                    .unwrap_or("No microphone selected")
+            );
+            0
+        }
+        Err(e) => {
+            eprintln!("Failed to reach running instance: {e}");
+            3
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+use tokio::sync::watch;
+use zbus::{Connection, fdo::DBusProxy};
+
+use crate::{
+    events::{self, Level},
+    ipc::Snapshot,
+};
+
+const NAME_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const PATH: &str = "/org/mpris/MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// Which players to pause, and when.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    // the part of the bus name after the prefix, e.g. "spotify". every player when empty
+    pub players: Vec<String>,
+    // opening the mic for less than this leaves the players alone
+    pub min_open: Duration,
+}
+
+impl Settings {
+    fn wants(&self, player: &str) -> bool {
+        self.players.is_empty()
+            || self
+                .players
+                .iter()
+                .any(|wanted| player == wanted || player.starts_with(&format!("{wanted}.")))
+    }
+}
+
+fn transmitting(state: &Snapshot) -> bool {
+    state.active && !state.muted
+}
+
+/// Pauses the media players that are playing once the microphone has been open for a while,
+/// and plays the same ones again when it closes. Players paused by hand are left alone.
+pub async fn pause_while_transmitting(settings: Settings, mut state: watch::Receiver<Snapshot>) {
+    let bus = match Connection::session().await {
+        Ok(bus) => bus,
+        Err(e) => {
+            events::record(
+                Level::Warning,
+                format!("Media players can't be paused without the session bus: {e}"),
+            );
+            return;
+        }
+    };
+
+    loop {
+        if state.wait_for(transmitting).await.is_err() {
+            return;
+        }
+        // quick taps would make the music stutter
+        let released = tokio::time::timeout(
+            settings.min_open,
+            state.wait_for(|state| !transmitting(state)),
+        )
+        .await
+        .map(|res| res.is_ok());
+        match released {
+            Ok(true) => continue,
+            Ok(false) => return,
+            // still open
+            Err(_) => {}
+        }
+
+        let paused = pause(&bus, &settings).await;
+        let closed = state.wait_for(|state| !transmitting(state)).await.is_ok();
+        play(&bus, &paused).await;
+        if !closed {
+            return;
+        }
+    }
+}
+
+// returns the players that were playing until now
+async fn pause(bus: &Connection, settings: &Settings) -> Vec<String> {
+    let names = match DBusProxy::new(bus).await {
+        Ok(dbus) => dbus.list_names().await.unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+    let mut paused = Vec::new();
+    for name in &names {
+        let name = name.as_str();
+        let Some(player) = name.strip_prefix(NAME_PREFIX) else {
+            continue;
+        };
+        if !settings.wants(player) {
+            continue;
+        }
+
+        let res = async {
+            let proxy = zbus::Proxy::new(bus, name, PATH, PLAYER_INTERFACE).await?;
+            if proxy.get_property::<String>("PlaybackStatus").await? != "Playing" {
+                return Ok(false);
+            }
+            proxy.call_method("Pause", &()).await?;
+            Ok::<_, zbus::Error>(true)
+        };
+        match res.await {
+            Ok(true) => paused.push(name.to_string()),
+            Ok(false) => {}
+            Err(e) => events::record(Level::Info, format!("Failed to pause {player}: {e}")),
+        }
+    }
+    paused
+}
+
+async fn play(bus: &Connection, players: &[String]) {
+    for name in players {
+        let res = async {
+            zbus::Proxy::new(bus, name.as_str(), PATH, PLAYER_INTERFACE)
+                .await?
+                .call_method("Play", &())
+                .await
+        };
+        // a player that was closed in the meantime is no reason to complain
+        if let Err(e) = res.await {
+            events::record(Level::Info, format!("Failed to resume {name}: {e}"));
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use std::{fmt::Display, time::Duration};
+
+use iced::{
+    futures::{SinkExt, Stream, StreamExt, channel::mpsc::Sender},
+    stream,
+};
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Packet, QoS};
+use serde_json::{Value, json};
+use tokio::sync::watch;
+use tokio_stream::wrappers::WatchStream;
+
+use crate::{
+    app::Msg,
+    events::{self, Level},
+    i18n::tr,
+    instance,
+    ipc::{Request, Snapshot},
+};
+
+const DEFAULT_PORT: u16 = 1883;
+// where Home Assistant looks for entities, unless changed there
+const DISCOVERY_PREFIX: &str = "homeassistant";
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+// requests waiting for the connection, the state is sent again in full after connecting anyway
+const CAPACITY: usize = 16;
+// waits between attempts to reach the broker, doubling up to the longest
+const RETRY_FIRST: Duration = Duration::from_secs(2);
+const RETRY_LONGEST: Duration = Duration::from_mins(1);
+
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error("the broker URL must look like mqtt://host:port")]
+    Url,
+}
+
+/// The connection to the broker, for the settings page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Connecting,
+    Connected,
+    // why the last attempt failed, while waiting to try again
+    Disconnected(String),
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&match self {
+            Self::Connecting => tr!("mqtt-connecting"),
+            Self::Connected => tr!("mqtt-connected"),
+            Self::Disconnected(error) => tr!("mqtt-disconnected", error = error.as_str()),
+        })
+    }
+}
+
+/// Where the broker is and what to publish there.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub broker: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub prefix: String,
+    // whether `<prefix>/set` is listened to
+    pub commands: bool,
+    pub discovery: bool,
+}
+
+impl Settings {
+    fn topic(&self, name: &str) -> String {
+        format!("{}/{name}", self.prefix)
+    }
+}
+
+// host and port from the broker URL in the config file
+fn parse_broker(url: &str) -> Result<(String, u16), Error> {
+    let address = url
+        .strip_prefix("mqtt://")
+        .unwrap_or(url)
+        .trim_end_matches('/');
+    let (host, port) = match address.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(|_| Error::Url)?),
+        None => (address, DEFAULT_PORT),
+    };
+    if host.is_empty() || host.contains(['/', '?', '@']) {
+        return Err(Error::Url);
+    }
+    Ok((host.to_string(), port))
+}
+
+/// Publishes `state` as retained messages under the topic prefix whenever it changes, and turns
+/// messages on `<prefix>/set` into requests when commands are allowed. Connects again whenever
+/// the broker goes away, nothing here can hold up the app.
+pub fn sync(settings: Settings, state: watch::Receiver<Snapshot>) -> impl Stream<Item = Msg> {
+    stream::channel(10, async move |mut tx| {
+        let (host, port) = match parse_broker(&settings.broker) {
+            Ok(address) => address,
+            Err(e) => {
+                events::record(Level::Error, format!("MQTT is unavailable: {e}"));
+                let _ = tx
+                    .send(Msg::MqttStatus(Status::Disconnected(e.to_string())))
+                    .await;
+                return;
+            }
+        };
+        let node = node_id();
+        let mut options = MqttOptions::new(&node, &host, port);
+        options.set_keep_alive(KEEP_ALIVE);
+        // the broker says so itself when the app goes away without a word
+        options.set_last_will(LastWill::new(
+            settings.topic("status"),
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
+        if let Some(username) = &settings.username {
+            options.set_credentials(username, settings.password.as_deref().unwrap_or_default());
+        }
+        let (client, mut connection) = AsyncClient::new(options, CAPACITY);
+
+        // the connection is only driven below, so changes are queued from a task of their own
+        let publisher = tokio::spawn(publish_changes(
+            client.clone(),
+            settings.clone(),
+            state.clone(),
+        ));
+        let _publisher = AbortOnDrop(publisher);
+
+        let mut retry = RETRY_FIRST;
+        let mut last_error = None;
+        let _ = tx.send(Msg::MqttStatus(Status::Connecting)).await;
+        loop {
+            match connection.poll().await {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    events::record(
+                        Level::Info,
+                        format!("Connected to the MQTT broker at {host}:{port}"),
+                    );
+                    retry = RETRY_FIRST;
+                    last_error = None;
+                    connected(&client, &settings, &node, &state.borrow());
+                    let _ = tx.send(Msg::MqttStatus(Status::Connected)).await;
+                }
+                Ok(Event::Incoming(Packet::Publish(publish))) => {
+                    if settings.commands && publish.topic == settings.topic("set") {
+                        command(&publish.payload, &mut tx).await;
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    let error = e.to_string();
+                    // the broker not running is recorded once, not on every attempt
+                    if last_error.as_ref() != Some(&error) {
+                        events::record(Level::Warning, format!("No connection to MQTT: {error}"));
+                    }
+                    last_error = Some(error.clone());
+                    let _ = tx.send(Msg::MqttStatus(Status::Disconnected(error))).await;
+
+                    // polling again connects again
+                    tokio::time::sleep(retry).await;
+                    retry = (retry * 2).min(RETRY_LONGEST);
+                    let _ = tx.send(Msg::MqttStatus(Status::Connecting)).await;
+                }
+            }
+        }
+    })
+}
+
+struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+// every change after the first, which is sent on connecting
+async fn publish_changes(
+    client: AsyncClient,
+    settings: Settings,
+    state: watch::Receiver<Snapshot>,
+) {
+    let mut changes = WatchStream::from_changes(state);
+    while let Some(state) = changes.next().await {
+        for (topic, payload) in state_messages(&settings, &state) {
+            let _ = client.publish(topic, QoS::AtLeastOnce, true, payload).await;
+        }
+    }
+}
+
+// everything a fresh broker, or one that lost its retained messages, needs
+fn connected(client: &AsyncClient, settings: &Settings, node: &str, state: &Snapshot) {
+    let mut messages = vec![(settings.topic("status"), "online".to_string())];
+    if settings.discovery {
+        messages.extend(discovery(settings, node));
+    }
+    messages.extend(state_messages(settings, state));
+    for (topic, payload) in messages {
+        // failing only when the queue is full, and the next connection sends all of it again
+        let _ = client.try_publish(topic, QoS::AtLeastOnce, true, payload);
+    }
+    if settings.commands {
+        let _ = client.try_subscribe(settings.topic("set"), QoS::AtLeastOnce);
+    }
+}
+
+fn state_messages(settings: &Settings, state: &Snapshot) -> [(String, String); 3] {
+    [
+        (settings.topic("active"), state.active.to_string()),
+        (settings.topic("muted"), state.muted.to_string()),
+        (
+            settings.topic("microphone"),
+            state.microphone.clone().unwrap_or_default(),
+        ),
+    ]
+}
+
+// the payloads accepted on `<prefix>/set`
+fn parse_command(payload: &str) -> Option<Request> {
+    Some(match payload.trim() {
+        "enable" => Request::SetActive(true),
+        "disable" => Request::SetActive(false),
+        "toggle" => Request::ToggleActive,
+        "mute" => Request::SetMuted(true),
+        "unmute" => Request::SetMuted(false),
+        "toggle_mute" => Request::ToggleMute,
+        _ => return None,
+    })
+}
+
+async fn command(payload: &[u8], tx: &mut Sender<Msg>) {
+    let payload = String::from_utf8_lossy(payload);
+    match parse_command(&payload) {
+        Some(request) => {
+            let _ = tx.send(Msg::Request(request)).await;
+        }
+        None => events::record(
+            Level::Warning,
+            format!("Ignored an unknown MQTT command: {payload}"),
+        ),
+    }
+}
+
+// Home Assistant entities for the state topics, as switches when commands are allowed. an empty
+// payload removes the kind of entity that isn't used, e.g. after turning commands off
+fn discovery(settings: &Settings, node: &str) -> Vec<(String, String)> {
+    let device = json!({
+        "identifiers": [node],
+        "name": instance::suffixed("Global Push-to-Talk", " "),
+        "manufacturer": "global-ptt",
+        "sw_version": env!("CARGO_PKG_VERSION"),
+    });
+    let config = |name: &str, topic: &str, entity: Value| {
+        let mut config = json!({
+            "name": name,
+            "unique_id": format!("{node}_{topic}"),
+            "object_id": format!("{node}_{topic}"),
+            "state_topic": settings.topic(topic),
+            "availability_topic": settings.topic("status"),
+            "device": device,
+        });
+        if let (Some(config), Value::Object(entity)) = (config.as_object_mut(), entity) {
+            config.extend(entity);
+        }
+        config.to_string()
+    };
+    let topic = |component: &str, object: &str| {
+        format!("{DISCOVERY_PREFIX}/{component}/{node}/{object}/config")
+    };
+
+    let mut messages = Vec::new();
+    for (object, name, on, off, icon) in [
+        (
+            "active",
+            "Push-to-talk",
+            "enable",
+            "disable",
+            "mdi:microphone-settings",
+        ),
+        (
+            "muted",
+            "Microphone muted",
+            "mute",
+            "unmute",
+            "mdi:microphone-off",
+        ),
+    ] {
+        let (used, unused) = if settings.commands {
+            ("switch", "binary_sensor")
+        } else {
+            ("binary_sensor", "switch")
+        };
+        let mut entity = json!({ "icon": icon });
+        if settings.commands {
+            entity["command_topic"] = settings.topic("set").into();
+            entity["payload_on"] = on.into();
+            entity["payload_off"] = off.into();
+            entity["state_on"] = "true".into();
+            entity["state_off"] = "false".into();
+        } else {
+            entity["payload_on"] = "true".into();
+            entity["payload_off"] = "false".into();
+        }
+        messages.push((topic(used, object), config(name, object, entity)));
+        messages.push((topic(unused, object), String::new()));
+    }
+    messages.push((
+        topic("sensor", "microphone"),
+        config(
+            "Microphone",
+            "microphone",
+            json!({ "icon": "mdi:microphone" }),
+        ),
+    ));
+    messages
+}
+
+// the same on every start, so that Home Assistant keeps the entities. entities of several
+// machines or instances on one broker mustn't clash
+fn node_id() -> String {
+    let host = nix::unistd::gethostname()
+        .map(|host| host.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let node = instance::suffixed(&format!("global_ptt_{host}"), "_");
+    node.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
@@ -0,0 +1,222 @@
+use std::{fmt::Display, time::Duration};
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use iced::{
+    futures::{SinkExt, Stream, StreamExt, channel::mpsc::Sender, stream::select},
+    stream,
+};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tokio::sync::watch;
+use tokio_stream::wrappers::WatchStream;
+use tokio_tungstenite::tungstenite::{self, Message};
+
+use crate::{
+    app::Msg,
+    events::{self, Level},
+    i18n::tr,
+    ipc::Snapshot,
+};
+
+// obs-websocket 5
+const RPC_VERSION: u32 = 1;
+const OP_HELLO: u64 = 0;
+const OP_IDENTIFY: u64 = 1;
+const OP_IDENTIFIED: u64 = 2;
+const OP_REQUEST: u64 = 6;
+const OP_REQUEST_RESPONSE: u64 = 7;
+// waits between attempts to reach OBS, doubling up to the longest
+const RETRY_FIRST: Duration = Duration::from_secs(2);
+const RETRY_LONGEST: Duration = Duration::from_mins(1);
+
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error("{0}")]
+    WebSocket(#[from] tungstenite::Error),
+    #[error("OBS closed the connection")]
+    Closed,
+    // with the reason OBS gave, e.g. that authentication failed
+    #[error("OBS closed the connection: {0}")]
+    Refused(String),
+    #[error("unexpected message from OBS")]
+    Protocol,
+    #[error("OBS asks for a password, set obs_password in the config file")]
+    NoPassword,
+}
+
+/// The connection to OBS, for the settings page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Connecting,
+    Connected,
+    // why the last attempt failed, while waiting to try again
+    Disconnected(String),
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&match self {
+            Self::Connecting => tr!("obs-connecting"),
+            Self::Connected => tr!("obs-connected"),
+            Self::Disconnected(error) => tr!("obs-disconnected", error = error.as_str()),
+        })
+    }
+}
+
+/// Where to find OBS and what to mute there.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub host: String,
+    pub port: u16,
+    pub password: Option<String>,
+    pub input: String,
+}
+
+/// Keeps `input` in OBS muted whenever `state` is, connecting again whenever OBS goes away.
+/// Only the status comes back, nothing here can hold up the app.
+pub fn sync(settings: Settings, state: watch::Receiver<Snapshot>) -> impl Stream<Item = Msg> {
+    stream::channel(10, async move |mut tx| {
+        let mut retry = RETRY_FIRST;
+        let mut last_error = None;
+        loop {
+            let _ = tx.send(Msg::ObsStatus(Status::Connecting)).await;
+            let mut connected = false;
+            let error = match session(&settings, state.clone(), &mut tx, &mut connected).await {
+                Ok(()) => Error::Closed.to_string(),
+                Err(e) => e.to_string(),
+            };
+            if connected {
+                retry = RETRY_FIRST;
+            }
+            // OBS not running is recorded once, not on every attempt
+            if connected || last_error.as_ref() != Some(&error) {
+                events::record(Level::Warning, format!("No connection to OBS: {error}"));
+            }
+            last_error = Some(error.clone());
+            let _ = tx.send(Msg::ObsStatus(Status::Disconnected(error))).await;
+
+            tokio::time::sleep(retry).await;
+            retry = (retry * 2).min(RETRY_LONGEST);
+        }
+    })
+}
+
+enum Event {
+    Obs(Result<Message, tungstenite::Error>),
+    Muted(bool),
+}
+
+// one connection, from logging in until it ends
+async fn session(
+    settings: &Settings,
+    state: watch::Receiver<Snapshot>,
+    tx: &mut Sender<Msg>,
+    connected: &mut bool,
+) -> Result<(), Error> {
+    let url = format!("ws://{}:{}", settings.host, settings.port);
+    let (ws, _) = tokio_tungstenite::connect_async(url).await?;
+    let (mut sink, mut stream) = ws.split();
+
+    let hello = receive(&mut stream, OP_HELLO).await?;
+    let mut identify = json!({ "rpcVersion": RPC_VERSION, "eventSubscriptions": 0 });
+    if let Some(auth) = hello.get("authentication") {
+        let password = settings.password.as_deref().ok_or(Error::NoPassword)?;
+        let (Some(salt), Some(challenge)) = (
+            auth.get("salt").and_then(Value::as_str),
+            auth.get("challenge").and_then(Value::as_str),
+        ) else {
+            return Err(Error::Protocol);
+        };
+        identify["authentication"] = authentication(password, salt, challenge).into();
+    }
+    sink.send(message(OP_IDENTIFY, &identify)).await?;
+    receive(&mut stream, OP_IDENTIFIED).await?;
+
+    events::record(
+        Level::Info,
+        format!("Connected to OBS at {}:{}", settings.host, settings.port),
+    );
+    *connected = true;
+    let _ = tx.send(Msg::ObsStatus(Status::Connected)).await;
+
+    // the current state comes first, then every change
+    let events = select(
+        stream.map(Event::Obs),
+        WatchStream::new(state).map(|state| Event::Muted(state.muted)),
+    );
+    let mut events = std::pin::pin!(events);
+    let mut request_id = 0_u64;
+    let mut sent = None;
+    while let Some(event) = events.next().await {
+        match event {
+            Event::Muted(muted) if sent != Some(muted) => {
+                request_id += 1;
+                let request = json!({
+                    "requestType": "SetInputMute",
+                    "requestId": request_id.to_string(),
+                    "requestData": { "inputName": settings.input, "inputMuted": muted },
+                });
+                sink.send(message(OP_REQUEST, &request)).await?;
+                sent = Some(muted);
+            }
+            Event::Muted(_) => {}
+            Event::Obs(msg) => match msg? {
+                Message::Text(text) => report_failure(text.as_str()),
+                Message::Close(_) => return Err(Error::Closed),
+                _ => {}
+            },
+        }
+    }
+    Err(Error::Closed)
+}
+
+// e.g. an input that doesn't exist. the connection is fine, so this is only recorded
+fn report_failure(text: &str) {
+    let Ok(msg) = serde_json::from_str::<Value>(text) else {
+        return;
+    };
+    if msg.get("op").and_then(Value::as_u64) != Some(OP_REQUEST_RESPONSE) {
+        return;
+    }
+    let status = &msg["d"]["requestStatus"];
+    if status["result"].as_bool() == Some(false) {
+        let comment = status["comment"].as_str().unwrap_or("no reason given");
+        events::record(Level::Warning, format!("OBS refused to mute: {comment}"));
+    }
+}
+
+fn message(op: u64, data: &Value) -> Message {
+    Message::Text(json!({ "op": op, "d": data }).to_string().into())
+}
+
+// the data of the next message, which has to be `op`
+async fn receive(
+    stream: &mut (impl Stream<Item = Result<Message, tungstenite::Error>> + Unpin),
+    op: u64,
+) -> Result<Value, Error> {
+    loop {
+        let text = match stream.next().await.ok_or(Error::Closed)?? {
+            Message::Text(text) => text,
+            Message::Close(frame) => {
+                return Err(match frame {
+                    Some(frame) if !frame.reason.is_empty() => {
+                        Error::Refused(frame.reason.to_string())
+                    }
+                    _ => Error::Closed,
+                });
+            }
+            _ => continue,
+        };
+        let mut msg: Value = serde_json::from_str(text.as_str()).map_err(|_| Error::Protocol)?;
+        if msg.get("op").and_then(Value::as_u64) != Some(op) {
+            return Err(Error::Protocol);
+        }
+        return Ok(msg["d"].take());
+    }
+}
+
+// base64(sha256(base64(sha256(password + salt)) + challenge)), as obs-websocket expects
+fn authentication(password: &str, salt: &str, challenge: &str) -> String {
+    let secret = BASE64_STANDARD.encode(Sha256::digest(format!("{password}{salt}")));
+    BASE64_STANDARD.encode(Sha256::digest(format!("{secret}{challenge}")))
+}
@@ -0,0 +1,171 @@
+use std::time::Duration;
+
+use notify_rust::{Hint, Notification, Timeout, Urgency};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use zbus::{Connection, fdo::DBusProxy, names::BusName};
+
+use crate::{
+    events::{self, Level},
+    i18n::tr,
+};
+
+// plasmashell shows the same popup for volume changes
+const KDE_SERVICE: &str = "org.kde.plasmashell";
+const KDE_PATH: &str = "/org/kde/osdService";
+const KDE_INTERFACE: &str = "org.kde.osdService";
+// notification servers that know this hint replace the popup in place instead of queueing them
+const SYNCHRONOUS_HINT: &str = "x-canonical-private-synchronous";
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_millis(1500);
+// changes in between are merged, only the latest one is shown
+const MIN_INTERVAL: Duration = Duration::from_millis(400);
+
+/// What the popup says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indication {
+    Live,
+    Muted,
+    Disabled,
+}
+
+impl Indication {
+    fn icon(self) -> &'static str {
+        match self {
+            Self::Live => "audio-input-microphone",
+            Self::Muted | Self::Disabled => "microphone-sensitivity-muted",
+        }
+    }
+
+    fn text(self) -> String {
+        match self {
+            Self::Live => tr!("osd-live"),
+            Self::Muted => tr!("osd-muted"),
+            Self::Disabled => tr!("osd-disabled"),
+        }
+    }
+}
+
+/// Shows the desktop's on-screen display, KDE's when Plasma runs and a transient notification
+/// elsewhere. It runs on a thread of its own, so that a slow desktop can't hold up the app.
+#[derive(Debug, Clone)]
+pub struct Osd {
+    tx: UnboundedSender<Indication>,
+}
+
+impl Osd {
+    pub fn start() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let spawned = std::thread::Builder::new()
+            .name("osd".into())
+            .spawn(move || {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build();
+                match runtime {
+                    Ok(runtime) => runtime.block_on(run(rx)),
+                    Err(e) => events::record(
+                        Level::Warning,
+                        format!("The on-screen display is unavailable: {e}"),
+                    ),
+                }
+            });
+        if let Err(e) = spawned {
+            events::record(
+                Level::Warning,
+                format!("Failed to start the on-screen display thread: {e}"),
+            );
+        }
+        Self { tx }
+    }
+
+    pub fn show(&self, indication: Indication) {
+        // gone when the thread couldn't start
+        let _ = self.tx.send(indication);
+    }
+}
+
+async fn run(mut rx: UnboundedReceiver<Indication>) {
+    let mut display = Display::default();
+    let mut shown = None;
+    while let Some(mut indication) = rx.recv().await {
+        while let Ok(newer) = rx.try_recv() {
+            indication = newer;
+        }
+        // e.g. a press and release that were merged
+        if shown == Some(indication) {
+            continue;
+        }
+        display.show(indication).await;
+        shown = Some(indication);
+        tokio::time::sleep(MIN_INTERVAL).await;
+    }
+}
+
+#[derive(Default)]
+struct Display {
+    // only connected once the first popup is wanted
+    bus: Option<Connection>,
+    // the last notification, replaced by the next one
+    notification: Option<u32>,
+}
+
+impl Display {
+    async fn show(&mut self, indication: Indication) {
+        let text = indication.text();
+        match self.show_kde(indication.icon(), &text).await {
+            Ok(true) => return,
+            Ok(false) => {}
+            Err(e) => events::record(
+                Level::Warning,
+                format!("Plasma's on-screen display failed: {e}"),
+            ),
+        }
+
+        let mut notification = Notification::new();
+        notification
+            .appname("Global Push-to-Talk")
+            .summary(&text)
+            .icon(indication.icon())
+            .urgency(Urgency::Low)
+            .timeout(Timeout::Milliseconds(
+                NOTIFICATION_TIMEOUT
+                    .as_millis()
+                    .try_into()
+                    .unwrap_or(u32::MAX),
+            ))
+            .hint(Hint::Transient(true))
+            .hint(Hint::Custom(SYNCHRONOUS_HINT.into(), "global-ptt".into()));
+        if let Some(id) = self.notification {
+            notification.id(id);
+        }
+        match notification.show_async().await {
+            Ok(handle) => self.notification = Some(handle.id()),
+            Err(e) => events::record(
+                Level::Warning,
+                format!("Failed to show the on-screen display: {e}"),
+            ),
+        }
+    }
+
+    // false when Plasma isn't running, which is checked every time since it may be restarted
+    async fn show_kde(&mut self, icon: &str, text: &str) -> zbus::Result<bool> {
+        let bus = if let Some(bus) = &self.bus {
+            bus.clone()
+        } else {
+            let bus = Connection::session().await?;
+            self.bus = Some(bus.clone());
+            bus
+        };
+        let running = DBusProxy::new(&bus)
+            .await?
+            .name_has_owner(BusName::try_from(KDE_SERVICE)?)
+            .await?;
+        if !running {
+            return Ok(false);
+        }
+        zbus::Proxy::new(&bus, KDE_SERVICE, KDE_PATH, KDE_INTERFACE)
+            .await?
+            .call_method("showText", &(icon, text))
+            .await?;
+        Ok(true)
+    }
+}
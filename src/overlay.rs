@@ -0,0 +1,509 @@
+use std::sync::{Arc, Mutex};
+
+use smithay_client_toolkit::{
+    compositor::{CompositorHandler, CompositorState, Region},
+    delegate_compositor, delegate_layer, delegate_output, delegate_registry, delegate_shm,
+    output::{OutputHandler, OutputState},
+    reexports::{
+        calloop::{
+            EventLoop,
+            channel::{self, Channel, Sender},
+        },
+        calloop_wayland_source::WaylandSource,
+        client::{
+            Connection, QueueHandle,
+            globals::registry_queue_init,
+            protocol::{wl_output, wl_shm, wl_surface},
+        },
+    },
+    registry::{ProvidesRegistryState, RegistryState},
+    registry_handlers,
+    shell::{
+        WaylandSurface,
+        wlr_layer::{
+            Anchor, KeyboardInteractivity, Layer, LayerShell, LayerShellHandler, LayerSurface,
+            LayerSurfaceConfigure,
+        },
+    },
+    shm::{
+        Shm, ShmHandler,
+        slot::{Buffer, SlotPool},
+    },
+};
+
+use crate::{
+    config::Corner,
+    events::{self, Level},
+    tray::{IconColors, IconState, draw_icon},
+};
+
+// at 100%
+const SIZE: i32 = 48;
+// opacity of the icon while muted, on top of the overlay's own
+const DIMMED: f64 = 0.4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayState {
+    Hidden,
+    Muted,
+    Transmitting,
+}
+
+/// How the overlay looks and where it goes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Style {
+    pub corner: Corner,
+    // from the edges of the screen, in pixels
+    pub margin: u16,
+    // in percent
+    pub scale: u16,
+    pub opacity: u8,
+    pub colors: IconColors,
+    // the name of a monitor, the compositor picks one when unset or not connected
+    pub output: Option<String>,
+}
+
+#[derive(Debug)]
+enum Command {
+    State(OverlayState),
+    Style(Style),
+}
+
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error("could not connect to the Wayland compositor: {0}")]
+    Connect(#[from] smithay_client_toolkit::reexports::client::ConnectError),
+    #[error("the compositor does not support {0}")]
+    Unsupported(&'static str),
+    #[error("{0}")]
+    Other(String),
+}
+
+/// An always-on-top mic icon drawn on a layer-shell surface, so that it shows up over
+/// fullscreen windows too. It lives on its own thread, which stops when this is dropped.
+#[derive(Debug, Clone)]
+pub struct Overlay {
+    tx: Sender<Command>,
+    // names of the connected monitors, filled in by the thread
+    outputs: Arc<Mutex<Vec<String>>>,
+}
+
+impl Overlay {
+    /// Starts the overlay. Compositors without wlr-layer-shell, and X11, simply don't show
+    /// anything.
+    pub fn spawn(style: Style, state: OverlayState) -> Self {
+        let (tx, rx) = channel::channel();
+        let outputs = Arc::new(Mutex::new(Vec::new()));
+        let names = outputs.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = run(rx, style, state, names) {
+                events::record(Level::Warning, format!("Overlay unavailable: {e}"));
+            }
+        });
+        Self { tx, outputs }
+    }
+
+    pub fn set_state(&self, state: OverlayState) {
+        let _ = self.tx.send(Command::State(state));
+    }
+
+    /// Applies the style to the shown overlay, only moving it to another monitor starts over
+    /// with a new surface.
+    pub fn set_style(&self, style: Style) {
+        let _ = self.tx.send(Command::Style(style));
+    }
+
+    /// The names of the monitors the overlay can be put on.
+    pub fn outputs(&self) -> Vec<String> {
+        self.outputs
+            .lock()
+            .map(|outputs| outputs.clone())
+            .unwrap_or_default()
+    }
+}
+
+fn run(
+    rx: Channel<Command>,
+    style: Style,
+    state: OverlayState,
+    outputs: Arc<Mutex<Vec<String>>>,
+) -> Result<(), Error> {
+    let conn = Connection::connect_to_env()?;
+    let (globals, event_queue) =
+        registry_queue_init(&conn).map_err(|e| Error::Other(e.to_string()))?;
+    let qh = event_queue.handle();
+
+    let compositor =
+        CompositorState::bind(&globals, &qh).map_err(|_| Error::Unsupported("wl_compositor"))?;
+    let layer_shell =
+        LayerShell::bind(&globals, &qh).map_err(|_| Error::Unsupported("wlr-layer-shell"))?;
+    let shm = Shm::bind(&globals, &qh).map_err(|_| Error::Unsupported("wl_shm"))?;
+
+    let pool = SlotPool::new(usize::try_from(SIZE * SIZE * 4).unwrap_or_default(), &shm)
+        .map_err(|e| Error::Other(e.to_string()))?;
+    let output_state = OutputState::new(&globals, &qh);
+    // the outputs are only known after a roundtrip, so it starts out wherever the compositor
+    // puts it and moves once the chosen one shows up
+    let layer = create_layer(&compositor, &layer_shell, &qh, &style, None)?;
+
+    let mut event_loop: EventLoop<'_, OverlaySurface> =
+        EventLoop::try_new().map_err(|e| Error::Other(e.to_string()))?;
+    WaylandSource::new(conn, event_queue)
+        .insert(event_loop.handle())
+        .map_err(|e| Error::Other(e.to_string()))?;
+    event_loop
+        .handle()
+        .insert_source(rx, |event, (), overlay| match event {
+            channel::Event::Msg(Command::State(state)) => {
+                overlay.state = state;
+                overlay.draw();
+            }
+            channel::Event::Msg(Command::Style(style)) => overlay.restyle(style),
+            // the app dropped its handle
+            channel::Event::Closed => overlay.exit = true,
+        })
+        .map_err(|e| Error::Other(e.to_string()))?;
+
+    let mut overlay = OverlaySurface {
+        registry_state: RegistryState::new(&globals),
+        output_state,
+        compositor,
+        layer_shell,
+        qh,
+        shm,
+        pool,
+        buffer: None,
+        layer,
+        output: None,
+        configured: false,
+        state,
+        style,
+        outputs,
+        exit: false,
+    };
+    while !overlay.exit {
+        event_loop
+            .dispatch(None, &mut overlay)
+            .map_err(|e| Error::Other(e.to_string()))?;
+    }
+    Ok(())
+}
+
+// a fresh surface on `output`, committed and waiting for its first configure
+fn create_layer(
+    compositor: &CompositorState,
+    layer_shell: &LayerShell,
+    qh: &QueueHandle<OverlaySurface>,
+    style: &Style,
+    output: Option<&wl_output::WlOutput>,
+) -> Result<LayerSurface, Error> {
+    let surface = compositor.create_surface(qh);
+    let layer =
+        layer_shell.create_layer_surface(qh, surface, Layer::Overlay, Some("global-ptt"), output);
+    // never take any input away from the game underneath
+    layer.set_keyboard_interactivity(KeyboardInteractivity::None);
+    layer.set_exclusive_zone(0);
+    let region = Region::new(compositor).map_err(|e| Error::Other(e.to_string()))?;
+    layer
+        .wl_surface()
+        .set_input_region(Some(region.wl_region()));
+    place(&layer, style);
+    layer.commit();
+    Ok(layer)
+}
+
+fn place(layer: &LayerSurface, style: &Style) {
+    let size = size(style.scale).unsigned_abs();
+    layer.set_size(size, size);
+    let (anchor, [top, right, bottom, left]) = anchor(style.corner, i32::from(style.margin));
+    layer.set_anchor(anchor);
+    layer.set_margin(top, right, bottom, left);
+}
+
+// the width and height of the icon at `scale` percent
+fn size(scale: u16) -> i32 {
+    SIZE * i32::from(scale) / 100
+}
+
+// the edges to anchor to, and the margins to the top, right, bottom and left
+fn anchor(corner: Corner, margin: i32) -> (Anchor, [i32; 4]) {
+    match corner {
+        Corner::TopLeft => (Anchor::TOP | Anchor::LEFT, [margin, 0, 0, margin]),
+        Corner::TopRight => (Anchor::TOP | Anchor::RIGHT, [margin, margin, 0, 0]),
+        Corner::BottomLeft => (Anchor::BOTTOM | Anchor::LEFT, [0, 0, margin, margin]),
+        Corner::BottomRight => (Anchor::BOTTOM | Anchor::RIGHT, [0, margin, margin, 0]),
+    }
+}
+
+struct OverlaySurface {
+    registry_state: RegistryState,
+    output_state: OutputState,
+    compositor: CompositorState,
+    layer_shell: LayerShell,
+    qh: QueueHandle<Self>,
+    shm: Shm,
+    pool: SlotPool,
+    // kept until the compositor is done with it
+    buffer: Option<Buffer>,
+    layer: LayerSurface,
+    // the one asked for, the compositor's pick when none
+    output: Option<wl_output::WlOutput>,
+    configured: bool,
+    state: OverlayState,
+    style: Style,
+    outputs: Arc<Mutex<Vec<String>>>,
+    exit: bool,
+}
+
+impl OverlaySurface {
+    fn restyle(&mut self, style: Style) {
+        let old = std::mem::replace(&mut self.style, style);
+        if old.output != self.style.output {
+            self.follow_output(None);
+            return;
+        }
+        if old.scale != self.style.scale {
+            // drawn again once the compositor agreed to the new size
+            self.configured = false;
+        }
+        place(&self.layer, &self.style);
+        self.layer.commit();
+        self.draw();
+    }
+
+    // the connected output named in the style, leaving out one that's going away
+    fn wanted_output(&self, gone: Option<&wl_output::WlOutput>) -> Option<wl_output::WlOutput> {
+        let name = self.style.output.as_deref()?;
+        self.output_state
+            .outputs()
+            .filter(|output| Some(output) != gone)
+            .find(|output| {
+                self.output_state
+                    .info(output)
+                    .is_some_and(|info| info.name.as_deref() == Some(name))
+            })
+    }
+
+    // a layer surface can't move, so a new one is made when it should be on another output
+    fn follow_output(&mut self, gone: Option<&wl_output::WlOutput>) {
+        let output = self.wanted_output(gone);
+        if output != self.output {
+            self.replace_layer(output);
+        }
+    }
+
+    fn replace_layer(&mut self, output: Option<wl_output::WlOutput>) {
+        match create_layer(
+            &self.compositor,
+            &self.layer_shell,
+            &self.qh,
+            &self.style,
+            output.as_ref(),
+        ) {
+            Ok(layer) => {
+                self.layer = layer;
+                self.output = output;
+                self.configured = false;
+            }
+            Err(e) => {
+                events::record(Level::Warning, format!("Overlay unavailable: {e}"));
+                self.exit = true;
+            }
+        }
+    }
+
+    fn update_outputs(&mut self, gone: Option<&wl_output::WlOutput>) {
+        let names = self
+            .output_state
+            .outputs()
+            .filter(|output| Some(output) != gone)
+            .filter_map(|output| self.output_state.info(&output)?.name)
+            .collect();
+        if let Ok(mut outputs) = self.outputs.lock() {
+            *outputs = names;
+        }
+        self.follow_output(gone);
+    }
+
+    fn draw(&mut self) {
+        // nothing can be attached before the first configure
+        if !self.configured {
+            return;
+        }
+
+        let size = size(self.style.scale);
+        let Ok((buffer, canvas)) =
+            self.pool
+                .create_buffer(size, size, size * 4, wl_shm::Format::Argb8888)
+        else {
+            return;
+        };
+
+        // hidden is drawn fully transparent, which keeps the surface mapped for next time
+        let (icon_state, opacity) = match self.state {
+            OverlayState::Hidden => (IconState::Muted, 0.0),
+            OverlayState::Muted => (IconState::Muted, DIMMED),
+            OverlayState::Transmitting => (IconState::Transmitting, 1.0),
+        };
+        let opacity = opacity * f64::from(self.style.opacity) / 100.0;
+        let icon = draw_icon(size, icon_state, self.style.colors);
+
+        // the icon is ARGB in network byte order, shm wants premultiplied little-endian ARGB
+        for (dst, src) in canvas.chunks_exact_mut(4).zip(icon.data.chunks_exact(4)) {
+            let alpha = f64::from(src[0]) / 255.0 * opacity;
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let premultiply = |c: u8| (f64::from(c) * alpha).round() as u8;
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let a = (alpha * 255.0).round() as u8;
+            dst.copy_from_slice(&[
+                premultiply(src[3]),
+                premultiply(src[2]),
+                premultiply(src[1]),
+                a,
+            ]);
+        }
+
+        let surface = self.layer.wl_surface();
+        surface.damage_buffer(0, 0, size, size);
+        if buffer.attach_to(surface).is_err() {
+            return;
+        }
+        self.layer.commit();
+        self.buffer = Some(buffer);
+    }
+}
+
+impl CompositorHandler for OverlaySurface {
+    fn scale_factor_changed(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &wl_surface::WlSurface,
+        _: i32,
+    ) {
+    }
+
+    fn transform_changed(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &wl_surface::WlSurface,
+        _: wl_output::Transform,
+    ) {
+    }
+
+    fn frame(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &wl_surface::WlSurface, _: u32) {}
+
+    fn surface_enter(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &wl_surface::WlSurface,
+        _: &wl_output::WlOutput,
+    ) {
+    }
+
+    fn surface_leave(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &wl_surface::WlSurface,
+        _: &wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl OutputHandler for OverlaySurface {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+
+    fn new_output(&mut self, _: &Connection, _: &QueueHandle<Self>, _: wl_output::WlOutput) {
+        self.update_outputs(None);
+    }
+
+    fn update_output(&mut self, _: &Connection, _: &QueueHandle<Self>, _: wl_output::WlOutput) {
+        self.update_outputs(None);
+    }
+
+    // still listed while this runs
+    fn output_destroyed(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        output: wl_output::WlOutput,
+    ) {
+        self.update_outputs(Some(&output));
+    }
+}
+
+impl LayerShellHandler for OverlaySurface {
+    fn closed(&mut self, _: &Connection, _: &QueueHandle<Self>, layer: &LayerSurface) {
+        // one replaced by a surface on another output
+        if layer != &self.layer {
+            return;
+        }
+        // e.g. when the output it was on went away. one that never showed won't do better on
+        // a second try
+        if !self.configured {
+            self.exit = true;
+            return;
+        }
+        self.replace_layer(self.wanted_output(None));
+    }
+
+    fn configure(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &LayerSurface,
+        _: LayerSurfaceConfigure,
+        _: u32,
+    ) {
+        self.configured = true;
+        self.draw();
+    }
+}
+
+impl ShmHandler for OverlaySurface {
+    fn shm_state(&mut self) -> &mut Shm {
+        &mut self.shm
+    }
+}
+
+impl ProvidesRegistryState for OverlaySurface {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+
+    registry_handlers![OutputState];
+}
+
+delegate_compositor!(OverlaySurface);
+delegate_output!(OverlaySurface);
+delegate_shm!(OverlaySurface);
+delegate_layer!(OverlaySurface);
+delegate_registry!(OverlaySurface);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn margins_keep_away_from_the_anchored_edges() {
+        for corner in Corner::ALL {
+            let (anchor, [top, right, bottom, left]) = anchor(corner, 24);
+            assert_eq!(top == 24, anchor.contains(Anchor::TOP));
+            assert_eq!(right == 24, anchor.contains(Anchor::RIGHT));
+            assert_eq!(bottom == 24, anchor.contains(Anchor::BOTTOM));
+            assert_eq!(left == 24, anchor.contains(Anchor::LEFT));
+        }
+    }
+
+    #[test]
+    fn scale_is_a_percentage_of_the_icon() {
+        assert_eq!(size(100), SIZE);
+        assert_eq!(size(50), SIZE / 2);
+        assert_eq!(size(300), SIZE * 3);
+    }
+}
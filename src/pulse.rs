@@ -1,28 +1,80 @@
-use std::{cell::RefCell, fmt::Display, ops::Deref, rc::Rc, sync::mpsc};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    fmt::Display,
+    ops::Deref,
+    rc::Rc,
+    sync::{OnceLock, mpsc},
+    time::{Duration, Instant},
+};
 
 use libpulse_binding::{
     callbacks::ListResult,
     context::{Context, FlagSet, State},
     error::PAErr,
     mainloop::standard::{IterateResult, Mainloop},
-    operation,
+    operation::{self, Operation},
     proplist::{Proplist, properties},
+    volume::{ChannelVolumes, Volume},
+};
+
+use crate::{
+    events::{self, Level},
+    instance,
 };
 
-pub const VIRTUALMIC_DESCRIPTION: &str = "Global Push-to-Talk Virtual Microphone";
+const VIRTUALMIC_DESCRIPTION: &str = "Global Push-to-Talk Virtual Microphone";
+// every instance's virtual mic starts with this
 const VIRTUALMIC_NAME: &str = "GlobalPushToTalkVirtualMicrophone";
+// a server that stopped answering shouldn't keep the app from exiting
+const REMOVE_TIMEOUT: Duration = Duration::from_secs(2);
+// logind only waits a few seconds for the suspend to be ready
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+static VIRTUALMIC_INSTANCE_NAME: OnceLock<String> = OnceLock::new();
+static VIRTUALMIC_INSTANCE_DESCRIPTION: OnceLock<String> = OnceLock::new();
+
+/// The source name of this instance's virtual mic, e.g. `GlobalPushToTalkVirtualMicrophone_work`.
+pub fn virtual_mic_name() -> &'static str {
+    VIRTUALMIC_INSTANCE_NAME.get_or_init(|| instance::suffixed(VIRTUALMIC_NAME, "_"))
+}
+
+/// What the virtual mic is called in other apps, with the instance id for other instances.
+pub fn virtual_mic_description() -> &'static str {
+    VIRTUALMIC_INSTANCE_DESCRIPTION.get_or_init(|| match instance::id() {
+        Some(id) => format!("{VIRTUALMIC_DESCRIPTION} ({id})"),
+        None => VIRTUALMIC_DESCRIPTION.to_string(),
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerInfo {
+    pub name: String,
+    pub version: String,
+}
 
 #[derive(Clone)]
 pub struct PulseAudioState {
     mainloop: Rc<RefCell<Mainloop>>,
     context: Rc<RefCell<Context>>,
     src_name: Option<String>,
+    // volume to go back to once whispering stops
+    whisper_restore: Option<ChannelVolumes>,
+    // changes are only printed, as pactl commands
+    dry_run: bool,
+    // whether a new virtual mic starts out open, for when the app dies before muting it
+    fail_open: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct InputDevice {
     pub name: String,
     pub description: String,
+    // e.g. "s16le 2ch 48000Hz"
+    pub sample_spec: String,
+    pub active_port: Option<String>,
+    // whether the sound server records from it when nothing else is chosen
+    pub is_default: bool,
 }
 
 impl Display for InputDevice {
@@ -39,116 +91,166 @@ impl PartialEq for InputDevice {
 
 impl PulseAudioState {
     pub fn init() -> Result<Self, Error> {
-        let mut proplist = Proplist::new().ok_or(Error::Other)?;
-        proplist
-            .set_str(properties::APPLICATION_NAME, "GlobalPushToTalk")
-            .map_err(|()| Error::Other)?;
-
-        let mainloop = Rc::new(RefCell::new(
-            Mainloop::new().ok_or(Error::MainloopCreation)?,
-        ));
-
-        let context = Rc::new(RefCell::new(
-            Context::new_with_proplist(
-                mainloop.borrow().deref(),
-                "GlobalPushToTalkContext",
-                &proplist,
-            )
-            .ok_or(Error::ContextCreation)?,
-        ));
-
-        context.borrow_mut().connect(None, FlagSet::NOFLAGS, None)?;
-
-        // Wait for context to be ready
-        loop {
-            match mainloop.borrow_mut().iterate(false) {
-                IterateResult::Quit(_) | IterateResult::Err(_) => {
-                    return Err(Error::MainloopTick);
-                }
-                IterateResult::Success(_) => {}
-            }
-            match context.borrow().get_state() {
-                State::Ready => {
-                    break;
-                }
-                State::Failed | State::Terminated => {
-                    return Err(Error::ContextCreation);
-                }
-                _ => {}
-            }
-        }
-
+        let (mainloop, context) = connect("GlobalPushToTalkContext")?;
         Ok(Self {
-            mainloop: mainloop.clone(),
+            mainloop,
             context,
             src_name: None,
+            whisper_restore: None,
+            dry_run: false,
+            fail_open: false,
         })
     }
 
+    /// Prints every change to the audio server as the `pactl` command that would make it,
+    /// instead of making it. The server is still asked for devices and such.
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Lets a new virtual mic start out open instead of muted, until it is set to where
+    /// push-to-talk is.
+    pub fn set_fail_open(&mut self, fail_open: bool) {
+        self.fail_open = fail_open;
+    }
+
     pub fn remove_virtual_mic(&mut self) {
+        let dry_run = self.dry_run;
         let mut inner_introspect = self.context.borrow().introspect();
+        // unloads that haven't finished yet
+        let pending = Rc::new(Cell::new(0_usize));
+        let inner_pending = pending.clone();
 
-        let delete_op = self
-            .context
-            .borrow()
-            .introspect()
-            .get_module_info_list(move |item| match item {
-                ListResult::Item(i) => {
-                    if i.name.as_deref() != Some("module-remap-source") {
-                        return;
-                    }
+        let delete_op =
+            self.context
+                .borrow()
+                .introspect()
+                .get_module_info_list(move |item| match item {
+                    ListResult::Item(i) => {
+                        if i.name.as_deref() != Some("module-remap-source") {
+                            return;
+                        }
 
-                    if i.argument
-                        .as_ref()
-                        .filter(|args| args.contains(&format!("source_name={VIRTUALMIC_NAME}")))
-                        .is_none()
-                    {
-                        return;
-                    }
+                        // compared in full, the names of other instances start the same
+                        let source_name = format!("source_name={}", virtual_mic_name());
+                        if i.argument.as_ref().is_none_or(|args| {
+                            !args.split_whitespace().any(|arg| arg == source_name)
+                        }) {
+                            return;
+                        }
 
-                    inner_introspect.unload_module(i.index, |_| {});
-                }
-                ListResult::End | ListResult::Error => {}
-            });
+                        if dry_run {
+                            print_dry_run(&["unload-module", &i.index.to_string()]);
+                            return;
+                        }
+                        inner_pending.set(inner_pending.get() + 1);
+                        let done = inner_pending.clone();
+                        inner_introspect.unload_module(i.index, move |_| done.set(done.get() - 1));
+                    }
+                    ListResult::End | ListResult::Error => {}
+                });
 
         // wait for unloading to finish
-        loop {
+        let deadline = Instant::now() + REMOVE_TIMEOUT;
+        while Instant::now() < deadline {
             match self.mainloop.borrow_mut().iterate(false) {
                 IterateResult::Quit(_) | IterateResult::Err(_) => {
                     return;
                 }
                 IterateResult::Success(_) => {}
             }
-            if delete_op.get_state() == operation::State::Done {
+            if delete_op.get_state() != operation::State::Running && pending.get() == 0 {
                 break;
             }
         }
     }
 
-    pub fn set_virtual_mic(&mut self, source_name: &str) {
+    pub fn set_virtual_mic(&mut self, source_name: &str) -> Result<(), Error> {
         self.remove_virtual_mic();
 
-        let options = format!(
-            "master={source_name} source_name={VIRTUALMIC_NAME} source_properties=\"device.description='{VIRTUALMIC_DESCRIPTION}'\""
-        );
+        let options = [
+            format!("master={source_name}"),
+            format!("source_name={}", virtual_mic_name()),
+            format!(
+                "source_properties=\"device.description='{}'\"",
+                virtual_mic_description()
+            ),
+        ];
+        if self.dry_run {
+            let mut args = vec!["load-module", "module-remap-source"];
+            args.extend(options.iter().map(String::as_str));
+            print_dry_run(&args);
+            self.src_name = Some(source_name.to_string());
+            self.whisper_restore = None;
+            return self.set_mute(!self.fail_open);
+        }
+        let options = options.join(" ");
 
-        let create_op =
-            self.context
-                .borrow()
-                .introspect()
-                .load_module("module-remap-source", &options, |_| {});
+        let (tx, rx) = mpsc::channel();
+        let create_op = self.context.borrow().introspect().load_module(
+            "module-remap-source",
+            &options,
+            move |index| {
+                let _ = tx.send(index);
+            },
+        );
 
         // wait for loading to finish
-        loop {
-            match self.mainloop.borrow_mut().iterate(false) {
-                IterateResult::Quit(_) | IterateResult::Err(_) => {
-                    return;
+        self.wait(&create_op)?;
+        // the index is invalid when the module couldn't be loaded
+        if !matches!(rx.try_recv(), Ok(index) if index != u32::MAX) {
+            return Err(Error::ModuleLoad);
+        }
+
+        // the first thing the new source is told, since the server may remember another state for
+        // a source of the same name
+        let _ = self.set_mute(!self.fail_open);
+        self.src_name = Some(source_name.to_string());
+        self.whisper_restore = None;
+        Ok(())
+    }
+
+    /// Whether the virtual mic is still around. Other tools can unload its module behind our back.
+    pub fn virtual_mic_exists(&self) -> bool {
+        // it's only pretended to be there
+        if self.dry_run {
+            return self.src_name.is_some();
+        }
+        let (tx, rx) = mpsc::channel();
+        let op = self.context.borrow().introspect().get_source_info_by_name(
+            virtual_mic_name(),
+            move |item| {
+                if let ListResult::Item(_) = item {
+                    let _ = tx.send(());
                 }
-                IterateResult::Success(_) => {}
-            }
-            if create_op.get_state() != operation::State::Running {
-                let _ = self.set_mute(true);
-                self.src_name = Some(source_name.to_string());
+            },
+        );
+        // assume it's there when the server can't be asked
+        if self.wait(&op).is_err() {
+            return true;
+        }
+        rx.try_recv().is_ok()
+    }
+
+    /// Whether the connection to the audio server still works, e.g. after a suspend.
+    pub fn is_connected(&self) -> bool {
+        // the state is only updated while the mainloop runs
+        if let IterateResult::Quit(_) | IterateResult::Err(_) =
+            self.mainloop.borrow_mut().iterate(false)
+        {
+            return false;
+        }
+        self.context.borrow().get_state() == State::Ready
+    }
+
+    /// Waits for requests that were sent but not yet answered, e.g. before a suspend cuts the
+    /// connection off.
+    pub fn flush(&self) {
+        let deadline = Instant::now() + FLUSH_TIMEOUT;
+        while self.context.borrow().is_pending() && Instant::now() < deadline {
+            if let IterateResult::Quit(_) | IterateResult::Err(_) =
+                self.mainloop.borrow_mut().iterate(false)
+            {
                 return;
             }
         }
@@ -159,13 +261,126 @@ impl PulseAudioState {
     }
 
     pub fn set_mute(&mut self, mute: bool) -> Result<(), Error> {
-        let op =
-            self.context
-                .borrow()
-                .introspect()
-                .set_source_mute_by_name(VIRTUALMIC_NAME, mute, None);
+        if self.dry_run {
+            print_dry_run(&[
+                "set-source-mute",
+                virtual_mic_name(),
+                if mute { "1" } else { "0" },
+            ]);
+            return Ok(());
+        }
+        let (tx, rx) = mpsc::channel();
+        let op = self.context.borrow().introspect().set_source_mute_by_name(
+            virtual_mic_name(),
+            mute,
+            Some(Box::new(move |success| {
+                let _ = tx.send(success);
+            })),
+        );
+        self.wait(&op)?;
+
+        // e.g. when something else removed the virtual mic
+        if rx.try_recv().unwrap_or(false) {
+            Ok(())
+        } else {
+            Err(Error::Refused)
+        }
+    }
+
+    /// Lowers the virtual mic's volume to `percent` of its current volume, remembering the
+    /// current volume for [`Self::stop_whisper`].
+    pub fn start_whisper(&mut self, percent: u8) -> Result<(), Error> {
+        if self.whisper_restore.is_some() {
+            return Ok(());
+        }
+        // the real volume is scaled from whatever it is, which isn't there to be read
+        if self.dry_run {
+            let volume = format!("{percent}%");
+            print_dry_run(&["set-source-volume", virtual_mic_name(), &volume]);
+            // stands in for the volume to go back to, so that stopping knows it was started
+            self.whisper_restore = Some(ChannelVolumes::default());
+            return Ok(());
+        }
+
+        let volume = self.get_volume()?;
+        let max = u64::from(volume.max().0) * u64::from(percent) / 100;
+        let mut whisper = volume;
+        whisper.scale(Volume(u32::try_from(max).unwrap_or(u32::MAX)));
+        self.set_volume(&whisper)?;
+        self.whisper_restore = Some(volume);
+        Ok(())
+    }
+
+    /// Puts the volume back exactly as it was before whispering, even if it was changed since.
+    pub fn stop_whisper(&mut self) -> Result<(), Error> {
+        match self.whisper_restore.take() {
+            Some(_) if self.dry_run => {
+                print_dry_run(&["set-source-volume", virtual_mic_name(), "100%"]);
+                Ok(())
+            }
+            Some(volume) => self.set_volume(&volume),
+            None => Ok(()),
+        }
+    }
 
-        // wait for it to complete
+    /// Which sound server is running. `PipeWire` shows up as e.g. `PulseAudio (on PipeWire 1.0.5)`.
+    pub fn server_info(&self) -> Option<ServerInfo> {
+        let (tx, rx) = mpsc::channel();
+        let op = self
+            .context
+            .borrow()
+            .introspect()
+            .get_server_info(move |info| {
+                let _ = tx.send(ServerInfo {
+                    name: info.server_name.as_deref().unwrap_or_default().to_string(),
+                    version: info
+                        .server_version
+                        .as_deref()
+                        .unwrap_or_default()
+                        .to_string(),
+                });
+            });
+        self.wait(&op).ok()?;
+        rx.try_recv().ok()
+    }
+
+    fn default_source_name(&self) -> Option<String> {
+        let (tx, rx) = mpsc::channel();
+        let op = self
+            .context
+            .borrow()
+            .introspect()
+            .get_server_info(move |info| {
+                let _ = tx.send(info.default_source_name.as_deref().map(ToString::to_string));
+            });
+        self.wait(&op).ok()?;
+        rx.try_recv().ok().flatten()
+    }
+
+    fn get_volume(&self) -> Result<ChannelVolumes, Error> {
+        let (tx, rx) = mpsc::channel();
+        let op = self.context.borrow().introspect().get_source_info_by_name(
+            virtual_mic_name(),
+            move |item| {
+                if let ListResult::Item(i) = item {
+                    let _ = tx.send(i.volume);
+                }
+            },
+        );
+        self.wait(&op)?;
+        rx.try_recv().map_err(|_| Error::Other)
+    }
+
+    fn set_volume(&mut self, volume: &ChannelVolumes) -> Result<(), Error> {
+        let op = self
+            .context
+            .borrow()
+            .introspect()
+            .set_source_volume_by_name(virtual_mic_name(), volume, None);
+        self.wait(&op)
+    }
+
+    fn wait<T: ?Sized>(&self, op: &Operation<T>) -> Result<(), Error> {
         loop {
             match self.mainloop.borrow_mut().iterate(false) {
                 IterateResult::Quit(_) | IterateResult::Err(_) => {
@@ -181,6 +396,7 @@ impl PulseAudioState {
 
     pub fn get_input_devices(&self) -> Vec<InputDevice> {
         let mut vec = Vec::new();
+        let default_source = self.default_source_name();
         let (tx, rx) = mpsc::channel();
         let op = self
             .context
@@ -189,7 +405,8 @@ impl PulseAudioState {
             .get_source_info_list(move |item| {
                 if let ListResult::Item(i) = item
                     && let Some(name) = &i.name
-                    && name != VIRTUALMIC_NAME
+                    // no instance records from a virtual mic
+                    && !name.starts_with(VIRTUALMIC_NAME)
                 {
                     let _ = tx.send(InputDevice {
                         name: name.to_string(),
@@ -198,6 +415,14 @@ impl PulseAudioState {
                             .as_deref()
                             .map(ToString::to_string)
                             .unwrap_or(name.to_string()),
+                        sample_spec: i.sample_spec.print(),
+                        active_port: i.active_port.as_ref().and_then(|port| {
+                            port.description
+                                .as_deref()
+                                .or(port.name.as_deref())
+                                .map(ToString::to_string)
+                        }),
+                        is_default: default_source.as_deref() == Some(name.as_ref()),
                     });
                 }
             });
@@ -221,6 +446,70 @@ impl PulseAudioState {
     }
 }
 
+// a mainloop and the context running on it
+type Connection = (Rc<RefCell<Mainloop>>, Rc<RefCell<Context>>);
+
+/// A connection to the audio server, once it's ready to use.
+pub fn connect(name: &str) -> Result<Connection, Error> {
+    let mut proplist = Proplist::new().ok_or(Error::Other)?;
+    proplist
+        .set_str(properties::APPLICATION_NAME, "GlobalPushToTalk")
+        .map_err(|()| Error::Other)?;
+
+    let mainloop = Rc::new(RefCell::new(
+        Mainloop::new().ok_or(Error::MainloopCreation)?,
+    ));
+
+    let context = Rc::new(RefCell::new(
+        Context::new_with_proplist(mainloop.borrow().deref(), name, &proplist)
+            .ok_or(Error::ContextCreation)?,
+    ));
+
+    context.borrow_mut().connect(None, FlagSet::NOFLAGS, None)?;
+
+    // Wait for context to be ready
+    loop {
+        match mainloop.borrow_mut().iterate(false) {
+            IterateResult::Quit(_) | IterateResult::Err(_) => {
+                return Err(Error::MainloopTick);
+            }
+            IterateResult::Success(_) => {}
+        }
+        match context.borrow().get_state() {
+            State::Ready => {
+                break;
+            }
+            State::Failed | State::Terminated => {
+                return Err(Error::ContextCreation);
+            }
+            _ => {}
+        }
+    }
+
+    Ok((mainloop, context))
+}
+
+// prints the command to stdout and the diagnostics page
+fn print_dry_run(args: &[&str]) {
+    let args: Vec<_> = args.iter().map(|arg| shell_quote(arg)).collect();
+    let command = format!("pactl {}", args.join(" "));
+    println!("{command}");
+    events::record(Level::Info, format!("Dry run: {command}"));
+}
+
+// single quotes anything a shell would otherwise split or expand
+fn shell_quote(arg: &str) -> Cow<'_, str> {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_=.,:/@%+".contains(c));
+    if plain {
+        Cow::Borrowed(arg)
+    } else {
+        Cow::Owned(format!("'{}'", arg.replace('\'', "'\\''")))
+    }
+}
+
 impl Drop for PulseAudioState {
     fn drop(&mut self) {
         self.context.borrow_mut().disconnect();
@@ -240,6 +529,10 @@ pub enum Error {
     ContextConnection(#[from] PAErr),
     #[error("failed to tick mainloop")]
     MainloopTick,
+    #[error("failed to create the virtual microphone")]
+    ModuleLoad,
+    #[error("the audio server refused the change")]
+    Refused,
     #[error("other error")]
     Other,
 }
@@ -0,0 +1,83 @@
+use std::{sync::Arc, time::Duration};
+
+use iced::{
+    Task,
+    futures::{SinkExt, Stream, channel::mpsc::Sender},
+    stream,
+};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{
+    app::Msg,
+    events::{self, Level},
+    ipc::{self, Connection, Reply},
+};
+
+const ACCEPT_RETRY: Duration = Duration::from_millis(100);
+
+/// Listens for the JSON protocol of the control socket over TCP, e.g. for a Stream Deck on
+/// another machine. Every request has to carry `token`. Dropping the task stops listening.
+pub fn listen(address: String, port: u16, token: String) -> Task<Msg> {
+    Task::stream(serve(address, port, token))
+}
+
+// every connection gets a task of its own, so that a peer that sends nothing holds up nobody
+// else
+fn serve(address: String, port: u16, token: String) -> impl Stream<Item = Msg> {
+    stream::channel(16, async move |tx| {
+        let listener = match TcpListener::bind((address.as_str(), port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                events::record(
+                    Level::Error,
+                    format!("Failed to listen for remote control on {address}:{port}: {e}"),
+                );
+                return;
+            }
+        };
+        events::record(
+            Level::Info,
+            format!("Listening for remote control on {address}:{port}"),
+        );
+
+        let token = Arc::new(token);
+        loop {
+            let Ok((incoming, _)) = listener.accept().await else {
+                // e.g. out of file descriptors, which takes a moment to change
+                tokio::time::sleep(ACCEPT_RETRY).await;
+                continue;
+            };
+            tokio::spawn(handle(incoming, token.clone(), tx.clone()));
+        }
+    })
+}
+
+async fn handle(incoming: TcpStream, token: Arc<String>, mut tx: Sender<Msg>) {
+    let peer = incoming
+        .peer_addr()
+        .map_or_else(|_| "unknown address".into(), |addr| addr.to_string());
+    let (line, mut incoming) = ipc::read_request(incoming).await;
+
+    let request = match ipc::parse_with_token(&line, &token) {
+        Ok(request) => request,
+        Err(e) => {
+            events::record(
+                Level::Warning,
+                format!("Rejected a remote control request from {peer}: {e}"),
+            );
+            let _ = incoming.write_all(ipc::refusal(&e).as_bytes()).await;
+            return;
+        }
+    };
+
+    // the answer is written from the app, which doesn't run async code
+    let Ok(incoming) = incoming.into_std() else {
+        return;
+    };
+    let _ = incoming.set_nonblocking(false);
+    let reply = Reply::new(Connection::Tcp(incoming), false);
+    let _ = tx.send(Msg::Command(request, reply)).await;
+}
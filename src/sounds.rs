@@ -0,0 +1,184 @@
+use std::{
+    ffi::{CStr, c_char, c_int, c_void},
+    ptr,
+    sync::mpsc::{self, Receiver, Sender},
+};
+
+use libloading::Library;
+
+use crate::events::{self, Level};
+
+// loaded at runtime, so that the app still starts on systems without it
+const LIBRARY: &str = "libcanberra.so.0";
+// CA_ERROR_NOTFOUND, the sound theme has no sound for the event
+const NOT_FOUND: c_int = -9;
+// every sound is played with this id, so that a new one can cut off the one before
+const SOUND_ID: u32 = 0;
+
+type Create = unsafe extern "C" fn(*mut *mut c_void) -> c_int;
+type Destroy = unsafe extern "C" fn(*mut c_void) -> c_int;
+// both take pairs of property names and values, ended by a null pointer
+type ChangeProps = unsafe extern "C" fn(*mut c_void, ...) -> c_int;
+type Play = unsafe extern "C" fn(*mut c_void, u32, ...) -> c_int;
+type Cancel = unsafe extern "C" fn(*mut c_void, u32) -> c_int;
+
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error("libcanberra could not be loaded: {0}")]
+    Library(#[from] libloading::Error),
+    #[error("libcanberra failed with error {0}")]
+    Canberra(c_int),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    Unmute,
+    Mute,
+}
+
+impl Sound {
+    // events of the sound theme, tried in order until the theme has one. the first ones are named
+    // after what happened, the others are in nearly every theme
+    fn event_ids(self) -> &'static [&'static CStr] {
+        match self {
+            Self::Unmute => &[c"audio-volume-unmuted", c"device-added"],
+            Self::Mute => &[c"audio-volume-muted", c"device-removed"],
+        }
+    }
+
+    fn description(self) -> &'static CStr {
+        match self {
+            Self::Unmute => c"Microphone opened",
+            Self::Mute => c"Microphone closed",
+        }
+    }
+}
+
+/// Plays sounds of the desktop's sound theme, on a thread of its own so that nothing waits for
+/// them. Without libcanberra or a fitting sound, it stays silent.
+#[derive(Debug, Clone)]
+pub struct Sounds {
+    tx: Sender<Sound>,
+}
+
+impl Sounds {
+    pub fn start() -> Self {
+        let (tx, rx) = mpsc::channel();
+        let spawned = std::thread::Builder::new()
+            .name("sounds".into())
+            .spawn(move || run(&rx));
+        if let Err(e) = spawned {
+            events::record(
+                Level::Warning,
+                format!("Failed to start the sound thread: {e}"),
+            );
+        }
+        Self { tx }
+    }
+
+    pub fn play(&self, sound: Sound) {
+        // gone when libcanberra couldn't be loaded
+        let _ = self.tx.send(sound);
+    }
+}
+
+fn run(rx: &Receiver<Sound>) {
+    // only loaded once a sound is wanted, most people never turn them on
+    let Ok(first) = rx.recv() else {
+        return;
+    };
+    let player = match Player::load() {
+        Ok(player) => player,
+        Err(e) => {
+            events::record(Level::Info, format!("Event sounds are unavailable: {e}"));
+            return;
+        }
+    };
+
+    for sound in std::iter::once(first).chain(rx.iter()) {
+        if let Err(e) = player.play(sound) {
+            events::record(Level::Warning, format!("Failed to play a sound: {e}"));
+        }
+    }
+}
+
+struct Player {
+    context: *mut c_void,
+    destroy: Destroy,
+    play: Play,
+    cancel: Cancel,
+    // the function pointers above point into it
+    _library: Library,
+}
+
+impl Player {
+    fn load() -> Result<Self, Error> {
+        // SAFETY: libcanberra runs no code of note when loaded, and the signatures are the ones
+        // in canberra.h
+        unsafe {
+            let library = Library::new(LIBRARY)?;
+            let create = *library.get::<Create>(b"ca_context_create\0")?;
+            let change_props = *library.get::<ChangeProps>(b"ca_context_change_props\0")?;
+            let destroy = *library.get::<Destroy>(b"ca_context_destroy\0")?;
+            let play = *library.get::<Play>(b"ca_context_play\0")?;
+            let cancel = *library.get::<Cancel>(b"ca_context_cancel\0")?;
+
+            let mut context = ptr::null_mut();
+            let res = create(&raw mut context);
+            if res < 0 {
+                return Err(Error::Canberra(res));
+            }
+            // the sound server shows these, e.g. in the volume mixer
+            change_props(
+                context,
+                c"application.name".as_ptr(),
+                c"Global Push-to-Talk".as_ptr(),
+                c"application.icon_name".as_ptr(),
+                c"audio-input-microphone".as_ptr(),
+                ptr::null::<c_char>(),
+            );
+            Ok(Self {
+                context,
+                destroy,
+                play,
+                cancel,
+                _library: library,
+            })
+        }
+    }
+
+    fn play(&self, sound: Sound) -> Result<(), Error> {
+        // SAFETY: the context is valid until the player is dropped, and the properties are pairs
+        // of C strings ended by a null pointer
+        unsafe {
+            (self.cancel)(self.context, SOUND_ID);
+            for event_id in sound.event_ids() {
+                let res = (self.play)(
+                    self.context,
+                    SOUND_ID,
+                    c"event.id".as_ptr(),
+                    event_id.as_ptr(),
+                    c"event.description".as_ptr(),
+                    sound.description().as_ptr(),
+                    ptr::null::<c_char>(),
+                );
+                match res {
+                    NOT_FOUND => {}
+                    res if res < 0 => return Err(Error::Canberra(res)),
+                    _ => return Ok(()),
+                }
+            }
+        }
+        // the theme has none of them, which is fine
+        Ok(())
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        // SAFETY: the context came from ca_context_create and isn't used afterwards
+        unsafe {
+            (self.destroy)(self.context);
+        }
+    }
+}
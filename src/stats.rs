@@ -0,0 +1,95 @@
+use std::{collections::BTreeMap, io, path::PathBuf, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{Config, write_atomic},
+    events::{self, Level},
+    instance,
+};
+
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error("could not find the config directory")]
+    NoPath,
+    #[error("failed to serialize stats: {0}")]
+    Serialize(#[from] toml::ser::Error),
+    #[error("failed to write stats: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Talk time of a single day or session.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TalkTime {
+    pub seconds: u64,
+    pub activations: u32,
+}
+
+impl TalkTime {
+    pub fn add(&mut self, open_for: Duration) {
+        self.seconds += open_for.as_secs();
+        self.activations += 1;
+    }
+}
+
+// daily totals, keyed by the local date, e.g. "2025-01-31"
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct Stats {
+    #[serde(default)]
+    days: BTreeMap<String, TalkTime>,
+}
+
+// stats.toml (stats-<id>.toml for other instances), next to the config file
+fn path() -> Option<PathBuf> {
+    Config::path().map(|path| path.with_file_name(instance::file_name("stats.toml")))
+}
+
+fn today() -> String {
+    chrono::Local::now().date_naive().to_string()
+}
+
+fn load() -> Stats {
+    path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| toml::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Today's saved totals.
+pub fn load_today() -> TalkTime {
+    load().days.get(&today()).copied().unwrap_or_default()
+}
+
+/// Adds one activation to today's totals, returning them.
+pub fn record(open_for: Duration) -> TalkTime {
+    let mut stats = load();
+    let day = stats.days.entry(today()).or_default();
+    day.add(open_for);
+    let totals = *day;
+
+    if let Err(e) = save(&stats) {
+        events::record(Level::Error, format!("Failed to save stats: {e}"));
+    }
+    totals
+}
+
+fn save(stats: &Stats) -> Result<(), Error> {
+    let path = path().ok_or(Error::NoPath)?;
+    write_atomic(&path, &toml::to_string_pretty(stats)?)?;
+    Ok(())
+}
+
+/// Forgets every saved total.
+pub fn clear() {
+    let Some(path) = path() else {
+        return;
+    };
+    if let Err(e) = std::fs::remove_file(&path)
+        && e.kind() != io::ErrorKind::NotFound
+    {
+        events::record(
+            Level::Error,
+            format!("Failed to remove {}: {e}", path.display()),
+        );
+    }
+}
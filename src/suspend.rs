@@ -0,0 +1,90 @@
+use std::{pin::pin, time::Duration};
+
+use iced::{
+    futures::{SinkExt, Stream, StreamExt, channel::mpsc::Sender},
+    stream,
+};
+use tokio::sync::watch;
+use zbus::{Connection, Proxy, zvariant::OwnedFd};
+
+use crate::{
+    app::Msg,
+    events::{self, Level},
+    ipc::Snapshot,
+};
+
+const LOGIND: &str = "org.freedesktop.login1";
+const MANAGER_PATH: &str = "/org/freedesktop/login1";
+const MANAGER_INTERFACE: &str = "org.freedesktop.login1.Manager";
+// logind gives up on delay locks after InhibitDelayMaxSec, 5 seconds by default
+const MUTE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Mutes the microphone before the system suspends, holding off the suspend with a delay lock
+/// until it is, and reports when the system is back.
+pub fn watch(state: watch::Receiver<Snapshot>) -> impl Stream<Item = Msg> {
+    stream::channel(4, async move |mut tx| {
+        if let Err(e) = follow(&mut tx, state).await {
+            events::record(
+                Level::Warning,
+                format!("Suspend can't be prepared for through logind: {e}"),
+            );
+        }
+    })
+}
+
+async fn follow(tx: &mut Sender<Msg>, mut state: watch::Receiver<Snapshot>) -> zbus::Result<()> {
+    let bus = Connection::system().await?;
+    let manager = Proxy::new(&bus, LOGIND, MANAGER_PATH, MANAGER_INTERFACE).await?;
+    let mut signals = pin!(manager.receive_signal("PrepareForSleep").await?);
+
+    // closing it lets the suspend go ahead
+    let mut inhibitor = inhibit(&manager).await;
+    while let Some(signal) = signals.next().await {
+        let Ok(sleeping) = signal.body().deserialize::<bool>() else {
+            continue;
+        };
+        if !sleeping {
+            let _ = tx.send(Msg::Resumed).await;
+            // for the next time
+            inhibitor = inhibit(&manager).await;
+            continue;
+        }
+
+        let _ = tx.send(Msg::Suspending).await;
+        let muted = tokio::time::timeout(MUTE_TIMEOUT, state.wait_for(|state| state.muted))
+            .await
+            .is_ok_and(|res| res.is_ok());
+        if !muted {
+            events::record(
+                Level::Warning,
+                "The microphone couldn't be muted before suspending",
+            );
+        }
+        drop(inhibitor.take());
+    }
+    Ok(())
+}
+
+async fn inhibit(manager: &Proxy<'_>) -> Option<OwnedFd> {
+    let res = manager
+        .call(
+            "Inhibit",
+            &(
+                "sleep",
+                "Global Push-to-Talk",
+                "Muting the microphone",
+                "delay",
+            ),
+        )
+        .await;
+    match res {
+        Ok(fd) => Some(fd),
+        Err(e) => {
+            events::record(
+                Level::Warning,
+                format!("Failed to delay suspend until the microphone is muted: {e}"),
+            );
+            None
+        }
+    }
+}
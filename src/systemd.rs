@@ -0,0 +1,79 @@
+use std::{
+    env,
+    os::{
+        linux::net::SocketAddrExt,
+        unix::net::{SocketAddr, UnixDatagram},
+    },
+};
+
+use crate::{
+    events::{self, Level},
+    instance,
+};
+
+// set by systemd for units of Type=notify, and nothing is sent without it
+const NOTIFY_SOCKET: &str = "NOTIFY_SOCKET";
+
+/// Tells systemd that the app is up, once the audio server, the hotkeys and the control socket
+/// were set up, whether they worked or not.
+pub fn ready(status: &str) {
+    notify(&format!("READY=1\nSTATUS={status}"));
+}
+
+/// What `systemctl status` shows next to the unit.
+pub fn status(status: &str) {
+    notify(&format!("STATUS={status}"));
+}
+
+pub fn stopping() {
+    notify("STOPPING=1");
+}
+
+// sd_notify, without linking libsystemd
+fn notify(message: &str) {
+    let Some(path) = env::var_os(NOTIFY_SOCKET) else {
+        return;
+    };
+    let res = (|| {
+        // a leading @ stands for the abstract namespace
+        let addr = match path.as_encoded_bytes().strip_prefix(b"@") {
+            Some(name) => SocketAddr::from_abstract_name(name)?,
+            None => SocketAddr::from_pathname(&path)?,
+        };
+        let socket = UnixDatagram::unbound()?;
+        socket.send_to_addr(message.as_bytes(), &addr)?;
+        Ok::<_, std::io::Error>(())
+    })();
+    if let Err(e) = res {
+        events::record(Level::Warning, format!("Failed to notify systemd: {e}"));
+    }
+}
+
+/// A user unit that starts this binary in the tray with the graphical session, for
+/// `--print-systemd-unit`.
+pub fn unit() -> String {
+    let exe = env::current_exe().map_or_else(
+        |_| "/usr/bin/global-ptt".into(),
+        |exe| exe.display().to_string(),
+    );
+    let instance = instance::id().map_or_else(String::new, |id| format!(" --instance-id {id}"));
+    format!(
+        "\
+[Unit]
+Description=Global Push-to-Talk
+PartOf=graphical-session.target
+After=graphical-session.target pipewire-pulse.service pulseaudio.service
+
+[Service]
+Type=notify
+ExecStart={exe} --hidden{instance}
+Restart=on-failure
+RestartSec=2
+# SIGTERM removes the virtual microphone before exiting
+TimeoutStopSec=10
+
+[Install]
+WantedBy=graphical-session.target
+"
+    )
+}
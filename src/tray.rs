@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{fmt::Debug, sync::Arc, time::Duration};
 
 use iced::{
     futures::{
@@ -10,16 +10,47 @@ use iced::{
     stream,
 };
 use ksni::{
-    Category, MenuItem, Status, ToolTip,
-    menu::{CheckmarkItem, StandardItem},
+    Category, Handle, Icon, MenuItem, OfflineReason, Status, ToolTip, TrayMethods,
+    menu::{CheckmarkItem, RadioGroup, RadioItem, StandardItem, SubMenu},
 };
 
-use crate::app::Msg;
+use crate::{
+    activity::Cause,
+    app::Msg,
+    events::{self, Level},
+    i18n::tr,
+    instance,
+};
+
+// how often to try again when no tray host could be reached at startup
+const SPAWN_RETRY_INTERVAL: Duration = Duration::from_secs(10);
 
-#[derive(Debug)]
+// a copy of what the menu shows, which is mostly flags
+#[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Tray {
     msg_sender: Arc<Mutex<Sender<Msg>>>,
     ptt_enabled: bool,
+    muted: bool,
+    // whether the mic is muted while push-to-talk is disabled
+    resting_muted: bool,
+    profiles: Vec<String>,
+    active_profile: usize,
+    // description of the selected mic
+    microphone: Option<String>,
+    trigger_hotkey: String,
+    // shown next to the enable item, empty when the binding isn't known
+    toggle_shortcut: Vec<Vec<String>>,
+    // set when audio or hotkeys stopped working
+    error: bool,
+    // set while push-to-talk can't work at all
+    backend_error: Option<String>,
+    // skip the icon theme and only offer the drawn icons
+    builtin_icons: bool,
+    // how long the mic has been open, e.g. "2:05"
+    open_for: Option<String>,
+    // set when the user picked their own colors, which the icon theme can't show
+    colors: Option<IconColors>,
 }
 
 impl Tray {
@@ -35,23 +66,119 @@ impl Tray {
             Self {
                 msg_sender: Arc::new(Mutex::new(msg_sender)),
                 ptt_enabled: false,
+                muted: true,
+                resting_muted: false,
+                profiles: Vec::new(),
+                active_profile: 0,
+                microphone: None,
+                trigger_hotkey: String::new(),
+                toggle_shortcut: Vec::new(),
+                error: false,
+                backend_error: None,
+                builtin_icons: false,
+                open_for: None,
+                colors: None,
             },
             stream,
         )
     }
 
+    /// Keeps trying to spawn the tray until a tray host shows up.
+    pub async fn spawn_retrying(self) -> TrayHandle {
+        loop {
+            tokio::time::sleep(SPAWN_RETRY_INTERVAL).await;
+            if let Ok(handle) = self.clone().spawn().await {
+                return TrayHandle(handle);
+            }
+        }
+    }
+
     pub fn set_ptt_enabled(&mut self, enabled: bool) {
         self.ptt_enabled = enabled;
     }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    pub fn set_resting_muted(&mut self, muted: bool) {
+        self.resting_muted = muted;
+    }
+
+    pub fn set_info(
+        &mut self,
+        microphone: Option<String>,
+        trigger_hotkey: String,
+        toggle_shortcut: Vec<Vec<String>>,
+    ) {
+        self.microphone = microphone;
+        self.trigger_hotkey = trigger_hotkey;
+        self.toggle_shortcut = toggle_shortcut;
+    }
+
+    pub fn set_error(&mut self, error: bool) {
+        self.error = error;
+    }
+
+    pub fn set_backend_error(&mut self, error: Option<String>) {
+        self.backend_error = error;
+    }
+
+    pub fn set_open_for(&mut self, open_for: Option<String>) {
+        self.open_for = open_for;
+    }
+
+    pub fn set_colors(&mut self, colors: Option<IconColors>) {
+        self.colors = colors;
+    }
+
+    pub fn set_builtin_icons(&mut self, builtin: bool) {
+        self.builtin_icons = builtin;
+    }
+
+    pub fn set_profiles(&mut self, profiles: Vec<String>, active: &str) {
+        self.active_profile = profiles
+            .iter()
+            .position(|p| p == active)
+            .unwrap_or_default();
+        self.profiles = profiles;
+    }
 }
 
 impl ksni::Tray for Tray {
     fn id(&self) -> String {
-        env!("CARGO_PKG_NAME").into()
+        instance::suffixed(env!("CARGO_PKG_NAME"), "-")
     }
 
     fn icon_name(&self) -> String {
-        "microphone".into()
+        // hosts prefer the themed icon when there is one
+        if self.builtin_icons || self.colors.is_some() {
+            String::new()
+        } else if self.ptt_enabled && !self.muted {
+            // stands out while the mic is live
+            "microphone-sensitivity-high".into()
+        } else if self.ptt_enabled {
+            "microphone-sensitivity-muted".into()
+        } else {
+            "microphone".into()
+        }
+    }
+
+    // fallback for when the icon theme has no microphone icon
+    fn icon_pixmap(&self) -> Vec<Icon> {
+        let state = if self.error || self.backend_error.is_some() {
+            IconState::Error
+        } else if !self.ptt_enabled {
+            IconState::Normal
+        } else if self.muted {
+            IconState::Muted
+        } else {
+            IconState::Transmitting
+        };
+        PIXMAP_SIZES
+            .iter()
+            .map(|size| draw_icon(*size, state, self.colors.unwrap_or_default()))
+            .collect()
     }
 
     fn title(&self) -> String {
@@ -62,8 +189,27 @@ impl ksni::Tray for Tray {
         Category::ApplicationStatus
     }
 
+    // keep the service running when the panel goes away, so that ksni registers the item again
+    // as soon as a new StatusNotifierWatcher appears. the state lives in `Tray`, so nothing needs
+    // to be replayed.
+    fn watcher_offline(&self, reason: OfflineReason) -> bool {
+        events::record(Level::Warning, format!("Tray host went away: {reason:?}"));
+        true
+    }
+
+    fn attention_icon_name(&self) -> String {
+        "microphone-disabled-symbolic".into()
+    }
+
     fn status(&self) -> Status {
-        Status::Active
+        // passive items can be hidden by the desktop while push-to-talk is off
+        if self.error || self.backend_error.is_some() {
+            Status::NeedsAttention
+        } else if self.ptt_enabled {
+            Status::Active
+        } else {
+            Status::Passive
+        }
     }
 
     fn activate(&mut self, _: i32, _: i32) {
@@ -74,39 +220,240 @@ impl ksni::Tray for Tray {
 
     fn menu(&self) -> Vec<MenuItem<Self>> {
         let sender = self.msg_sender.clone();
+        let tx = sender.clone();
+        let exit = MenuItem::Standard(StandardItem {
+            label: tr!("exit"),
+            activate: Box::new(move |_| {
+                let _ = block_on(tx.lock()).send(Msg::Exit).now_or_never();
+            }),
+            ..Default::default()
+        });
+
+        let tx = sender.clone();
+        let about = MenuItem::Standard(StandardItem {
+            label: tr!("page-about"),
+            activate: Box::new(move |_| {
+                let _ = block_on(tx.lock()).send(Msg::ShowAbout).now_or_never();
+            }),
+            ..Default::default()
+        });
+
+        if self.backend_error.is_some() {
+            let unavailable = MenuItem::Standard(StandardItem {
+                label: tr!("tray-unavailable"),
+                enabled: false,
+                ..Default::default()
+            });
+            let tx = sender.clone();
+            let retry = MenuItem::Standard(StandardItem {
+                label: tr!("retry"),
+                activate: Box::new(move |_| {
+                    let _ = block_on(tx.lock()).send(Msg::RetryBackend).now_or_never();
+                }),
+                ..Default::default()
+            });
+            return vec![unavailable, retry, about, exit];
+        }
+
         let tx = sender.clone();
         let toggle_ptt = MenuItem::Checkmark(CheckmarkItem {
-            label: "Enable Push-to-Talk".into(),
+            label: tr!("tray-enable"),
             checked: self.ptt_enabled,
+            shortcut: self.toggle_shortcut.clone(),
             activate: Box::new(move |_| {
-                let _ = block_on(tx.lock()).send(Msg::ToggleActive).now_or_never();
+                let _ = block_on(tx.lock())
+                    .send(Msg::ToggleActive(Cause::Tray))
+                    .now_or_never();
             }),
             ..Default::default()
         });
         let tx = sender.clone();
-        let exit = MenuItem::Standard(StandardItem {
-            label: "Exit".into(),
+        let toggle_mute = MenuItem::Checkmark(CheckmarkItem {
+            label: tr!("tray-mic-open"),
+            // the mic can only be latched while push-to-talk is in control of it
+            enabled: self.ptt_enabled,
+            checked: !self.muted,
             activate: Box::new(move |_| {
-                let _ = block_on(tx.lock()).send(Msg::Exit).now_or_never();
+                let _ = block_on(tx.lock())
+                    .send(Msg::ToggleMute(Cause::Tray))
+                    .now_or_never();
             }),
             ..Default::default()
         });
-        vec![toggle_ptt, exit]
+        let profiles = MenuItem::SubMenu(SubMenu {
+            label: tr!("profile"),
+            submenu: vec![MenuItem::RadioGroup(RadioGroup {
+                selected: self.active_profile,
+                select: Box::new(|tray: &mut Self, i| {
+                    if let Some(name) = tray.profiles.get(i) {
+                        let _ = block_on(tray.msg_sender.lock())
+                            .send(Msg::SwitchProfile(name.clone()))
+                            .now_or_never();
+                    }
+                }),
+                options: self
+                    .profiles
+                    .iter()
+                    .map(|name| RadioItem {
+                        // a single underscore would mark an access key
+                        label: name.replace('_', "__"),
+                        ..Default::default()
+                    })
+                    .collect(),
+            })],
+            ..Default::default()
+        });
+        let trigger_hint = MenuItem::Standard(StandardItem {
+            label: if self.trigger_hotkey.is_empty() {
+                tr!("tray-no-trigger")
+            } else {
+                tr!("tray-hold-to-talk", hotkey = self.trigger_hotkey.as_str()).replace('_', "__")
+            },
+            enabled: false,
+            ..Default::default()
+        });
+        vec![toggle_ptt, toggle_mute, trigger_hint, profiles, about, exit]
     }
 
     fn tool_tip(&self) -> ToolTip {
-        if self.ptt_enabled {
-            ToolTip {
-                title: "Global Push-to-Talk".into(),
-                description: "Running".into(),
-                ..Default::default()
-            }
-        } else {
-            ToolTip {
-                title: "Global Push-to-Talk".into(),
-                description: "Not Running".into(),
+        if let Some(error) = &self.backend_error {
+            return ToolTip {
+                icon_name: "microphone-disabled-symbolic".into(),
+                title: tr!("tray-state", state = "error"),
+                description: error.clone(),
                 ..Default::default()
+            };
+        }
+
+        let state = match (self.ptt_enabled, self.muted) {
+            (true, false) => "transmitting",
+            (true, true) => "muted",
+            (false, _) => "disabled",
+        };
+
+        let mic = match &self.microphone {
+            Some(mic) => tr!("tray-mic", mic = mic.as_str()),
+            None => tr!("tray-no-mic"),
+        };
+        let usage = match (self.ptt_enabled, self.resting_muted) {
+            (false, true) => tr!("tray-muted-while-disabled"),
+            (false, false) => tr!("tray-open-while-disabled"),
+            (true, _) if self.trigger_hotkey.is_empty() => tr!("tray-no-trigger"),
+            (true, _) => tr!("tray-hold-to-talk", hotkey = self.trigger_hotkey.as_str()),
+        };
+        let description = match &self.open_for {
+            Some(time) => format!(
+                "{} • {mic} • {usage}",
+                tr!("tray-open-for", time = time.as_str())
+            ),
+            None => format!("{mic} • {usage}"),
+        };
+
+        ToolTip {
+            icon_name: if self.muted {
+                "microphone-sensitivity-muted".into()
+            } else {
+                "microphone-sensitivity-high".into()
+            },
+            title: tr!("tray-state", state = state),
+            description,
+            ..Default::default()
+        }
+    }
+}
+
+// a spawned tray, passed around in messages
+#[derive(Clone)]
+pub struct TrayHandle(pub Handle<Tray>);
+
+impl Debug for TrayHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TrayHandle")
+    }
+}
+
+const PIXMAP_SIZES: [i32; 2] = [22, 48];
+
+#[derive(Debug, Clone, Copy)]
+pub enum IconState {
+    Normal,
+    Muted,
+    Transmitting,
+    Error,
+}
+
+/// Colors of the drawn microphone in each state but errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IconColors {
+    pub muted: [u8; 3],
+    pub transmitting: [u8; 3],
+    pub disabled: [u8; 3],
+}
+
+impl Default for IconColors {
+    fn default() -> Self {
+        Self {
+            muted: [0xbd, 0xbd, 0xbd],
+            transmitting: [0x4c, 0xaf, 0x50],
+            disabled: [0xbd, 0xbd, 0xbd],
+        }
+    }
+}
+
+// draws a microphone, crossed out unless it's open, as ARGB32 in network byte order. the slash
+// tells the states apart without relying on color
+pub fn draw_icon(size: i32, state: IconState, colors: IconColors) -> Icon {
+    let (rgb, slashed) = match state {
+        IconState::Normal => (colors.disabled, false),
+        IconState::Muted => (colors.muted, true),
+        IconState::Transmitting => (colors.transmitting, false),
+        IconState::Error => ([0xe5, 0x39, 0x35], true),
+    };
+
+    let scale = f64::from(size);
+    let mut data = Vec::with_capacity(usize::try_from(size * size * 4).unwrap_or_default());
+    for y in 0..size {
+        for x in 0..size {
+            // sample the centre of the pixel, in units of the icon's size
+            let point = ((f64::from(x) + 0.5) / scale, (f64::from(y) + 0.5) / scale);
+
+            // capsule, the holder around its lower half, stem and base
+            let holder = if point.1 > 0.45 {
+                (distance(point, (0.5, 0.45)) - 0.28).abs() - 0.04
+            } else {
+                f64::MAX
+            };
+            let mut dist = (segment_distance(point, (0.5, 0.2), (0.5, 0.45)) - 0.14)
+                .min(holder)
+                .min(segment_distance(point, (0.5, 0.73), (0.5, 0.88)) - 0.04)
+                .min(segment_distance(point, (0.32, 0.88), (0.68, 0.88)) - 0.04);
+            if slashed {
+                dist = dist.min(segment_distance(point, (0.15, 0.1), (0.85, 0.9)) - 0.04);
             }
+
+            // anti-aliased coverage of the pixel
+            let alpha = (0.5 - dist * scale).clamp(0.0, 1.0);
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let a = (alpha * 255.0).round() as u8;
+            data.extend_from_slice(&[a, rgb[0], rgb[1], rgb[2]]);
         }
     }
+
+    Icon {
+        width: size,
+        height: size,
+        data,
+    }
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+// distance from `p` to the line segment between `a` and `b`
+fn segment_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let ab = (b.0 - a.0, b.1 - a.1);
+    let t =
+        (((p.0 - a.0) * ab.0 + (p.1 - a.1) * ab.1) / (ab.0 * ab.0 + ab.1 * ab.1)).clamp(0.0, 1.0);
+    distance(p, (a.0 + ab.0 * t, a.1 + ab.1 * t))
 }
@@ -0,0 +1,58 @@
+use std::{
+    io,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use evdev::{Device, EventType, KeyCode};
+use iced::{
+    futures::{SinkExt, Stream, channel::mpsc::Sender},
+    stream,
+};
+
+use crate::{app::Msg, input};
+
+// typing fast sends a key every few milliseconds, the app only needs to hear about some of them
+const THROTTLE: Duration = Duration::from_millis(50);
+// the first button code, everything below it is a key
+const BTN_MISC: u16 = 0x100;
+
+// pressed along with other keys, so they're quiet enough and part of most hotkeys
+const MODIFIERS: [KeyCode; 8] = [
+    KeyCode::KEY_LEFTCTRL,
+    KeyCode::KEY_RIGHTCTRL,
+    KeyCode::KEY_LEFTSHIFT,
+    KeyCode::KEY_RIGHTSHIFT,
+    KeyCode::KEY_LEFTALT,
+    KeyCode::KEY_RIGHTALT,
+    KeyCode::KEY_LEFTMETA,
+    KeyCode::KEY_RIGHTMETA,
+];
+
+/// Sends `Msg::Typing` whenever a key other than a modifier is pressed on any keyboard.
+/// Keyboards plugged in later are picked up.
+pub fn watch() -> impl Stream<Item = Msg> {
+    stream::channel(10, async move |tx| {
+        input::each_keyboard("typing", |path| follow(path, tx.clone())).await;
+    })
+}
+
+async fn follow(path: PathBuf, mut tx: Sender<Msg>) -> io::Result<()> {
+    let mut events = Device::open(path)?.into_event_stream()?;
+    let mut last_sent: Option<Instant> = None;
+    loop {
+        let event = events.next_event().await?;
+        // only presses, held keys repeating aren't heard. mouse buttons are keys as well
+        let pressed = event.event_type() == EventType::KEY
+            && event.value() == 1
+            && event.code() < BTN_MISC
+            && !MODIFIERS.iter().any(|key| key.code() == event.code());
+        if !pressed || last_sent.is_some_and(|at| at.elapsed() < THROTTLE) {
+            continue;
+        }
+        if tx.send(Msg::Typing).await.is_err() {
+            return Ok(());
+        }
+        last_sent = Some(Instant::now());
+    }
+}
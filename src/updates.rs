@@ -0,0 +1,100 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    config::Config,
+    events::{self, Level},
+};
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/Adamskye/global-ptt/releases/latest";
+// GitHub is asked at most this often
+const CHECK_INTERVAL: Duration = Duration::from_hours(24);
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A release newer than the running version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub version: String,
+    pub url: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubRelease {
+    tag_name: String,
+    html_url: String,
+}
+
+// when GitHub was last asked, next to the config file
+fn path() -> Option<PathBuf> {
+    Config::path().map(|path| path.with_file_name("last-update-check"))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn is_due() -> bool {
+    let last = path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| contents.trim().parse::<u64>().ok());
+    last.is_none_or(|last| now().saturating_sub(last) >= CHECK_INTERVAL.as_secs())
+}
+
+/// Looks for a newer release, unless that was already done in the last day. Failures are only
+/// recorded as events, nobody needs to hear about being offline.
+pub async fn check() -> Option<Release> {
+    if !is_due() {
+        return None;
+    }
+    if let Some(path) = path() {
+        let _ = std::fs::write(path, now().to_string());
+    }
+
+    let latest = tokio::task::spawn_blocking(fetch_latest).await.ok()?;
+    let latest = match latest {
+        Ok(latest) => latest,
+        Err(e) => {
+            events::record(Level::Info, format!("Update check failed: {e}"));
+            return None;
+        }
+    };
+
+    let version = latest.tag_name.trim_start_matches('v').to_string();
+    is_newer(&version, env!("CARGO_PKG_VERSION")).then_some(Release {
+        version,
+        url: latest.html_url,
+    })
+}
+
+fn fetch_latest() -> anyhow::Result<GitHubRelease> {
+    Ok(ureq::get(LATEST_RELEASE_URL)
+        .timeout(TIMEOUT)
+        // GitHub turns away requests without one
+        .set(
+            "User-Agent",
+            concat!("global-ptt/", env!("CARGO_PKG_VERSION")),
+        )
+        .set("Accept", "application/vnd.github+json")
+        .call()?
+        .into_json()?)
+}
+
+// compares dotted version numbers, ignoring anything after a "-", e.g. "1.2.0-rc1"
+fn is_newer(version: &str, current: &str) -> bool {
+    let parse = |v: &str| -> Vec<u64> {
+        v.split('-')
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or_default())
+            .collect()
+    };
+    parse(version) > parse(current)
+}